@@ -1,3 +1,28 @@
 fn main() {
-    tauri_build::build()
+    // Listing the app's commands makes them subject to capabilities, so each
+    // window only gets the ones its capability file allows.
+    tauri_build::try_build(tauri_build::Attributes::new().app_manifest(
+        tauri_build::AppManifest::new().commands(&[
+            "is_focused",
+            "request_focus",
+            "relinquish_focus",
+            "download",
+            "save_file",
+            "get_settings",
+            "set_settings",
+            "export_local_ca",
+            "open_approval_window",
+            "get_approval_request",
+            "answer_approval",
+            "show_wallet",
+            "get_presence",
+            "report_activity",
+            "lock_wallet",
+            "is_locked",
+            "get_launch_at_login",
+            "set_launch_at_login",
+            "take_deep_links",
+        ]),
+    ))
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,76 @@
+//! Small windows asking the user to approve a single request.
+//!
+//! Rather than raising the whole wallet window for every permission prompt,
+//! the frontend asks for an approval window tied to the ID of the request
+//! that raised the prompt, while the wallet window is in the background. The
+//! window loads the frontend with that ID in its URL, shows a [`Prompt`] for
+//! the request, and is closed by the desktop binary as soon as the request
+//! is no longer pending, whether it was answered, cancelled, or its client
+//! gave up.
+//!
+//! The user's [`Answer`] is sent to the main window, which grants or denies
+//! the wallet's prompt. A denial is also answered to the app right away, with
+//! `ERR_USER_DENIED`; closing the window counts as one.
+
+use serde::Serialize;
+
+use crate::bridge::IncomingRequest;
+use crate::notifications;
+
+/// Label prefix of approval windows, followed by the request ID.
+pub const LABEL_PREFIX: &str = "approval-";
+
+/// Logical size of an approval window.
+pub const WIDTH: f64 = 440.0;
+pub const HEIGHT: f64 = 600.0;
+
+/// Event sent to the main window with the user's [`Answer`].
+pub const EVENT_ANSWERED: &str = "approval-answered";
+
+/// The label of the approval window for `request_id`.
+pub fn label(request_id: u64) -> String {
+    format!("{}{}", LABEL_PREFIX, request_id)
+}
+
+/// The request an approval window is for, from its label.
+pub fn request_id(label: &str) -> Option<u64> {
+    label.strip_prefix(LABEL_PREFIX)?.parse().ok()
+}
+
+/// The frontend page an approval window loads.
+pub fn url(request_id: u64) -> String {
+    format!("index.html?approval={}", request_id)
+}
+
+/// What an approval window shows about its request.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Prompt {
+    pub request_id: u64,
+    pub origin: Option<String>,
+    pub method: &'static str,
+    /// Who is asking, e.g. "Request from example.com".
+    pub summary: String,
+    /// What they ask the wallet to do.
+    pub description: String,
+}
+
+/// How the user answered an approval window.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Answer {
+    pub request_id: u64,
+    pub approved: bool,
+}
+
+impl From<&IncomingRequest> for Prompt {
+    fn from(request: &IncomingRequest) -> Self {
+        Self {
+            request_id: request.request_id,
+            origin: request.origin.clone(),
+            method: request.method.name(),
+            summary: notifications::summary(request),
+            description: notifications::body(request),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Launching the app at login through an XDG autostart entry.
+//!
+//! Desktop environments following the XDG autostart spec start every
+//! `.desktop` file in `$XDG_CONFIG_HOME/autostart` when the user logs in.
+//! The entry written here is the app's own desktop entry, pointed at the
+//! running executable and started with [`MINIMIZED_ARG`] so the wallet
+//! comes up in the tray without opening its window.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Argument that starts the app with its window hidden.
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+/// The desktop entry shipped with the app, used as the template.
+const DESKTOP_ENTRY: &str = include_str!("../metanet-desktop.desktop");
+
+/// File name of the autostart entry.
+const ENTRY_FILE: &str = "metanet-desktop.desktop";
+
+/// Where the autostart entry lives, given the user's config dir
+/// (`$XDG_CONFIG_HOME`, usually `~/.config`).
+pub fn entry_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("autostart").join(ENTRY_FILE)
+}
+
+/// Whether the app is set to start at login.
+pub fn is_enabled(config_dir: &Path) -> bool {
+    entry_path(config_dir).is_file()
+}
+
+/// The program to start at login: the AppImage when running from one,
+/// since the executable inside it is unpacked to a new place every run.
+pub fn launcher() -> io::Result<PathBuf> {
+    match std::env::var_os("APPIMAGE") {
+        Some(appimage) => Ok(PathBuf::from(appimage)),
+        None => std::env::current_exe(),
+    }
+}
+
+/// The autostart entry launching `exe` minimized. Scheme handler
+/// registrations are left to the installed desktop entry.
+pub fn entry(exe: &Path) -> String {
+    let mut entry = String::new();
+    for line in DESKTOP_ENTRY.lines() {
+        if line.starts_with("MimeType=") {
+            continue;
+        }
+        if line.starts_with("Exec=") {
+            entry.push_str(&format!("Exec={} {}", exec_arg(exe), MINIMIZED_ARG));
+        } else {
+            entry.push_str(line);
+        }
+        entry.push('\n');
+    }
+    entry.push_str("X-GNOME-Autostart-enabled=true\n");
+    entry
+}
+
+/// Write the autostart entry for `exe`, replacing any earlier one.
+pub fn enable(config_dir: &Path, exe: &Path) -> io::Result<()> {
+    let path = entry_path(config_dir);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, entry(exe))
+}
+
+/// Remove the autostart entry, if there is one.
+pub fn disable(config_dir: &Path) -> io::Result<()> {
+    match fs::remove_file(entry_path(config_dir)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// `path` as an argument of an `Exec` key, quoted as the desktop entry spec
+/// requires when it contains reserved characters.
+fn exec_arg(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('%', "%%");
+    let reserved = |c: char| c.is_whitespace() || "\"'\\><~|&;$*?#()`".contains(c);
+    if !path.contains(reserved) {
+        return path;
+    }
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    // Backslashes are escaped once more when the value is read as a string.
+    quoted.replace('\\', "\\\\")
+}
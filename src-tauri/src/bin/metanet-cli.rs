@@ -0,0 +1,1011 @@
+//! Command-line client for the wallet's JSON API.
+//!
+//! Every BRC-100 method is a subcommand, e.g.
+//! `metanet-cli get-public-key --protocol "2:message signing" --key-id 1`.
+//! Arguments are checked against the same schema the wallet bridge uses
+//! before anything is sent. Results are pretty-printed to stdout.
+//!
+//! Exit codes: 0 on success, 1 when the wallet returned an error, 2 for
+//! invalid arguments and 3 when the wallet could not be reached.
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{Args, Parser, Subcommand};
+use metanet_desktop::{
+    brc100::*,
+    client::{BridgeClient, ClientResponse},
+    idempotency::IDEMPOTENCY_KEY_HEADER,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+/// The originator used until the CLI is paired under another name.
+const DEFAULT_ORIGIN: &str = "metanet-cli";
+
+const EXIT_WALLET_ERROR: u8 = 1;
+const EXIT_INVALID_ARGS: u8 = 2;
+const EXIT_UNREACHABLE: u8 = 3;
+
+const BYTES_HELP: &str = "Byte arguments: text is sent as UTF-8, `hex:<digits>` as the decoded \
+bytes and `@<path>` as the contents of a file.";
+
+#[derive(Parser)]
+#[command(
+    name = "metanet-cli",
+    version,
+    about = "Call a running Metanet Desktop wallet"
+)]
+struct Cli {
+    /// Name the wallet knows this client by. Defaults to the paired origin.
+    #[arg(long, global = true, env = "METANET_ORIGIN")]
+    origin: Option<String>,
+    /// Only use the TCP listener at this address, not the Unix socket.
+    #[arg(long, global = true, value_name = "HOST:PORT")]
+    addr: Option<String>,
+    /// Send an `Idempotency-Key`, so a retried spending call is not run twice.
+    #[arg(long, global = true, value_name = "KEY")]
+    idempotency_key: Option<String>,
+    /// Print results on one line.
+    #[arg(long, global = true)]
+    compact: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Wait until the wallet is unlocked, ask it to approve this client and
+    /// remember the origin for later calls.
+    Pair,
+    /// Call a method by name with a raw JSON arguments object (`-` reads
+    /// it from stdin).
+    Call {
+        method: String,
+        args: Option<String>,
+    },
+    /// Create a new transaction.
+    CreateAction(CreateAction),
+    /// Sign a transaction created without `signAndProcess`.
+    SignAction(SignAction),
+    /// Abort a transaction that is still being built.
+    AbortAction(AbortAction),
+    /// List transactions by label.
+    ListActions(ListActions),
+    /// Take in outputs of a transaction paid to the wallet.
+    InternalizeAction(InternalizeAction),
+    /// List the outputs in a basket.
+    ListOutputs(ListOutputs),
+    /// Stop tracking an output.
+    RelinquishOutput(RelinquishOutput),
+    /// Get the identity key or a derived public key.
+    GetPublicKey(GetPublicKey),
+    /// Reveal the key linkage with a counterparty to a verifier.
+    RevealCounterpartyKeyLinkage(RevealCounterpartyKeyLinkage),
+    /// Reveal the linkage of one derived key to a verifier.
+    RevealSpecificKeyLinkage(RevealSpecificKeyLinkage),
+    /// Encrypt data with a derived key.
+    Encrypt(Encrypt),
+    /// Decrypt data with a derived key.
+    Decrypt(Decrypt),
+    /// Create an HMAC with a derived key.
+    CreateHmac(CreateHmac),
+    /// Check an HMAC made with a derived key.
+    VerifyHmac(VerifyHmac),
+    /// Sign data with a derived key.
+    CreateSignature(CreateSignature),
+    /// Check a signature made with a derived key.
+    VerifySignature(VerifySignature),
+    /// Acquire an identity certificate.
+    AcquireCertificate(AcquireCertificate),
+    /// List certificates by certifier and type.
+    ListCertificates(ListCertificates),
+    /// Reveal certificate fields to a verifier.
+    ProveCertificate(ProveCertificate),
+    /// Forget a certificate.
+    RelinquishCertificate(RelinquishCertificate),
+    /// Find certificates issued to an identity key.
+    DiscoverByIdentityKey(DiscoverByIdentityKey),
+    /// Find certificates by their field values.
+    DiscoverByAttributes(DiscoverByAttributes),
+    /// Whether the wallet is unlocked.
+    IsAuthenticated,
+    /// Wait until the wallet is unlocked.
+    WaitForAuthentication,
+    /// The current chain height.
+    GetHeight,
+    /// The block header at a height.
+    GetHeaderForHeight(GetHeaderForHeight),
+    /// Mainnet or testnet.
+    GetNetwork,
+    /// The wallet's version.
+    GetVersion,
+}
+
+// Flags shared by several methods.
+
+#[derive(Args)]
+struct Privilege {
+    /// Use the privileged keyring.
+    #[arg(long)]
+    privileged: bool,
+    /// Why privileged access is needed, shown to the user.
+    #[arg(long, requires = "privileged")]
+    privileged_reason: Option<String>,
+}
+
+#[derive(Args)]
+struct Page {
+    #[arg(long)]
+    limit: Option<u32>,
+    #[arg(long)]
+    offset: Option<u32>,
+}
+
+#[derive(Args)]
+struct SeekPermission {
+    /// Whether the wallet may prompt for permission (default true).
+    #[arg(long, value_name = "BOOL")]
+    seek_permission: Option<bool>,
+}
+
+// The key a cryptographic operation uses.
+#[derive(Args)]
+struct Key {
+    /// Security level and protocol name, as `LEVEL:NAME`.
+    #[arg(long, value_parser = protocol)]
+    protocol: WalletProtocol,
+    #[arg(long)]
+    key_id: String,
+    /// `self`, `anyone` or a public key.
+    #[arg(long)]
+    counterparty: Option<String>,
+    #[command(flatten)]
+    privilege: Privilege,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+impl Key {
+    fn into_args(self) -> WalletEncryptionArgs {
+        WalletEncryptionArgs {
+            protocol_id: self.protocol,
+            key_id: self.key_id,
+            counterparty: self.counterparty,
+            privileged: flag(self.privilege.privileged),
+            privileged_reason: self.privilege.privileged_reason,
+            seek_permission: self.seek.seek_permission,
+        }
+    }
+}
+
+// Actions
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct CreateAction {
+    #[arg(long)]
+    description: String,
+    /// BEEF for the inputs.
+    #[arg(long, value_parser = bytes)]
+    input_beef: Option<Bytes>,
+    /// An input as a JSON object; repeat for more.
+    #[arg(long = "input", value_parser = json::<CreateActionInput>)]
+    inputs: Vec<CreateActionInput>,
+    /// An output as a JSON object; repeat for more.
+    #[arg(long = "output", value_parser = json::<CreateActionOutput>)]
+    outputs: Vec<CreateActionOutput>,
+    #[arg(long)]
+    lock_time: Option<u32>,
+    #[arg(long)]
+    version: Option<u32>,
+    #[arg(long = "label")]
+    labels: Vec<String>,
+    /// Options as a JSON object.
+    #[arg(long, value_parser = json::<CreateActionOptions>)]
+    options: Option<CreateActionOptions>,
+}
+
+#[derive(Args)]
+struct SignAction {
+    #[arg(long)]
+    reference: String,
+    /// Unlocking scripts by input index, as a JSON object.
+    #[arg(long, value_parser = json::<BTreeMap<u32, SignActionSpend>>)]
+    spends: BTreeMap<u32, SignActionSpend>,
+    /// Options as a JSON object.
+    #[arg(long, value_parser = json::<SignActionOptions>)]
+    options: Option<SignActionOptions>,
+}
+
+#[derive(Args)]
+struct AbortAction {
+    #[arg(long)]
+    reference: String,
+}
+
+#[derive(Args)]
+struct ListActions {
+    #[arg(long = "label", required = true)]
+    labels: Vec<String>,
+    /// `any` or `all`.
+    #[arg(long, value_parser = json_string::<QueryMode>)]
+    label_query_mode: Option<QueryMode>,
+    #[arg(long)]
+    include_labels: bool,
+    #[arg(long)]
+    include_inputs: bool,
+    #[arg(long)]
+    include_input_source_locking_scripts: bool,
+    #[arg(long)]
+    include_input_unlocking_scripts: bool,
+    #[arg(long)]
+    include_outputs: bool,
+    #[arg(long)]
+    include_output_locking_scripts: bool,
+    #[command(flatten)]
+    page: Page,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct InternalizeAction {
+    /// The transaction as AtomicBEEF.
+    #[arg(long, value_parser = bytes)]
+    tx: Bytes,
+    /// An output to take in, as a JSON object; repeat for more.
+    #[arg(long = "output", value_parser = json::<InternalizeOutput>, required = true)]
+    outputs: Vec<InternalizeOutput>,
+    #[arg(long)]
+    description: String,
+    #[arg(long = "label")]
+    labels: Vec<String>,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+// Outputs
+
+#[derive(Args)]
+struct ListOutputs {
+    #[arg(long)]
+    basket: String,
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+    /// `any` or `all`.
+    #[arg(long, value_parser = json_string::<QueryMode>)]
+    tag_query_mode: Option<QueryMode>,
+    /// `locking scripts` or `entire transactions`.
+    #[arg(long, value_parser = json_string::<OutputInclude>)]
+    include: Option<OutputInclude>,
+    #[arg(long)]
+    include_custom_instructions: bool,
+    #[arg(long)]
+    include_tags: bool,
+    #[arg(long)]
+    include_labels: bool,
+    #[command(flatten)]
+    page: Page,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+#[derive(Args)]
+struct RelinquishOutput {
+    #[arg(long)]
+    basket: String,
+    /// The outpoint, as `txid.vout`.
+    #[arg(long)]
+    output: String,
+}
+
+// Keys
+
+#[derive(Args)]
+struct GetPublicKey {
+    /// Return the wallet's identity key.
+    #[arg(long, conflicts_with_all = ["protocol", "key_id"])]
+    identity_key: bool,
+    /// Security level and protocol name, as `LEVEL:NAME`.
+    #[arg(long, value_parser = protocol, requires = "key_id")]
+    protocol: Option<WalletProtocol>,
+    #[arg(long, requires = "protocol")]
+    key_id: Option<String>,
+    /// `self`, `anyone` or a public key.
+    #[arg(long)]
+    counterparty: Option<String>,
+    #[arg(long)]
+    for_self: bool,
+    #[command(flatten)]
+    privilege: Privilege,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+#[derive(Args)]
+struct RevealCounterpartyKeyLinkage {
+    #[arg(long)]
+    counterparty: String,
+    #[arg(long)]
+    verifier: String,
+    #[command(flatten)]
+    privilege: Privilege,
+}
+
+#[derive(Args)]
+struct RevealSpecificKeyLinkage {
+    #[arg(long)]
+    counterparty: String,
+    #[arg(long)]
+    verifier: String,
+    /// Security level and protocol name, as `LEVEL:NAME`.
+    #[arg(long, value_parser = protocol)]
+    protocol: WalletProtocol,
+    #[arg(long)]
+    key_id: String,
+    #[command(flatten)]
+    privilege: Privilege,
+}
+
+// Cryptography
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct Encrypt {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes)]
+    plaintext: Bytes,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct Decrypt {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes)]
+    ciphertext: Bytes,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct CreateHmac {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes)]
+    data: Bytes,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct VerifyHmac {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes)]
+    data: Bytes,
+    #[arg(long, value_parser = bytes)]
+    hmac: Bytes,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct CreateSignature {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes, required_unless_present = "hash_to_directly_sign")]
+    data: Option<Bytes>,
+    #[arg(long, value_parser = bytes, conflicts_with = "data")]
+    hash_to_directly_sign: Option<Bytes>,
+}
+
+#[derive(Args)]
+#[command(after_help = BYTES_HELP)]
+struct VerifySignature {
+    #[command(flatten)]
+    key: Key,
+    #[arg(long, value_parser = bytes, required_unless_present = "hash_to_directly_verify")]
+    data: Option<Bytes>,
+    #[arg(long, value_parser = bytes, conflicts_with = "data")]
+    hash_to_directly_verify: Option<Bytes>,
+    #[arg(long, value_parser = bytes)]
+    signature: Bytes,
+    #[arg(long)]
+    for_self: bool,
+}
+
+// Certificates
+
+#[derive(Args)]
+struct AcquireCertificate {
+    #[arg(long = "type")]
+    cert_type: String,
+    #[arg(long)]
+    certifier: String,
+    /// `direct` or `issuance`.
+    #[arg(long, value_parser = json_string::<AcquisitionProtocol>)]
+    acquisition_protocol: AcquisitionProtocol,
+    /// A certificate field as `NAME=VALUE`; repeat for more.
+    #[arg(long = "field", value_parser = key_value)]
+    fields: Vec<(String, String)>,
+    #[arg(long)]
+    serial_number: Option<String>,
+    #[arg(long)]
+    revocation_outpoint: Option<String>,
+    #[arg(long)]
+    signature: Option<String>,
+    #[arg(long)]
+    certifier_url: Option<String>,
+    #[arg(long)]
+    keyring_revealer: Option<String>,
+    /// A keyring entry for the subject as `NAME=VALUE`; repeat for more.
+    #[arg(long = "keyring", value_parser = key_value)]
+    keyring_for_subject: Vec<(String, String)>,
+    #[command(flatten)]
+    privilege: Privilege,
+}
+
+#[derive(Args)]
+struct ListCertificates {
+    #[arg(long = "certifier")]
+    certifiers: Vec<String>,
+    #[arg(long = "type")]
+    types: Vec<String>,
+    #[command(flatten)]
+    page: Page,
+    #[command(flatten)]
+    privilege: Privilege,
+}
+
+#[derive(Args)]
+struct ProveCertificate {
+    /// The certificate to prove, as a JSON object.
+    #[arg(long, value_parser = json::<PartialWalletCertificate>)]
+    certificate: PartialWalletCertificate,
+    /// A field to reveal to the verifier; repeat for more.
+    #[arg(long = "reveal", required = true)]
+    fields_to_reveal: Vec<String>,
+    #[arg(long)]
+    verifier: String,
+    #[command(flatten)]
+    privilege: Privilege,
+}
+
+#[derive(Args)]
+struct RelinquishCertificate {
+    #[arg(long = "type")]
+    cert_type: String,
+    #[arg(long)]
+    serial_number: String,
+    #[arg(long)]
+    certifier: String,
+}
+
+// Discovery and chain
+
+#[derive(Args)]
+struct DiscoverByIdentityKey {
+    #[arg(long)]
+    identity_key: String,
+    #[command(flatten)]
+    page: Page,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+#[derive(Args)]
+struct DiscoverByAttributes {
+    /// An attribute to match as `NAME=VALUE`; repeat for more.
+    #[arg(long = "attribute", value_parser = key_value, required = true)]
+    attributes: Vec<(String, String)>,
+    #[command(flatten)]
+    page: Page,
+    #[command(flatten)]
+    seek: SeekPermission,
+}
+
+#[derive(Args)]
+struct GetHeaderForHeight {
+    #[arg(long)]
+    height: u32,
+}
+
+/// A set flag as `Some(true)`, so unset flags are left to the wallet's
+/// defaults.
+fn flag(set: bool) -> Option<bool> {
+    set.then_some(true)
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    (!items.is_empty()).then_some(items)
+}
+
+fn protocol(value: &str) -> Result<WalletProtocol, String> {
+    let (level, name) = value
+        .split_once(':')
+        .ok_or("expected LEVEL:NAME, e.g. `2:my protocol`")?;
+    let level = level
+        .trim()
+        .parse()
+        .map_err(|_| "the security level must be 0, 1 or 2")?;
+    Ok(WalletProtocol(level, name.to_string()))
+}
+
+/// A byte argument. Not a bare `Vec<u8>`, which clap would take as a list
+/// of numbers.
+#[derive(Clone)]
+struct Bytes(Vec<u8>);
+
+fn bytes(value: &str) -> Result<Bytes, String> {
+    if let Some(hex) = value.strip_prefix("hex:") {
+        if hex.len() % 2 != 0 {
+            return Err("hex must have an even number of digits".to_string());
+        }
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| e.to_string()))
+            .collect::<Result<_, _>>()
+            .map(Bytes);
+    }
+    if let Some(path) = value.strip_prefix('@') {
+        return fs::read(path)
+            .map(Bytes)
+            .map_err(|e| format!("{}: {}", path, e));
+    }
+    Ok(Bytes(value.as_bytes().to_vec()))
+}
+
+fn json<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_str(value).map_err(|e| e.to_string())
+}
+
+/// A plain string naming one of the API's enum values.
+fn json_string<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+    serde_json::from_value(Value::String(value.to_string())).map_err(|e| e.to_string())
+}
+
+fn key_value(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| "expected NAME=VALUE".to_string())
+}
+
+fn to_body<T: Serialize>(method: Method, args: &T) -> (Method, String) {
+    let body = serde_json::to_string(args).expect("arguments always serialize");
+    (method, body)
+}
+
+impl Command {
+    /// The method to call and its JSON arguments, or `None` for commands
+    /// that are not a single call.
+    fn into_request(self) -> Result<Option<(Method, String)>, String> {
+        let request = match self {
+            Command::Pair => return Ok(None),
+            Command::Call { method, args } => {
+                let method = Method::from_name(&method)
+                    .ok_or_else(|| format!("unknown method `{}`", method))?;
+                let body = match args.as_deref() {
+                    None => "{}".to_string(),
+                    Some("-") => {
+                        let mut body = String::new();
+                        io::stdin()
+                            .read_to_string(&mut body)
+                            .map_err(|e| e.to_string())?;
+                        body
+                    }
+                    Some(args) => args.to_string(),
+                };
+                (method, body)
+            }
+            Command::CreateAction(a) => to_body(
+                Method::CreateAction,
+                &CreateActionArgs {
+                    description: a.description,
+                    input_beef: a.input_beef.map(|b| b.0),
+                    inputs: non_empty(a.inputs),
+                    outputs: non_empty(a.outputs),
+                    lock_time: a.lock_time,
+                    version: a.version,
+                    labels: non_empty(a.labels),
+                    options: a.options,
+                },
+            ),
+            Command::SignAction(a) => to_body(
+                Method::SignAction,
+                &SignActionArgs {
+                    spends: a.spends,
+                    reference: a.reference,
+                    options: a.options,
+                },
+            ),
+            Command::AbortAction(a) => to_body(
+                Method::AbortAction,
+                &AbortActionArgs {
+                    reference: a.reference,
+                },
+            ),
+            Command::ListActions(a) => to_body(
+                Method::ListActions,
+                &ListActionsArgs {
+                    labels: a.labels,
+                    label_query_mode: a.label_query_mode,
+                    include_labels: flag(a.include_labels),
+                    include_inputs: flag(a.include_inputs),
+                    include_input_source_locking_scripts: flag(
+                        a.include_input_source_locking_scripts,
+                    ),
+                    include_input_unlocking_scripts: flag(a.include_input_unlocking_scripts),
+                    include_outputs: flag(a.include_outputs),
+                    include_output_locking_scripts: flag(a.include_output_locking_scripts),
+                    limit: a.page.limit,
+                    offset: a.page.offset,
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::InternalizeAction(a) => to_body(
+                Method::InternalizeAction,
+                &InternalizeActionArgs {
+                    tx: a.tx.0,
+                    outputs: a.outputs,
+                    description: a.description,
+                    labels: non_empty(a.labels),
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::ListOutputs(a) => to_body(
+                Method::ListOutputs,
+                &ListOutputsArgs {
+                    basket: a.basket,
+                    tags: non_empty(a.tags),
+                    tag_query_mode: a.tag_query_mode,
+                    include: a.include,
+                    include_custom_instructions: flag(a.include_custom_instructions),
+                    include_tags: flag(a.include_tags),
+                    include_labels: flag(a.include_labels),
+                    limit: a.page.limit,
+                    offset: a.page.offset,
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::RelinquishOutput(a) => to_body(
+                Method::RelinquishOutput,
+                &RelinquishOutputArgs {
+                    basket: a.basket,
+                    output: a.output,
+                },
+            ),
+            Command::GetPublicKey(a) => to_body(
+                Method::GetPublicKey,
+                &GetPublicKeyArgs {
+                    identity_key: flag(a.identity_key),
+                    protocol_id: a.protocol,
+                    key_id: a.key_id,
+                    counterparty: a.counterparty,
+                    for_self: flag(a.for_self),
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::RevealCounterpartyKeyLinkage(a) => to_body(
+                Method::RevealCounterpartyKeyLinkage,
+                &RevealCounterpartyKeyLinkageArgs {
+                    counterparty: a.counterparty,
+                    verifier: a.verifier,
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                },
+            ),
+            Command::RevealSpecificKeyLinkage(a) => to_body(
+                Method::RevealSpecificKeyLinkage,
+                &RevealSpecificKeyLinkageArgs {
+                    counterparty: a.counterparty,
+                    verifier: a.verifier,
+                    protocol_id: a.protocol,
+                    key_id: a.key_id,
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                },
+            ),
+            Command::Encrypt(a) => to_body(
+                Method::Encrypt,
+                &WalletEncryptArgs {
+                    key: a.key.into_args(),
+                    plaintext: a.plaintext.0,
+                },
+            ),
+            Command::Decrypt(a) => to_body(
+                Method::Decrypt,
+                &WalletDecryptArgs {
+                    key: a.key.into_args(),
+                    ciphertext: a.ciphertext.0,
+                },
+            ),
+            Command::CreateHmac(a) => to_body(
+                Method::CreateHmac,
+                &CreateHmacArgs {
+                    key: a.key.into_args(),
+                    data: a.data.0,
+                },
+            ),
+            Command::VerifyHmac(a) => to_body(
+                Method::VerifyHmac,
+                &VerifyHmacArgs {
+                    key: a.key.into_args(),
+                    data: a.data.0,
+                    hmac: a.hmac.0,
+                },
+            ),
+            Command::CreateSignature(a) => to_body(
+                Method::CreateSignature,
+                &CreateSignatureArgs {
+                    key: a.key.into_args(),
+                    data: a.data.map(|b| b.0),
+                    hash_to_directly_sign: a.hash_to_directly_sign.map(|b| b.0),
+                },
+            ),
+            Command::VerifySignature(a) => to_body(
+                Method::VerifySignature,
+                &VerifySignatureArgs {
+                    key: a.key.into_args(),
+                    data: a.data.map(|b| b.0),
+                    hash_to_directly_verify: a.hash_to_directly_verify.map(|b| b.0),
+                    signature: a.signature.0,
+                    for_self: flag(a.for_self),
+                },
+            ),
+            Command::AcquireCertificate(a) => to_body(
+                Method::AcquireCertificate,
+                &AcquireCertificateArgs {
+                    cert_type: a.cert_type,
+                    certifier: a.certifier,
+                    acquisition_protocol: a.acquisition_protocol,
+                    fields: a.fields.into_iter().collect(),
+                    serial_number: a.serial_number,
+                    revocation_outpoint: a.revocation_outpoint,
+                    signature: a.signature,
+                    certifier_url: a.certifier_url,
+                    keyring_revealer: a.keyring_revealer,
+                    keyring_for_subject: (!a.keyring_for_subject.is_empty())
+                        .then(|| a.keyring_for_subject.into_iter().collect()),
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                },
+            ),
+            Command::ListCertificates(a) => to_body(
+                Method::ListCertificates,
+                &ListCertificatesArgs {
+                    certifiers: a.certifiers,
+                    types: a.types,
+                    limit: a.page.limit,
+                    offset: a.page.offset,
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                },
+            ),
+            Command::ProveCertificate(a) => to_body(
+                Method::ProveCertificate,
+                &ProveCertificateArgs {
+                    certificate: a.certificate,
+                    fields_to_reveal: a.fields_to_reveal,
+                    verifier: a.verifier,
+                    privileged: flag(a.privilege.privileged),
+                    privileged_reason: a.privilege.privileged_reason,
+                },
+            ),
+            Command::RelinquishCertificate(a) => to_body(
+                Method::RelinquishCertificate,
+                &RelinquishCertificateArgs {
+                    cert_type: a.cert_type,
+                    serial_number: a.serial_number,
+                    certifier: a.certifier,
+                },
+            ),
+            Command::DiscoverByIdentityKey(a) => to_body(
+                Method::DiscoverByIdentityKey,
+                &DiscoverByIdentityKeyArgs {
+                    identity_key: a.identity_key,
+                    limit: a.page.limit,
+                    offset: a.page.offset,
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::DiscoverByAttributes(a) => to_body(
+                Method::DiscoverByAttributes,
+                &DiscoverByAttributesArgs {
+                    attributes: a.attributes.into_iter().collect(),
+                    limit: a.page.limit,
+                    offset: a.page.offset,
+                    seek_permission: a.seek.seek_permission,
+                },
+            ),
+            Command::GetHeaderForHeight(a) => to_body(
+                Method::GetHeaderForHeight,
+                &GetHeaderArgs { height: a.height },
+            ),
+            Command::IsAuthenticated => to_body(Method::IsAuthenticated, &EmptyArgs {}),
+            Command::WaitForAuthentication => to_body(Method::WaitForAuthentication, &EmptyArgs {}),
+            Command::GetHeight => to_body(Method::GetHeight, &EmptyArgs {}),
+            Command::GetNetwork => to_body(Method::GetNetwork, &EmptyArgs {}),
+            Command::GetVersion => to_body(Method::GetVersion, &EmptyArgs {}),
+        };
+        Ok(Some(request))
+    }
+}
+
+/// Settings remembered by `metanet-cli pair`.
+#[derive(Serialize, Deserialize, Default)]
+struct PairedConfig {
+    origin: Option<String>,
+}
+
+fn config_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("metanet-cli").join("config.json"))
+}
+
+impl PairedConfig {
+    fn load() -> Self {
+        config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> io::Result<PathBuf> {
+        let path = config_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(&path, json)?;
+        Ok(path)
+    }
+}
+
+/// How a call failed, mapped to the exit code.
+enum Failure {
+    InvalidArgs(String),
+    Unreachable(String),
+    Wallet(String),
+}
+
+impl Failure {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Failure::InvalidArgs(_) => EXIT_INVALID_ARGS,
+            Failure::Unreachable(_) => EXIT_UNREACHABLE,
+            Failure::Wallet(_) => EXIT_WALLET_ERROR,
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            Failure::InvalidArgs(m) | Failure::Unreachable(m) | Failure::Wallet(m) => m,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create Tokio runtime");
+    match runtime.block_on(run(cli)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("metanet-cli: {}", failure.message());
+            ExitCode::from(failure.exit_code())
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), Failure> {
+    let mut config = PairedConfig::load();
+    let origin = cli
+        .origin
+        .clone()
+        .or_else(|| config.origin.clone())
+        .unwrap_or_else(|| DEFAULT_ORIGIN.to_string());
+
+    let mut client = BridgeClient::new();
+    if let Some(addr) = &cli.addr {
+        client = client.with_addr(addr.as_str());
+    }
+    client = client.with_header("Originator", origin.as_str());
+    if let Some(key) = &cli.idempotency_key {
+        client = client.with_header(IDEMPOTENCY_KEY_HEADER, key.as_str());
+    }
+
+    let Some((method, body)) = cli.command.into_request().map_err(Failure::InvalidArgs)? else {
+        // Pairing: once the wallet is unlocked, asking for the identity key
+        // makes it prompt the user to connect this origin.
+        call(&client, Method::WaitForAuthentication, "{}".to_string()).await?;
+        let body = serde_json::to_string(&GetPublicKeyArgs {
+            identity_key: Some(true),
+            ..GetPublicKeyArgs::default()
+        })
+        .expect("arguments always serialize");
+        let result = call(&client, Method::GetPublicKey, body).await?;
+        config.origin = Some(origin.clone());
+        match config.save() {
+            Ok(path) => eprintln!("Paired as `{}` (saved to {})", origin, path.display()),
+            Err(e) => eprintln!("Paired as `{}`, but could not save it: {}", origin, e),
+        }
+        print(&result, cli.compact);
+        return Ok(());
+    };
+
+    if let Err(fields) = validate_request(method, &body) {
+        let mut message = format!("invalid arguments for {}:", method.name());
+        for field in fields {
+            let name = if field.field.is_empty() {
+                "(body)"
+            } else {
+                &field.field
+            };
+            message.push_str(&format!("\n  {}: {}", name, field.message));
+        }
+        return Err(Failure::InvalidArgs(message));
+    }
+    let result = call(&client, method, body).await?;
+    print(&result, cli.compact);
+    Ok(())
+}
+
+/// Call `method`, returning its result or the wallet's error.
+async fn call(client: &BridgeClient, method: Method, body: String) -> Result<Value, Failure> {
+    let ClientResponse { status, body } = client
+        .post(&method.path(), body)
+        .await
+        .map_err(|e| Failure::Unreachable(e.to_string()))?;
+    let value: Value = serde_json::from_slice(&body)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned()));
+
+    let is_error = value
+        .get("isError")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    if status.is_success() && !is_error {
+        return Ok(value);
+    }
+    let message = match serde_json::from_value::<WalletError>(value.clone()) {
+        Ok(error) => {
+            let mut message = format!("{}: {}", error.code, error.description);
+            for field in error.fields {
+                message.push_str(&format!("\n  {}: {}", field.field, field.message));
+            }
+            message
+        }
+        Err(_) => format!(
+            "wallet answered {}: {}",
+            status,
+            value
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| value.to_string())
+        ),
+    };
+    Err(Failure::Wallet(message))
+}
+
+fn print(value: &Value, compact: bool) {
+    let output = if compact {
+        value.to_string()
+    } else {
+        serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string())
+    };
+    println!("{}", output);
+}
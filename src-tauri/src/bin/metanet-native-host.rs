@@ -0,0 +1,93 @@
+//! Native messaging host for browser extensions.
+//!
+//! Started by the browser, this relays the extension's JSON-RPC messages to
+//! the running wallet. Run it with `install` to register it with the
+//! browsers found in the home directory, or `uninstall` to remove it again.
+
+use std::{env, path::PathBuf, process::ExitCode};
+
+use metanet_desktop::{client::BridgeClient, native_messaging};
+
+const USAGE: &str =
+    "Usage: metanet-native-host install [--chrome-extension ID]... [--firefox-extension ID]...
+       metanet-native-host uninstall";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Browsers pass their own arguments, so anything else runs the host.
+    let result = match args.first().map(String::as_str) {
+        Some("install") => install(&args[1..]),
+        Some("uninstall") => uninstall(),
+        Some("--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => run(&args),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("metanet-native-host: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut client = BridgeClient::new();
+    if let Some((name, value)) = native_messaging::caller_origin(args) {
+        client = client.with_header(name, value);
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?;
+    runtime
+        .block_on(native_messaging::run(client))
+        .map_err(|e| e.to_string())
+}
+
+fn install(args: &[String]) -> Result<(), String> {
+    let mut chrome_ids = Vec::new();
+    let mut firefox_ids = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let ids = match arg.as_str() {
+            "--chrome-extension" => &mut chrome_ids,
+            "--firefox-extension" => &mut firefox_ids,
+            _ => return Err(format!("unexpected argument `{}`\n{}", arg, USAGE)),
+        };
+        let id = args
+            .next()
+            .ok_or_else(|| format!("`{}` needs an extension ID", arg))?;
+        ids.push(id.clone());
+    }
+    if chrome_ids.is_empty() && firefox_ids.is_empty() {
+        return Err(format!("no extensions to allow\n{}", USAGE));
+    }
+
+    let host_path = env::current_exe().map_err(|e| e.to_string())?;
+    let written =
+        native_messaging::install_manifests(&home()?, &host_path, &chrome_ids, &firefox_ids)
+            .map_err(|e| e.to_string())?;
+    if written.is_empty() {
+        return Err("no supported browser was found".to_string());
+    }
+    for path in written {
+        println!("Installed {}", path.display());
+    }
+    Ok(())
+}
+
+fn uninstall() -> Result<(), String> {
+    for path in native_messaging::uninstall_manifests(&home()?).map_err(|e| e.to_string())? {
+        println!("Removed {}", path.display());
+    }
+    Ok(())
+}
+
+fn home() -> Result<PathBuf, String> {
+    env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| "HOME is not set".to_string())
+}
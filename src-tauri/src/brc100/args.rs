@@ -0,0 +1,513 @@
+//! Argument objects for each BRC-100 method, mirroring the `*Args`
+//! interfaces of `@bsv/sdk`.
+//!
+//! String-typed fields (hex, base64, outpoints, public keys) are kept as
+//! `String` here and checked by [`Validate`](super::Validate).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Security level and protocol name, serialized as `[level, "name"]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct WalletProtocol(pub u8, pub String);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryMode {
+    Any,
+    All,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustSelf {
+    Known,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputInclude {
+    #[serde(rename = "locking scripts")]
+    LockingScripts,
+    #[serde(rename = "entire transactions")]
+    EntireTransactions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InternalizeProtocol {
+    #[serde(rename = "wallet payment")]
+    WalletPayment,
+    #[serde(rename = "basket insertion")]
+    BasketInsertion,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AcquisitionProtocol {
+    Direct,
+    Issuance,
+}
+
+// createAction
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateActionArgs {
+    pub description: String,
+    #[serde(rename = "inputBEEF", skip_serializing_if = "Option::is_none")]
+    pub input_beef: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<Vec<CreateActionInput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<CreateActionOutput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lock_time: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<CreateActionOptions>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateActionInput {
+    pub outpoint: String,
+    pub input_description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlocking_script: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlocking_script_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_number: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateActionOutput {
+    pub locking_script: String,
+    pub satoshis: u64,
+    pub output_description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub basket: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_instructions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateActionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sign_and_process: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_delayed_broadcast: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trust_self: Option<TrustSelf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_txids: Option<Vec<String>>,
+    #[serde(rename = "returnTXIDOnly", skip_serializing_if = "Option::is_none")]
+    pub return_txid_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_send: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_send_change: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_with: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub randomize_outputs: Option<bool>,
+}
+
+// signAction / abortAction
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignActionArgs {
+    pub spends: BTreeMap<u32, SignActionSpend>,
+    pub reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<SignActionOptions>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignActionSpend {
+    pub unlocking_script: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sequence_number: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignActionOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accept_delayed_broadcast: Option<bool>,
+    #[serde(rename = "returnTXIDOnly", skip_serializing_if = "Option::is_none")]
+    pub return_txid_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_send: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_with: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AbortActionArgs {
+    pub reference: String,
+}
+
+// listActions / internalizeAction
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListActionsArgs {
+    pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_query_mode: Option<QueryMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_labels: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_inputs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_input_source_locking_scripts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_input_unlocking_scripts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_outputs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_output_locking_scripts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InternalizeActionArgs {
+    pub tx: Vec<u8>,
+    pub outputs: Vec<InternalizeOutput>,
+    pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct InternalizeOutput {
+    pub output_index: u32,
+    pub protocol: InternalizeProtocol,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_remittance: Option<WalletPayment>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insertion_remittance: Option<BasketInsertion>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletPayment {
+    pub derivation_prefix: String,
+    pub derivation_suffix: String,
+    pub sender_identity_key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BasketInsertion {
+    pub basket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_instructions: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+}
+
+// listOutputs / relinquishOutput
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListOutputsArgs {
+    pub basket: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_query_mode: Option<QueryMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include: Option<OutputInclude>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_custom_instructions: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_tags: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_labels: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinquishOutputArgs {
+    pub basket: String,
+    pub output: String,
+}
+
+// Key derivation and linkage
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GetPublicKeyArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_key: Option<bool>,
+    #[serde(rename = "protocolID", skip_serializing_if = "Option::is_none")]
+    pub protocol_id: Option<WalletProtocol>,
+    #[serde(rename = "keyID", skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub for_self: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevealCounterpartyKeyLinkageArgs {
+    pub counterparty: String,
+    pub verifier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RevealSpecificKeyLinkageArgs {
+    pub counterparty: String,
+    pub verifier: String,
+    #[serde(rename = "protocolID")]
+    pub protocol_id: WalletProtocol,
+    #[serde(rename = "keyID")]
+    pub key_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+}
+
+/// Fields shared by every key-derived cryptographic operation
+/// (encrypt, decrypt, HMACs and signatures).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletEncryptionArgs {
+    #[serde(rename = "protocolID")]
+    pub protocol_id: WalletProtocol,
+    #[serde(rename = "keyID")]
+    pub key_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counterparty: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+// Cryptographic operations
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletEncryptArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    pub plaintext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletDecryptArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    pub ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateHmacArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    pub data: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyHmacArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    pub data: Vec<u8>,
+    pub hmac: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateSignatureArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash_to_directly_sign: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifySignatureArgs {
+    #[serde(flatten)]
+    pub key: WalletEncryptionArgs,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<u8>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash_to_directly_verify: Option<Vec<u8>>,
+    pub signature: Vec<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub for_self: Option<bool>,
+}
+
+// Certificates
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AcquireCertificateArgs {
+    #[serde(rename = "type")]
+    pub cert_type: String,
+    pub certifier: String,
+    pub acquisition_protocol: AcquisitionProtocol,
+    pub fields: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation_outpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certifier_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyring_revealer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyring_for_subject: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ListCertificatesArgs {
+    pub certifiers: Vec<String>,
+    pub types: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+}
+
+/// A certificate as held by the wallet. Every field is optional when used
+/// as the `certificate` argument of `proveCertificate`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PartialWalletCertificate {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub cert_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revocation_outpoint: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProveCertificateArgs {
+    pub certificate: PartialWalletCertificate,
+    pub fields_to_reveal: Vec<String>,
+    pub verifier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged_reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RelinquishCertificateArgs {
+    #[serde(rename = "type")]
+    pub cert_type: String,
+    pub serial_number: String,
+    pub certifier: String,
+}
+
+// Discovery
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverByIdentityKeyArgs {
+    pub identity_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoverByAttributesArgs {
+    pub attributes: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seek_permission: Option<bool>,
+}
+
+// Chain and authentication state
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GetHeaderArgs {
+    pub height: u32,
+}
+
+/// Arguments for `isAuthenticated`, `waitForAuthentication`, `getHeight`,
+/// `getNetwork` and `getVersion`, which all take an empty object.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmptyArgs {}
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// The bridge itself failed (serialization, event emission, ...).
+pub const ERR_INTERNAL: &str = "ERR_INTERNAL";
+/// The frontend answered, but with something the bridge cannot relay.
+pub const ERR_INVALID_RESPONSE: &str = "ERR_INVALID_RESPONSE";
+/// The frontend never answered the request.
+pub const ERR_NO_RESPONSE: &str = "ERR_NO_RESPONSE";
+/// The request path does not name a BRC-100 method.
+pub const ERR_UNKNOWN_METHOD: &str = "ERR_UNKNOWN_METHOD";
+/// The HTTP method is not accepted for this BRC-100 method.
+pub const ERR_METHOD_NOT_ALLOWED: &str = "ERR_METHOD_NOT_ALLOWED";
+/// The request body could not be read, or is too large.
+pub const ERR_INVALID_BODY: &str = "ERR_INVALID_BODY";
+/// The request body failed schema validation; see `fields`.
+pub const ERR_INVALID_PARAMETER: &str = "ERR_INVALID_PARAMETER";
+/// An `Idempotency-Key` was reused for a request with different arguments.
+pub const ERR_IDEMPOTENCY_KEY_REUSED: &str = "ERR_IDEMPOTENCY_KEY_REUSED";
+/// The user paused app access to the wallet.
+pub const ERR_ACCESS_PAUSED: &str = "ERR_ACCESS_PAUSED";
+/// The wallet is locked until the user authenticates again.
+pub const ERR_WALLET_LOCKED: &str = "ERR_WALLET_LOCKED";
+/// The user denied the request in its approval window.
+pub const ERR_USER_DENIED: &str = "ERR_USER_DENIED";
+
+/// Error body in the BRC-100 `{ isError, code, description }` shape.
+///
+/// Errors produced by the wallet itself are passed through untouched; this
+/// type is used for failures generated on the Rust side of the bridge, so
+/// clients can treat every error response the same way.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletError {
+    pub is_error: bool,
+    pub code: String,
+    pub description: String,
+    /// Per-field validation failures, only present for invalid arguments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+/// A single validation failure, located by its JSON path in the arguments
+/// object (e.g. `outputs[0].satoshis`).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl WalletError {
+    pub fn new(code: &str, description: impl Into<String>) -> Self {
+        Self {
+            is_error: true,
+            code: code.to_string(),
+            description: description.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// An `ERR_INVALID_PARAMETER` error listing every failed field.
+    pub fn invalid_parameters(method: &str, fields: Vec<FieldError>) -> Self {
+        let description = match fields.as_slice() {
+            [only] if only.field.is_empty() => {
+                format!("Invalid {} arguments: {}", method, only.message)
+            }
+            [only] => format!(
+                "Invalid {} argument `{}`: {}",
+                method, only.field, only.message
+            ),
+            _ => format!("{} invalid {} arguments", fields.len(), method),
+        };
+        Self {
+            fields,
+            ..Self::new(ERR_INVALID_PARAMETER, description)
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("WalletError is always serializable")
+    }
+}
@@ -0,0 +1,188 @@
+/// Every method of the BRC-100 wallet interface exposed on the bridge.
+///
+/// Each method is served at `/<name>`, e.g. `/createAction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    CreateAction,
+    SignAction,
+    AbortAction,
+    ListActions,
+    InternalizeAction,
+    ListOutputs,
+    RelinquishOutput,
+    GetPublicKey,
+    RevealCounterpartyKeyLinkage,
+    RevealSpecificKeyLinkage,
+    Encrypt,
+    Decrypt,
+    CreateHmac,
+    VerifyHmac,
+    CreateSignature,
+    VerifySignature,
+    AcquireCertificate,
+    ListCertificates,
+    ProveCertificate,
+    RelinquishCertificate,
+    DiscoverByIdentityKey,
+    DiscoverByAttributes,
+    IsAuthenticated,
+    WaitForAuthentication,
+    GetHeight,
+    GetHeaderForHeight,
+    GetNetwork,
+    GetVersion,
+}
+
+/// How a method affects the wallet, which decides how its requests are
+/// scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MethodClass {
+    /// Reads wallet or chain state and never prompts the user.
+    ReadOnly,
+    /// Uses the wallet's keys; may prompt for a permission.
+    Crypto,
+    /// Builds transactions or otherwise changes wallet state; usually needs
+    /// the user's approval.
+    Spending,
+}
+
+impl Method {
+    /// All methods, in the order they appear in the BRC-100 specification.
+    pub const ALL: [Method; 28] = [
+        Method::CreateAction,
+        Method::SignAction,
+        Method::AbortAction,
+        Method::ListActions,
+        Method::InternalizeAction,
+        Method::ListOutputs,
+        Method::RelinquishOutput,
+        Method::GetPublicKey,
+        Method::RevealCounterpartyKeyLinkage,
+        Method::RevealSpecificKeyLinkage,
+        Method::Encrypt,
+        Method::Decrypt,
+        Method::CreateHmac,
+        Method::VerifyHmac,
+        Method::CreateSignature,
+        Method::VerifySignature,
+        Method::AcquireCertificate,
+        Method::ListCertificates,
+        Method::ProveCertificate,
+        Method::RelinquishCertificate,
+        Method::DiscoverByIdentityKey,
+        Method::DiscoverByAttributes,
+        Method::IsAuthenticated,
+        Method::WaitForAuthentication,
+        Method::GetHeight,
+        Method::GetHeaderForHeight,
+        Method::GetNetwork,
+        Method::GetVersion,
+    ];
+
+    /// The camelCase method name, as used in the request path.
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::CreateAction => "createAction",
+            Method::SignAction => "signAction",
+            Method::AbortAction => "abortAction",
+            Method::ListActions => "listActions",
+            Method::InternalizeAction => "internalizeAction",
+            Method::ListOutputs => "listOutputs",
+            Method::RelinquishOutput => "relinquishOutput",
+            Method::GetPublicKey => "getPublicKey",
+            Method::RevealCounterpartyKeyLinkage => "revealCounterpartyKeyLinkage",
+            Method::RevealSpecificKeyLinkage => "revealSpecificKeyLinkage",
+            Method::Encrypt => "encrypt",
+            Method::Decrypt => "decrypt",
+            Method::CreateHmac => "createHmac",
+            Method::VerifyHmac => "verifyHmac",
+            Method::CreateSignature => "createSignature",
+            Method::VerifySignature => "verifySignature",
+            Method::AcquireCertificate => "acquireCertificate",
+            Method::ListCertificates => "listCertificates",
+            Method::ProveCertificate => "proveCertificate",
+            Method::RelinquishCertificate => "relinquishCertificate",
+            Method::DiscoverByIdentityKey => "discoverByIdentityKey",
+            Method::DiscoverByAttributes => "discoverByAttributes",
+            Method::IsAuthenticated => "isAuthenticated",
+            Method::WaitForAuthentication => "waitForAuthentication",
+            Method::GetHeight => "getHeight",
+            Method::GetHeaderForHeight => "getHeaderForHeight",
+            Method::GetNetwork => "getNetwork",
+            Method::GetVersion => "getVersion",
+        }
+    }
+
+    /// Look a method up by its camelCase name.
+    pub fn from_name(name: &str) -> Option<Method> {
+        Method::ALL.into_iter().find(|m| m.name() == name)
+    }
+
+    /// Look a method up by request path, e.g. `/getVersion`.
+    pub fn from_path(path: &str) -> Option<Method> {
+        path.strip_prefix('/').and_then(Method::from_name)
+    }
+
+    /// The request path this method is served at.
+    pub fn path(self) -> String {
+        format!("/{}", self.name())
+    }
+
+    /// Whether the method takes an arguments object. Methods that don't
+    /// ignore the request body.
+    pub fn takes_args(self) -> bool {
+        !matches!(
+            self,
+            Method::IsAuthenticated
+                | Method::WaitForAuthentication
+                | Method::GetHeight
+                | Method::GetNetwork
+                | Method::GetVersion
+        )
+    }
+
+    /// The scheduling class of the method.
+    pub fn class(self) -> MethodClass {
+        match self {
+            Method::CreateAction
+            | Method::SignAction
+            | Method::AbortAction
+            | Method::InternalizeAction
+            | Method::RelinquishOutput
+            | Method::AcquireCertificate
+            | Method::RelinquishCertificate => MethodClass::Spending,
+            Method::GetPublicKey
+            | Method::RevealCounterpartyKeyLinkage
+            | Method::RevealSpecificKeyLinkage
+            | Method::Encrypt
+            | Method::Decrypt
+            | Method::CreateHmac
+            | Method::VerifyHmac
+            | Method::CreateSignature
+            | Method::VerifySignature
+            | Method::ProveCertificate => MethodClass::Crypto,
+            Method::ListActions
+            | Method::ListOutputs
+            | Method::ListCertificates
+            | Method::DiscoverByIdentityKey
+            | Method::DiscoverByAttributes
+            | Method::IsAuthenticated
+            | Method::WaitForAuthentication
+            | Method::GetHeight
+            | Method::GetHeaderForHeight
+            | Method::GetNetwork
+            | Method::GetVersion => MethodClass::ReadOnly,
+        }
+    }
+
+    /// Whether the method may be called with the given HTTP method.
+    ///
+    /// Everything accepts `POST`; argument-less methods also accept `GET`.
+    pub fn allows_http_method(self, http_method: &str) -> bool {
+        match http_method {
+            "POST" => true,
+            "GET" => !self.takes_args(),
+            _ => false,
+        }
+    }
+}
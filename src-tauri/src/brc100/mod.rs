@@ -0,0 +1,15 @@
+//! Types describing the BRC-100 wallet JSON API served on the local bridge.
+
+mod args;
+mod error;
+mod method;
+mod validate;
+
+pub use args::*;
+pub use error::{
+    FieldError, WalletError, ERR_ACCESS_PAUSED, ERR_IDEMPOTENCY_KEY_REUSED, ERR_INTERNAL,
+    ERR_INVALID_BODY, ERR_INVALID_PARAMETER, ERR_INVALID_RESPONSE, ERR_METHOD_NOT_ALLOWED,
+    ERR_NO_RESPONSE, ERR_UNKNOWN_METHOD, ERR_USER_DENIED, ERR_WALLET_LOCKED,
+};
+pub use method::{Method, MethodClass};
+pub use validate::{parse_args, validate_request, Validate};
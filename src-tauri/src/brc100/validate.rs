@@ -0,0 +1,625 @@
+//! Schema validation of BRC-100 request bodies.
+//!
+//! Structural problems (wrong types, missing fields) are reported by serde
+//! with the path of the offending field; the [`Validate`] impls then check
+//! the string formats and ranges the TypeScript types only document.
+
+use serde::de::DeserializeOwned;
+
+use super::args::*;
+use super::error::FieldError;
+use super::method::Method;
+
+/// Semantic checks run after an arguments object has been deserialized.
+pub trait Validate {
+    /// Push one [`FieldError`] per problem found. `errors` is left untouched
+    /// if the arguments are valid.
+    fn validate(&self, errors: &mut Vec<FieldError>);
+}
+
+/// Parse and validate an arguments object of type `T` from a JSON body.
+pub fn parse_args<T: DeserializeOwned + Validate>(body: &str) -> Result<T, Vec<FieldError>> {
+    let mut de = serde_json::Deserializer::from_str(body);
+    let args: T = serde_path_to_error::deserialize(&mut de).map_err(|e| {
+        let field = match e.path().to_string() {
+            p if p == "." => String::new(),
+            p => p,
+        };
+        vec![FieldError::new(field, serde_message(e.inner()))]
+    })?;
+    de.end()
+        .map_err(|e| vec![FieldError::new("", serde_message(&e))])?;
+
+    let mut errors = Vec::new();
+    args.validate(&mut errors);
+    if errors.is_empty() {
+        Ok(args)
+    } else {
+        Err(errors)
+    }
+}
+
+/// A serde_json error message without the trailing line/column position,
+/// which is meaningless to callers who only see the field path.
+fn serde_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    match message.rfind(" at line ") {
+        Some(at) => message[..at].to_string(),
+        None => message,
+    }
+}
+
+/// Validate the body of a request for `method`.
+///
+/// Methods without arguments accept an empty body or any JSON object.
+pub fn validate_request(method: Method, body: &str) -> Result<(), Vec<FieldError>> {
+    fn check<T: DeserializeOwned + Validate>(body: &str) -> Result<(), Vec<FieldError>> {
+        parse_args::<T>(body).map(|_| ())
+    }
+
+    match method {
+        Method::CreateAction => check::<CreateActionArgs>(body),
+        Method::SignAction => check::<SignActionArgs>(body),
+        Method::AbortAction => check::<AbortActionArgs>(body),
+        Method::ListActions => check::<ListActionsArgs>(body),
+        Method::InternalizeAction => check::<InternalizeActionArgs>(body),
+        Method::ListOutputs => check::<ListOutputsArgs>(body),
+        Method::RelinquishOutput => check::<RelinquishOutputArgs>(body),
+        Method::GetPublicKey => check::<GetPublicKeyArgs>(body),
+        Method::RevealCounterpartyKeyLinkage => check::<RevealCounterpartyKeyLinkageArgs>(body),
+        Method::RevealSpecificKeyLinkage => check::<RevealSpecificKeyLinkageArgs>(body),
+        Method::Encrypt => check::<WalletEncryptArgs>(body),
+        Method::Decrypt => check::<WalletDecryptArgs>(body),
+        Method::CreateHmac => check::<CreateHmacArgs>(body),
+        Method::VerifyHmac => check::<VerifyHmacArgs>(body),
+        Method::CreateSignature => check::<CreateSignatureArgs>(body),
+        Method::VerifySignature => check::<VerifySignatureArgs>(body),
+        Method::AcquireCertificate => check::<AcquireCertificateArgs>(body),
+        Method::ListCertificates => check::<ListCertificatesArgs>(body),
+        Method::ProveCertificate => check::<ProveCertificateArgs>(body),
+        Method::RelinquishCertificate => check::<RelinquishCertificateArgs>(body),
+        Method::DiscoverByIdentityKey => check::<DiscoverByIdentityKeyArgs>(body),
+        Method::DiscoverByAttributes => check::<DiscoverByAttributesArgs>(body),
+        Method::GetHeaderForHeight => check::<GetHeaderArgs>(body),
+        Method::IsAuthenticated
+        | Method::WaitForAuthentication
+        | Method::GetHeight
+        | Method::GetNetwork
+        | Method::GetVersion => {
+            if body.trim().is_empty() {
+                Ok(())
+            } else {
+                check::<EmptyArgs>(body)
+            }
+        }
+    }
+}
+
+// -----
+// Format helpers
+// -----
+
+/// Largest amount of satoshis that can ever exist.
+const MAX_SATOSHIS: u64 = 2_100_000_000_000_000;
+/// Upper bound on `limit` for the list and discover methods.
+const MAX_LIMIT: u32 = 10_000;
+
+fn push(errors: &mut Vec<FieldError>, field: &str, message: impl Into<String>) {
+    errors.push(FieldError::new(field, message));
+}
+
+fn byte_length(errors: &mut Vec<FieldError>, field: &str, value: &str, min: usize, max: usize) {
+    if value.len() < min || value.len() > max {
+        push(
+            errors,
+            field,
+            format!("must be between {} and {} bytes", min, max),
+        );
+    }
+}
+
+fn is_hex(value: &str) -> bool {
+    value.len().is_multiple_of(2) && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn hex(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if !is_hex(value) {
+        push(errors, field, "must be an even-length hex string");
+    }
+}
+
+fn txid(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if value.len() != 64 || !is_hex(value) {
+        push(errors, field, "must be a 32-byte hex txid");
+    }
+}
+
+fn outpoint(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    let valid = match value.split_once('.') {
+        Some((txid, vout)) => txid.len() == 64 && is_hex(txid) && vout.parse::<u32>().is_ok(),
+        None => false,
+    };
+    if !valid {
+        push(
+            errors,
+            field,
+            "must be an outpoint of the form <txid>.<vout>",
+        );
+    }
+}
+
+fn pub_key(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    let valid =
+        value.len() == 66 && is_hex(value) && (value.starts_with("02") || value.starts_with("03"));
+    if !valid {
+        push(errors, field, "must be a compressed public key in hex");
+    }
+}
+
+fn counterparty(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if value != "self" && value != "anyone" {
+        pub_key(errors, field, value);
+    }
+}
+
+fn base64(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    let data = value.trim_end_matches('=');
+    let valid = value.len().is_multiple_of(4)
+        && value.len() - data.len() <= 2
+        && data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+    if !valid {
+        push(errors, field, "must be a base64 string");
+    }
+}
+
+fn labels(errors: &mut Vec<FieldError>, field: &str, values: &[String]) {
+    for (i, value) in values.iter().enumerate() {
+        byte_length(errors, &format!("{}[{}]", field, i), value, 1, 300);
+    }
+}
+
+fn limit(errors: &mut Vec<FieldError>, value: Option<u32>) {
+    if let Some(limit) = value {
+        if limit == 0 || limit > MAX_LIMIT {
+            push(
+                errors,
+                "limit",
+                format!("must be between 1 and {}", MAX_LIMIT),
+            );
+        }
+    }
+}
+
+fn protocol(errors: &mut Vec<FieldError>, field: &str, value: &WalletProtocol) {
+    if value.0 > 2 {
+        push(
+            errors,
+            &format!("{}[0]", field),
+            "security level must be 0, 1 or 2",
+        );
+    }
+    let name = &value.1;
+    if name.len() < 5 || name.len() > 400 {
+        push(
+            errors,
+            &format!("{}[1]", field),
+            "protocol name must be between 5 and 400 bytes",
+        );
+    } else if name.ends_with(" protocol") {
+        push(
+            errors,
+            &format!("{}[1]", field),
+            "protocol name must not end with \" protocol\"",
+        );
+    }
+}
+
+fn key_id(errors: &mut Vec<FieldError>, value: &str) {
+    byte_length(errors, "keyID", value, 1, 800);
+}
+
+fn privileged_reason(errors: &mut Vec<FieldError>, value: &Option<String>) {
+    if let Some(reason) = value {
+        byte_length(errors, "privilegedReason", reason, 5, 50);
+    }
+}
+
+// -----
+// Per-method rules
+// -----
+
+impl Validate for CreateActionArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        byte_length(errors, "description", &self.description, 5, 50);
+        for (i, input) in self.inputs.iter().flatten().enumerate() {
+            let field = |name: &str| format!("inputs[{}].{}", i, name);
+            outpoint(errors, &field("outpoint"), &input.outpoint);
+            byte_length(
+                errors,
+                &field("inputDescription"),
+                &input.input_description,
+                5,
+                50,
+            );
+            match (&input.unlocking_script, input.unlocking_script_length) {
+                (Some(script), _) => hex(errors, &field("unlockingScript"), script),
+                (None, Some(_)) => {}
+                (None, None) => push(
+                    errors,
+                    &field("unlockingScript"),
+                    "one of unlockingScript or unlockingScriptLength is required",
+                ),
+            }
+        }
+        for (i, output) in self.outputs.iter().flatten().enumerate() {
+            let field = |name: &str| format!("outputs[{}].{}", i, name);
+            hex(errors, &field("lockingScript"), &output.locking_script);
+            if output.satoshis > MAX_SATOSHIS {
+                push(
+                    errors,
+                    &field("satoshis"),
+                    format!("must not exceed {}", MAX_SATOSHIS),
+                );
+            }
+            byte_length(
+                errors,
+                &field("outputDescription"),
+                &output.output_description,
+                5,
+                50,
+            );
+            if let Some(basket) = &output.basket {
+                byte_length(errors, &field("basket"), basket, 1, 300);
+            }
+            labels(
+                errors,
+                &field("tags"),
+                output.tags.as_deref().unwrap_or_default(),
+            );
+        }
+        labels(errors, "labels", self.labels.as_deref().unwrap_or_default());
+        if let Some(options) = &self.options {
+            for (i, id) in options.known_txids.iter().flatten().enumerate() {
+                txid(errors, &format!("options.knownTxids[{}]", i), id);
+            }
+            for (i, op) in options.no_send_change.iter().flatten().enumerate() {
+                outpoint(errors, &format!("options.noSendChange[{}]", i), op);
+            }
+            for (i, id) in options.send_with.iter().flatten().enumerate() {
+                txid(errors, &format!("options.sendWith[{}]", i), id);
+            }
+        }
+    }
+}
+
+impl Validate for SignActionArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        base64(errors, "reference", &self.reference);
+        for (index, spend) in &self.spends {
+            hex(
+                errors,
+                &format!("spends.{}.unlockingScript", index),
+                &spend.unlocking_script,
+            );
+        }
+        if let Some(options) = &self.options {
+            for (i, id) in options.send_with.iter().flatten().enumerate() {
+                txid(errors, &format!("options.sendWith[{}]", i), id);
+            }
+        }
+    }
+}
+
+impl Validate for AbortActionArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        base64(errors, "reference", &self.reference);
+    }
+}
+
+impl Validate for ListActionsArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        labels(errors, "labels", &self.labels);
+        limit(errors, self.limit);
+    }
+}
+
+impl Validate for InternalizeActionArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        if self.tx.is_empty() {
+            push(errors, "tx", "must be a non-empty AtomicBEEF");
+        }
+        if self.outputs.is_empty() {
+            push(errors, "outputs", "at least one output is required");
+        }
+        for (i, output) in self.outputs.iter().enumerate() {
+            let field = |name: &str| format!("outputs[{}].{}", i, name);
+            match output.protocol {
+                InternalizeProtocol::WalletPayment => match &output.payment_remittance {
+                    Some(payment) => {
+                        base64(
+                            errors,
+                            &field("paymentRemittance.derivationPrefix"),
+                            &payment.derivation_prefix,
+                        );
+                        base64(
+                            errors,
+                            &field("paymentRemittance.derivationSuffix"),
+                            &payment.derivation_suffix,
+                        );
+                        pub_key(
+                            errors,
+                            &field("paymentRemittance.senderIdentityKey"),
+                            &payment.sender_identity_key,
+                        );
+                    }
+                    None => push(
+                        errors,
+                        &field("paymentRemittance"),
+                        "required for the \"wallet payment\" protocol",
+                    ),
+                },
+                InternalizeProtocol::BasketInsertion => match &output.insertion_remittance {
+                    Some(insertion) => {
+                        byte_length(
+                            errors,
+                            &field("insertionRemittance.basket"),
+                            &insertion.basket,
+                            1,
+                            300,
+                        );
+                        labels(
+                            errors,
+                            &field("insertionRemittance.tags"),
+                            insertion.tags.as_deref().unwrap_or_default(),
+                        );
+                    }
+                    None => push(
+                        errors,
+                        &field("insertionRemittance"),
+                        "required for the \"basket insertion\" protocol",
+                    ),
+                },
+            }
+        }
+        byte_length(errors, "description", &self.description, 5, 50);
+        labels(errors, "labels", self.labels.as_deref().unwrap_or_default());
+    }
+}
+
+impl Validate for ListOutputsArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        byte_length(errors, "basket", &self.basket, 1, 300);
+        labels(errors, "tags", self.tags.as_deref().unwrap_or_default());
+        limit(errors, self.limit);
+    }
+}
+
+impl Validate for RelinquishOutputArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        byte_length(errors, "basket", &self.basket, 1, 300);
+        outpoint(errors, "output", &self.output);
+    }
+}
+
+impl Validate for GetPublicKeyArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        if self.identity_key != Some(true) {
+            match &self.protocol_id {
+                Some(p) => protocol(errors, "protocolID", p),
+                None => push(errors, "protocolID", "required unless identityKey is true"),
+            }
+            match &self.key_id {
+                Some(id) => key_id(errors, id),
+                None => push(errors, "keyID", "required unless identityKey is true"),
+            }
+        }
+        if let Some(c) = &self.counterparty {
+            counterparty(errors, "counterparty", c);
+        }
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for RevealCounterpartyKeyLinkageArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        pub_key(errors, "counterparty", &self.counterparty);
+        pub_key(errors, "verifier", &self.verifier);
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for RevealSpecificKeyLinkageArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        counterparty(errors, "counterparty", &self.counterparty);
+        pub_key(errors, "verifier", &self.verifier);
+        protocol(errors, "protocolID", &self.protocol_id);
+        key_id(errors, &self.key_id);
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for WalletEncryptionArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        protocol(errors, "protocolID", &self.protocol_id);
+        key_id(errors, &self.key_id);
+        if let Some(c) = &self.counterparty {
+            counterparty(errors, "counterparty", c);
+        }
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for WalletEncryptArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+    }
+}
+
+impl Validate for WalletDecryptArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+    }
+}
+
+impl Validate for CreateHmacArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+    }
+}
+
+impl Validate for VerifyHmacArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+        if self.hmac.len() != 32 {
+            push(errors, "hmac", "must be 32 bytes");
+        }
+    }
+}
+
+impl Validate for CreateSignatureArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+        match (&self.data, &self.hash_to_directly_sign) {
+            (Some(_), None) => {}
+            (None, Some(hash)) if hash.len() == 32 => {}
+            (None, Some(_)) => push(errors, "hashToDirectlySign", "must be 32 bytes"),
+            _ => push(
+                errors,
+                "data",
+                "exactly one of data or hashToDirectlySign is required",
+            ),
+        }
+    }
+}
+
+impl Validate for VerifySignatureArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        self.key.validate(errors);
+        match (&self.data, &self.hash_to_directly_verify) {
+            (Some(_), None) => {}
+            (None, Some(hash)) if hash.len() == 32 => {}
+            (None, Some(_)) => push(errors, "hashToDirectlyVerify", "must be 32 bytes"),
+            _ => push(
+                errors,
+                "data",
+                "exactly one of data or hashToDirectlyVerify is required",
+            ),
+        }
+        if self.signature.is_empty() {
+            push(errors, "signature", "must not be empty");
+        }
+    }
+}
+
+impl Validate for AcquireCertificateArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        base64(errors, "type", &self.cert_type);
+        pub_key(errors, "certifier", &self.certifier);
+        match self.acquisition_protocol {
+            AcquisitionProtocol::Direct => {
+                let required = [
+                    ("serialNumber", self.serial_number.is_some()),
+                    ("revocationOutpoint", self.revocation_outpoint.is_some()),
+                    ("signature", self.signature.is_some()),
+                    ("keyringRevealer", self.keyring_revealer.is_some()),
+                    ("keyringForSubject", self.keyring_for_subject.is_some()),
+                ];
+                for (name, present) in required {
+                    if !present {
+                        push(errors, name, "required for direct acquisition");
+                    }
+                }
+            }
+            AcquisitionProtocol::Issuance => {
+                if self.certifier_url.is_none() {
+                    push(errors, "certifierUrl", "required for issuance");
+                }
+            }
+        }
+        if let Some(serial) = &self.serial_number {
+            base64(errors, "serialNumber", serial);
+        }
+        if let Some(op) = &self.revocation_outpoint {
+            outpoint(errors, "revocationOutpoint", op);
+        }
+        if let Some(sig) = &self.signature {
+            hex(errors, "signature", sig);
+        }
+        if let Some(revealer) = &self.keyring_revealer {
+            if revealer != "certifier" {
+                pub_key(errors, "keyringRevealer", revealer);
+            }
+        }
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for ListCertificatesArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        for (i, certifier) in self.certifiers.iter().enumerate() {
+            pub_key(errors, &format!("certifiers[{}]", i), certifier);
+        }
+        for (i, t) in self.types.iter().enumerate() {
+            base64(errors, &format!("types[{}]", i), t);
+        }
+        limit(errors, self.limit);
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for ProveCertificateArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        let cert = &self.certificate;
+        if let Some(t) = &cert.cert_type {
+            base64(errors, "certificate.type", t);
+        }
+        if let Some(subject) = &cert.subject {
+            pub_key(errors, "certificate.subject", subject);
+        }
+        if let Some(certifier) = &cert.certifier {
+            pub_key(errors, "certificate.certifier", certifier);
+        }
+        if let Some(op) = &cert.revocation_outpoint {
+            outpoint(errors, "certificate.revocationOutpoint", op);
+        }
+        if self.fields_to_reveal.is_empty() {
+            push(errors, "fieldsToReveal", "at least one field is required");
+        }
+        pub_key(errors, "verifier", &self.verifier);
+        privileged_reason(errors, &self.privileged_reason);
+    }
+}
+
+impl Validate for RelinquishCertificateArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        base64(errors, "type", &self.cert_type);
+        base64(errors, "serialNumber", &self.serial_number);
+        pub_key(errors, "certifier", &self.certifier);
+    }
+}
+
+impl Validate for DiscoverByIdentityKeyArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        pub_key(errors, "identityKey", &self.identity_key);
+        limit(errors, self.limit);
+    }
+}
+
+impl Validate for DiscoverByAttributesArgs {
+    fn validate(&self, errors: &mut Vec<FieldError>) {
+        if self.attributes.is_empty() {
+            push(errors, "attributes", "at least one attribute is required");
+        }
+        limit(errors, self.limit);
+    }
+}
+
+impl Validate for GetHeaderArgs {
+    fn validate(&self, _errors: &mut Vec<FieldError>) {}
+}
+
+impl Validate for EmptyArgs {
+    fn validate(&self, _errors: &mut Vec<FieldError>) {}
+}
@@ -0,0 +1,908 @@
+//! The request/response bridge between the local HTTP server and the wallet
+//! running in the webview.
+//!
+//! Each request is emitted to the frontend as an `http-request` event and
+//! parked in a pending map until the matching `ts-response` event arrives.
+//! Large bodies may instead follow the response in `ts-response-chunk`
+//! events, which are relayed to the client as they arrive.
+//! State changes the frontend announces with `wallet-event` are fanned out
+//! to every subscriber of the bridge.
+
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use dashmap::DashMap;
+use hyper::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    sync::{broadcast, mpsc, oneshot, watch},
+    time::{timeout_at, Instant},
+};
+
+use crate::brc100::{
+    Method, MethodClass, WalletError, ERR_ACCESS_PAUSED, ERR_IDEMPOTENCY_KEY_REUSED, ERR_INTERNAL,
+    ERR_INVALID_PARAMETER, ERR_INVALID_RESPONSE, ERR_NO_RESPONSE, ERR_USER_DENIED,
+    ERR_WALLET_LOCKED,
+};
+use crate::coalesce::{Join, ReadCoalescer, SharedResponse};
+use crate::idempotency::{idempotency_key, Claim, IdempotencyStore};
+use crate::origin::request_origin;
+use crate::schedule::{LaneLimits, Scheduler};
+use crate::ws::Session;
+
+/// Credentials of the local process on the other end of a Unix socket,
+/// as reported by the kernel (`SO_PEERCRED`).
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    pub pid: Option<i32>,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Payload sent from Rust to the frontend for each HTTP request.
+#[derive(Serialize, Debug, Clone)]
+pub struct HttpRequestEvent {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub request_id: u64,
+    /// Only present for requests received over the Unix socket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer: Option<PeerCredentials>,
+}
+
+/// Expected payload sent back from the frontend.
+#[derive(Deserialize, Debug)]
+pub struct TsResponse {
+    pub request_id: u64,
+    pub status: u16,
+    #[serde(default)]
+    pub body: String,
+    /// The body is sent separately as `ts-response-chunk` events and `body`
+    /// is ignored.
+    #[serde(default)]
+    pub chunked: bool,
+}
+
+/// One part of a chunked response body.
+#[derive(Deserialize, Debug)]
+pub struct TsResponseChunk {
+    pub request_id: u64,
+    #[serde(default)]
+    pub data: String,
+    /// Set on the last chunk of the body.
+    #[serde(default)]
+    pub done: bool,
+}
+
+/// A wallet state change announced by the frontend, such as
+/// `authenticated` or `networkChanged`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WalletEvent {
+    pub event: String,
+    #[serde(default)]
+    pub data: Value,
+    /// Set for events that concern a single app; they are only delivered
+    /// to clients of that origin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<String>,
+}
+
+/// The user authenticated and the wallet is ready.
+pub const EVENT_AUTHENTICATED: &str = "authenticated";
+/// The wallet was locked and needs the user before it can be used again.
+pub const EVENT_LOCKED: &str = "locked";
+/// The wallet was unlocked after being locked.
+pub const EVENT_UNLOCKED: &str = "unlocked";
+/// The wallet switched networks; `data` is `{ network }`.
+pub const EVENT_NETWORK_CHANGED: &str = "networkChanged";
+/// A new block arrived; `data` is `{ height }`.
+pub const EVENT_HEIGHT_CHANGED: &str = "heightChanged";
+
+impl WalletEvent {
+    /// Whether a client of `origin` may see this event.
+    pub fn visible_to(&self, origin: &str) -> bool {
+        self.origin.as_deref().is_none_or(|o| o == origin)
+    }
+
+    /// Whether this event means the user authenticated, ending a lock.
+    fn unlocks(&self) -> bool {
+        self.origin.is_none()
+            && match self.event.as_str() {
+                EVENT_UNLOCKED => true,
+                EVENT_AUTHENTICATED => self.data.get("authenticated") != Some(&Value::Bool(false)),
+                _ => false,
+            }
+    }
+
+    /// The piece of wallet state this event updates, if any. Only the most
+    /// recent event for each piece of state is worth replaying.
+    fn state_slot(&self) -> Option<&'static str> {
+        if self.origin.is_some() {
+            return None;
+        }
+        match self.event.as_str() {
+            EVENT_AUTHENTICATED | EVENT_LOCKED | EVENT_UNLOCKED => Some("auth"),
+            EVENT_NETWORK_CHANGED => Some("network"),
+            EVENT_HEIGHT_CHANGED => Some("height"),
+            _ => None,
+        }
+    }
+}
+
+/// A BRC-100 call on its way to the wallet, for anyone telling the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncomingRequest {
+    /// The ID it is pending under until the wallet answers.
+    pub request_id: u64,
+    pub method: Method,
+    pub origin: Option<String>,
+}
+
+/// How many undelivered events a slow subscriber may fall behind by before
+/// it starts missing them.
+const EVENT_BACKLOG: usize = 64;
+
+/// How many parts of a chunked body may wait for a slow client. A client
+/// falling further behind gets its body cut off, so memory stays bounded.
+const CHUNK_BUFFER: usize = 64;
+
+/// How long the frontend has to send the whole of a chunked body.
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// What the frontend answered with: the response and, for chunked
+/// responses, the body parts to come.
+#[derive(Debug)]
+pub struct FrontendReply {
+    pub response: TsResponse,
+    pub chunks: Option<ChunkedBody>,
+}
+
+/// A type alias for our concurrent map of pending responses.
+pub type PendingMap = DashMap<u64, oneshot::Sender<FrontendReply>>;
+
+/// Delivers a serialized `http-request` payload to the frontend.
+pub type Emitter = Box<dyn Fn(String) -> Result<(), String> + Send + Sync>;
+
+/// A request to forward to the wallet, as received by one of the transports.
+#[derive(Debug, Clone)]
+pub struct WalletRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    pub peer: Option<PeerCredentials>,
+}
+
+/// Parts of a response body, in order, as the frontend sends them.
+#[derive(Debug)]
+pub struct ChunkedBody {
+    chunks: mpsc::Receiver<io::Result<String>>,
+    deadline: Instant,
+}
+
+impl ChunkedBody {
+    /// The next part, `None` after the last one, or an error if the body
+    /// was cut off or not finished in time.
+    pub async fn next(&mut self) -> Option<io::Result<String>> {
+        match timeout_at(self.deadline, self.chunks.recv()).await {
+            Ok(chunk) => chunk,
+            Err(_) => Some(Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "the wallet did not finish its response in time",
+            ))),
+        }
+    }
+}
+
+/// The body of a wallet response.
+#[derive(Debug)]
+pub enum WalletBody {
+    Full(String),
+    Chunked(ChunkedBody),
+}
+
+impl WalletBody {
+    /// Wait for the whole body, joining chunks if necessary.
+    pub async fn into_string(self) -> Result<String, BridgeError> {
+        match self {
+            WalletBody::Full(body) => Ok(body),
+            WalletBody::Chunked(mut chunks) => {
+                let mut body = String::new();
+                while let Some(chunk) = chunks.next().await {
+                    body.push_str(&chunk.map_err(BridgeError::IncompleteBody)?);
+                }
+                Ok(body)
+            }
+        }
+    }
+}
+
+/// The wallet's answer to a forwarded request.
+#[derive(Debug)]
+pub struct WalletResponse {
+    pub status: StatusCode,
+    pub body: WalletBody,
+}
+
+impl From<SharedResponse> for WalletResponse {
+    fn from(response: SharedResponse) -> Self {
+        Self {
+            status: response.status,
+            body: WalletBody::Full(response.body),
+        }
+    }
+}
+
+/// Ways forwarding a request to the frontend can fail on the Rust side.
+#[derive(Debug)]
+pub enum BridgeError {
+    Serialize(serde_json::Error),
+    Emit(String),
+    NoResponse,
+    InvalidStatus(u16),
+    InvalidIdempotencyKey(String),
+    IdempotencyKeyReused,
+    /// The user paused app access to the wallet.
+    Paused,
+    /// The wallet is locked and the request would use its keys.
+    Locked,
+    /// A chunked body was cut off or not finished in time.
+    IncompleteBody(io::Error),
+}
+
+impl BridgeError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            BridgeError::Serialize(_) | BridgeError::Emit(_) | BridgeError::InvalidStatus(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            BridgeError::NoResponse | BridgeError::IncompleteBody(_) => StatusCode::GATEWAY_TIMEOUT,
+            BridgeError::InvalidIdempotencyKey(_) => StatusCode::BAD_REQUEST,
+            BridgeError::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            BridgeError::Paused => StatusCode::SERVICE_UNAVAILABLE,
+            BridgeError::Locked => StatusCode::LOCKED,
+        }
+    }
+
+    pub fn to_wallet_error(&self) -> WalletError {
+        match self {
+            BridgeError::Serialize(_) => {
+                WalletError::new(ERR_INTERNAL, "Failed to serialize request for the wallet")
+            }
+            BridgeError::Emit(_) => {
+                WalletError::new(ERR_INTERNAL, "Failed to deliver request to the wallet")
+            }
+            BridgeError::NoResponse => {
+                WalletError::new(ERR_NO_RESPONSE, "The wallet did not respond to the request")
+            }
+            BridgeError::InvalidStatus(status) => WalletError::new(
+                ERR_INVALID_RESPONSE,
+                format!("Wallet returned invalid HTTP status {}", status),
+            ),
+            BridgeError::InvalidIdempotencyKey(reason) => {
+                WalletError::new(ERR_INVALID_PARAMETER, reason.clone())
+            }
+            BridgeError::IdempotencyKeyReused => WalletError::new(
+                ERR_IDEMPOTENCY_KEY_REUSED,
+                "This Idempotency-Key was already used for a request with different arguments",
+            ),
+            BridgeError::Paused => WalletError::new(
+                ERR_ACCESS_PAUSED,
+                "The user has paused app access to the wallet",
+            ),
+            BridgeError::Locked => WalletError::new(
+                ERR_WALLET_LOCKED,
+                "The wallet is locked; the user has to unlock it first",
+            ),
+            BridgeError::IncompleteBody(e) => WalletError::new(
+                ERR_NO_RESPONSE,
+                format!("The wallet response was incomplete: {}", e),
+            ),
+        }
+    }
+}
+
+/// Whether `method` spends or uses the wallet's keys, and so is refused
+/// while the wallet is locked.
+fn needs_keys(method: Method) -> bool {
+    method.class() != MethodClass::ReadOnly
+}
+
+/// Map the status the frontend answered with onto a final HTTP status.
+/// Anything outside the 2xx-5xx range is rejected.
+fn frontend_status(status: u16) -> Option<StatusCode> {
+    if !(200..600).contains(&status) {
+        return None;
+    }
+    StatusCode::from_u16(status).ok()
+}
+
+/// Tunables for how the bridge forwards requests.
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    pub lanes: LaneLimits,
+    /// How long the wallet has to answer a request. A request it never
+    /// answers fails with `NoResponse` and gives up its lane slot.
+    pub answer_timeout: Duration,
+    /// Run `createAction`, `signAction`, `abortAction` and
+    /// `internalizeAction` one at a time per origin, in arrival order.
+    pub order_per_origin: bool,
+    /// How long immutable answers (headers by height) are cached. Zero
+    /// disables the cache; identical in-flight reads are still coalesced.
+    pub read_cache_ttl: Duration,
+    /// How long answers to spending calls sent with an `Idempotency-Key`
+    /// are replayed to retries. Zero ignores the header.
+    pub idempotency_window: Duration,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            lanes: LaneLimits::default(),
+            answer_timeout: Duration::from_secs(5 * 60),
+            order_per_origin: false,
+            read_cache_ttl: Duration::from_secs(30),
+            idempotency_window: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+pub struct Bridge {
+    pending: PendingMap,
+    counter: AtomicU64,
+    emit: Emitter,
+    events: broadcast::Sender<WalletEvent>,
+    incoming: broadcast::Sender<IncomingRequest>,
+    state: DashMap<&'static str, WalletEvent>,
+    /// What each pending BRC-100 call asks for, by request ID.
+    requests: DashMap<u64, IncomingRequest>,
+    /// Chunked bodies still being received, by request ID.
+    chunks: Arc<DashMap<u64, mpsc::Sender<io::Result<String>>>>,
+    /// Open WebSocket sessions, by session ID.
+    sessions: DashMap<u64, Session>,
+    session_counter: AtomicU64,
+    scheduler: Scheduler,
+    reads: ReadCoalescer,
+    idempotency: Arc<IdempotencyStore>,
+    /// The number of pending requests, for anyone showing it.
+    pending_count: watch::Sender<usize>,
+    paused: AtomicBool,
+    /// Set when the wallet is locked, until the user authenticates again.
+    locked: AtomicBool,
+    /// Set while the frontend listens for requests: from the user
+    /// authenticating until the wallet is locked and the frontend reloads.
+    frontend_ready: watch::Sender<bool>,
+    answer_timeout: Duration,
+}
+
+impl Bridge {
+    pub fn new(emit: Emitter) -> Self {
+        Self::with_config(emit, BridgeConfig::default())
+    }
+
+    pub fn with_config(emit: Emitter, config: BridgeConfig) -> Self {
+        // Until the frontend says otherwise the user has not authenticated,
+        // which is what clients connecting this early are told.
+        let state = DashMap::new();
+        state.insert(
+            "auth",
+            WalletEvent {
+                event: EVENT_AUTHENTICATED.to_string(),
+                data: serde_json::json!({ "authenticated": false }),
+                origin: None,
+            },
+        );
+        Self {
+            pending: DashMap::new(),
+            // Request IDs start at 1, as they always have.
+            counter: AtomicU64::new(1),
+            emit,
+            events: broadcast::channel(EVENT_BACKLOG).0,
+            incoming: broadcast::channel(EVENT_BACKLOG).0,
+            state,
+            requests: DashMap::new(),
+            chunks: Arc::new(DashMap::new()),
+            sessions: DashMap::new(),
+            session_counter: AtomicU64::new(1),
+            scheduler: Scheduler::new(config.lanes, config.order_per_origin),
+            reads: ReadCoalescer::new(config.read_cache_ttl),
+            idempotency: Arc::new(IdempotencyStore::new(config.idempotency_window)),
+            pending_count: watch::channel(0).0,
+            paused: AtomicBool::new(false),
+            locked: AtomicBool::new(false),
+            frontend_ready: watch::channel(false).0,
+            answer_timeout: config.answer_timeout,
+        }
+    }
+
+    /// Handle the payload of a `wallet-event` event from the frontend.
+    pub fn publish(&self, payload: &str) {
+        match serde_json::from_str::<WalletEvent>(payload) {
+            Ok(event) => {
+                if event.unlocks() {
+                    self.locked.store(false, Ordering::Relaxed);
+                    self.frontend_ready.send_replace(true);
+                }
+                if let Some(slot) = event.state_slot() {
+                    self.state.insert(slot, event.clone());
+                }
+                // Sending only fails when nobody is subscribed, which is fine.
+                _ = self.events.send(event);
+            }
+            Err(err) => eprintln!("Failed to parse wallet-event payload: {:?}", err),
+        }
+    }
+
+    /// The latest event for each piece of wallet state (authentication,
+    /// network, height) published so far.
+    pub fn current_state(&self) -> Vec<WalletEvent> {
+        self.state
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    /// Receive every wallet event published from now on.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<WalletEvent> {
+        self.events.subscribe()
+    }
+
+    /// Hear of every BRC-100 call sent to the wallet from now on.
+    pub fn subscribe_incoming(&self) -> broadcast::Receiver<IncomingRequest> {
+        self.incoming.subscribe()
+    }
+
+    /// Register a WebSocket session for `origin`, returning its ID.
+    pub fn open_session(&self, origin: String) -> u64 {
+        let session_id = self.session_counter.fetch_add(1, Ordering::Relaxed);
+        self.sessions.insert(session_id, Session::new(origin));
+        session_id
+    }
+
+    pub fn close_session(&self, session_id: u64) {
+        self.sessions.remove(&session_id);
+    }
+
+    /// Run `f` on an open WebSocket session.
+    pub fn with_session<R>(&self, session_id: u64, f: impl FnOnce(&mut Session) -> R) -> Option<R> {
+        self.sessions
+            .get_mut(&session_id)
+            .map(|mut session| f(&mut session))
+    }
+
+    /// Every open WebSocket session, by session ID.
+    pub fn sessions(&self) -> Vec<(u64, Session)> {
+        self.sessions
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Number of requests currently waiting on the frontend.
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether request `request_id` is still waiting on the frontend.
+    pub fn is_pending(&self, request_id: u64) -> bool {
+        self.pending.contains_key(&request_id)
+    }
+
+    /// What pending request `request_id` asks for, if it is a BRC-100 call
+    /// still waiting on the frontend.
+    pub fn pending_request(&self, request_id: u64) -> Option<IncomingRequest> {
+        if !self.is_pending(request_id) {
+            return None;
+        }
+        self.requests.get(&request_id).map(|entry| entry.clone())
+    }
+
+    /// Follow the number of requests waiting on the frontend.
+    pub fn watch_pending(&self) -> watch::Receiver<usize> {
+        self.pending_count.subscribe()
+    }
+
+    fn pending_changed(&self) {
+        self.pending_count.send_replace(self.pending.len());
+    }
+
+    /// Refuse every request until unpaused. Requests already with the
+    /// wallet are still answered.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// Lock the wallet: fail the requests waiting on it, refuse requests
+    /// using its keys until the frontend announces the user authenticated
+    /// again, and tell subscribers. Reads still go through.
+    pub fn lock(&self) {
+        self.locked.store(true, Ordering::Relaxed);
+        self.frontend_ready.send_replace(false);
+        self.cancel_pending();
+        self.publish(&serde_json::json!({ "event": EVENT_LOCKED }).to_string());
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// Fail every request waiting on the frontend, e.g. because the wallet
+    /// was locked and will never answer them.
+    pub fn cancel_pending(&self) {
+        // Dropping the senders fails the waiting requests with `NoResponse`.
+        self.pending.clear();
+        self.requests.clear();
+        self.chunks.clear();
+        self.pending_changed();
+    }
+
+    /// Handle the payload of a `ts-response` event from the frontend.
+    pub fn resolve(&self, payload: &str) {
+        if payload.is_empty() {
+            eprintln!("ts-response event did not include a payload");
+            return;
+        }
+        match serde_json::from_str::<TsResponse>(payload) {
+            Ok(ts_response) => {
+                self.answer(ts_response);
+            }
+            Err(err) => {
+                eprintln!("Failed to parse ts-response payload: {:?}", err);
+            }
+        }
+    }
+
+    /// Answer pending request `request_id` on the user's behalf with a
+    /// BRC-100 denial, e.g. from its approval window. Returns whether it was
+    /// still pending.
+    pub fn deny(&self, request_id: u64) -> bool {
+        self.answer(TsResponse {
+            request_id,
+            status: StatusCode::BAD_REQUEST.as_u16(),
+            body: WalletError::new(ERR_USER_DENIED, "The user denied the request").to_json(),
+            chunked: false,
+        })
+    }
+
+    /// Settle a pending request with `ts_response`. Returns whether it was
+    /// still pending.
+    fn answer(&self, ts_response: TsResponse) -> bool {
+        if let Some((req_id, tx)) = self.pending.remove(&ts_response.request_id) {
+            self.pending_changed();
+            let chunks = ts_response.chunked.then(|| {
+                // One more slot than the buffer, for the error ending
+                // a body that overflows it.
+                let (chunk_tx, chunk_rx) = mpsc::channel(CHUNK_BUFFER + 1);
+                self.chunks.insert(req_id, chunk_tx);
+                ChunkedBody {
+                    chunks: chunk_rx,
+                    deadline: Instant::now() + CHUNK_TIMEOUT,
+                }
+            });
+            let reply = FrontendReply {
+                response: ts_response,
+                chunks,
+            };
+            if let Err(err) = tx.send(reply) {
+                self.chunks.remove(&req_id);
+                eprintln!(
+                    "Failed to send response via oneshot channel for request {}: {:?}",
+                    req_id, err
+                );
+            }
+            true
+        } else {
+            eprintln!(
+                "Received ts-response for unknown request_id: {}",
+                ts_response.request_id
+            );
+            false
+        }
+    }
+
+    /// Handle the payload of a `ts-response-chunk` event from the frontend.
+    pub fn resolve_chunk(&self, payload: &str) {
+        let chunk = match serde_json::from_str::<TsResponseChunk>(payload) {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                eprintln!("Failed to parse ts-response-chunk payload: {:?}", err);
+                return;
+            }
+        };
+        let Some(tx) = self
+            .chunks
+            .get(&chunk.request_id)
+            .map(|entry| entry.value().clone())
+        else {
+            eprintln!(
+                "Received ts-response-chunk for unknown request_id: {}",
+                chunk.request_id
+            );
+            return;
+        };
+        let delivered = if chunk.data.is_empty() {
+            true
+        } else if tx.capacity() <= 1 {
+            // The client is too slow to keep up; cut it off.
+            _ = tx.try_send(Err(io::Error::other(
+                "the client fell too far behind the wallet response",
+            )));
+            false
+        } else {
+            // Fails if the client went away; stop collecting its body then.
+            tx.try_send(Ok(chunk.data)).is_ok()
+        };
+        if chunk.done || !delivered {
+            // Dropping the sender ends the body.
+            self.chunks.remove(&chunk.request_id);
+        }
+    }
+
+    /// Forward a request to the wallet and wait for its response, sharing
+    /// identical concurrent reads and respecting the scheduling lanes.
+    pub async fn forward(
+        self: &Arc<Self>,
+        request: WalletRequest,
+    ) -> Result<WalletResponse, BridgeError> {
+        if self.is_paused() {
+            return Err(BridgeError::Paused);
+        }
+        let Some(method) = Method::from_path(&request.path) else {
+            self.frontend_ready().await;
+            return self.emit_and_wait(request, None).await;
+        };
+        if needs_keys(method) && self.is_locked() {
+            return Err(BridgeError::Locked);
+        }
+        let origin = request_origin(&request.headers);
+
+        // Retries of a spending call are answered from the first attempt.
+        if method.class() == MethodClass::Spending && self.idempotency.is_enabled() {
+            if let Some(key) = idempotency_key(&request.headers) {
+                let key = key.map_err(BridgeError::InvalidIdempotencyKey)?;
+                return self
+                    .forward_idempotent(method, origin.as_deref(), &key, request)
+                    .await;
+            }
+        }
+
+        // Identical reads already on their way to the wallet are shared.
+        let Some(key) = ReadCoalescer::key(method, origin.as_deref(), &request.body) else {
+            return self.schedule(method, origin.as_deref(), request).await;
+        };
+        let leader = match self.reads.join(method, key) {
+            Join::Cached(response) => return Ok(response.into()),
+            Join::Wait(mut answer) => match answer.recv().await {
+                Ok(response) => return Ok(response.into()),
+                // The shared request failed; try on our own.
+                Err(_) => return self.schedule(method, origin.as_deref(), request).await,
+            },
+            Join::Lead(leader) => leader,
+        };
+        let response = self.schedule(method, origin.as_deref(), request).await?;
+        let response = SharedResponse {
+            status: response.status,
+            body: response.body.into_string().await?,
+        };
+        leader.finish(&response);
+        Ok(response.into())
+    }
+
+    async fn forward_idempotent(
+        self: &Arc<Self>,
+        method: Method,
+        origin: Option<&str>,
+        key: &str,
+        request: WalletRequest,
+    ) -> Result<WalletResponse, BridgeError> {
+        let reservation = match self.idempotency.claim(method, origin, key, &request.body) {
+            Claim::Replay(response) => return Ok(response.into()),
+            // If the first attempt fails the retry fails too, rather than
+            // several waiting retries all running at once.
+            Claim::Wait(mut answer) => {
+                return answer
+                    .recv()
+                    .await
+                    .map(Into::into)
+                    .map_err(|_| BridgeError::NoResponse)
+            }
+            Claim::Conflict => return Err(BridgeError::IdempotencyKeyReused),
+            Claim::Run(reservation) => reservation,
+        };
+
+        // The first attempt runs on its own: the client sending it may give
+        // up while the wallet is still building the transaction, and its
+        // retry has to get that transaction rather than a second one.
+        let (tx, rx) = oneshot::channel();
+        let bridge = self.clone();
+        let origin = origin.map(str::to_string);
+        tokio::spawn(async move {
+            let response = match bridge.schedule(method, origin.as_deref(), request).await {
+                Ok(response) => response,
+                Err(err) => {
+                    _ = tx.send(Err(err));
+                    return;
+                }
+            };
+            let response = match response.body.into_string().await {
+                Ok(body) => SharedResponse {
+                    status: response.status,
+                    body,
+                },
+                Err(err) => {
+                    _ = tx.send(Err(err));
+                    return;
+                }
+            };
+            reservation.finish(&response);
+            // The client may be gone, which is fine.
+            _ = tx.send(Ok(response));
+        });
+        match rx.await {
+            Ok(answer) => answer.map(Into::into),
+            Err(_) => Err(BridgeError::NoResponse),
+        }
+    }
+
+    /// Wait until the frontend listens for requests. Until the user has
+    /// authenticated, and while the wallet is locked, requests would only be
+    /// emitted to nobody.
+    async fn frontend_ready(&self) {
+        let mut ready = self.frontend_ready.subscribe();
+        // The sender lives as long as the bridge.
+        _ = ready.wait_for(|ready| *ready).await;
+    }
+
+    /// Wait for the request's turn, which is held until the wallet has
+    /// answered or the answer timed out, then send it once the frontend
+    /// listens. Waiting for the frontend in turn keeps arrival order, and
+    /// nothing could be answered before it listens anyway.
+    async fn schedule(
+        &self,
+        method: Method,
+        origin: Option<&str>,
+        request: WalletRequest,
+    ) -> Result<WalletResponse, BridgeError> {
+        let _admission = self.scheduler.admit(method, origin).await;
+        self.frontend_ready().await;
+        // The wallet may have been locked while this waited for its turn.
+        if needs_keys(method) && self.is_locked() {
+            return Err(BridgeError::Locked);
+        }
+        self.emit_and_wait(request, Some((method, origin))).await
+    }
+
+    /// Emit a request to the frontend and wait for its response. `call`
+    /// names the BRC-100 method and origin, for anyone telling the user.
+    async fn emit_and_wait(
+        &self,
+        request: WalletRequest,
+        call: Option<(Method, Option<&str>)>,
+    ) -> Result<WalletResponse, BridgeError> {
+        // Requests queued before access was paused are refused too.
+        if self.is_paused() {
+            return Err(BridgeError::Paused);
+        }
+
+        // Generate a unique request ID.
+        let request_id = self.counter.fetch_add(1, Ordering::Relaxed);
+
+        // Create a oneshot channel for awaiting the frontend response.
+        let (tx, rx) = oneshot::channel::<FrontendReply>();
+        self.pending.insert(request_id, tx);
+        self.pending_changed();
+        // A client that gives up no longer counts as pending.
+        let _pending = PendingGuard {
+            bridge: self,
+            request_id,
+        };
+        if let Some((method, origin)) = call {
+            let incoming = IncomingRequest {
+                request_id,
+                method,
+                origin: origin.map(str::to_string),
+            };
+            self.requests.insert(request_id, incoming.clone());
+            // Sending only fails when nobody is subscribed, which is fine.
+            _ = self.incoming.send(incoming);
+        }
+
+        let event_payload = HttpRequestEvent {
+            method: request.method,
+            path: request.path,
+            headers: request.headers,
+            body: request.body,
+            request_id,
+            peer: request.peer,
+        };
+        let event_json = match serde_json::to_string(&event_payload) {
+            Ok(json) => json,
+            Err(e) => {
+                eprintln!("Failed to serialize HTTP event: {:?}", e);
+                return Err(BridgeError::Serialize(e));
+            }
+        };
+
+        if let Err(err) = (self.emit)(event_json) {
+            eprintln!("Failed to emit http-request event: {:?}", err);
+            return Err(BridgeError::Emit(err));
+        }
+
+        // Wait asynchronously for the frontend's response.
+        let reply = match tokio::time::timeout(self.answer_timeout, rx).await {
+            Ok(reply) => reply.map_err(|err| {
+                eprintln!(
+                    "Error awaiting frontend response for request {}: {:?}",
+                    request_id, err
+                );
+                BridgeError::NoResponse
+            })?,
+            Err(_) => {
+                eprintln!("Frontend did not answer request {} in time", request_id);
+                return Err(BridgeError::NoResponse);
+            }
+        };
+        let FrontendReply {
+            response: ts_response,
+            chunks,
+        } = reply;
+
+        // A frontend that never finishes the body does not keep it around.
+        if let Some(chunks) = &chunks {
+            let deadline = chunks.deadline;
+            let senders = self.chunks.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep_until(deadline).await;
+                senders.remove(&request_id);
+            });
+        }
+
+        // Never let a bogus status from the frontend turn into a success.
+        match frontend_status(ts_response.status) {
+            Some(status) => Ok(WalletResponse {
+                status,
+                body: match chunks {
+                    Some(chunks) => WalletBody::Chunked(chunks),
+                    None => WalletBody::Full(ts_response.body),
+                },
+            }),
+            None => {
+                self.chunks.remove(&request_id);
+                eprintln!(
+                    "Frontend returned invalid status {} for request {}",
+                    ts_response.status, request_id
+                );
+                Err(BridgeError::InvalidStatus(ts_response.status))
+            }
+        }
+    }
+}
+
+/// Removes a request from the pending map once nobody waits for it.
+struct PendingGuard<'a> {
+    bridge: &'a Bridge,
+    request_id: u64,
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.bridge.requests.remove(&self.request_id);
+        // Already gone if the frontend answered.
+        if self.bridge.pending.remove(&self.request_id).is_some() {
+            self.bridge.pending_changed();
+        }
+    }
+}
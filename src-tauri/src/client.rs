@@ -0,0 +1,133 @@
+//! A small client for the running wallet's local API, used by the companion
+//! binaries.
+//!
+//! On Linux the private Unix socket is preferred when it exists, so callers
+//! are identified by their process credentials; otherwise requests go to
+//! the TCP listener.
+
+use std::{fmt, io};
+
+use hyper::{
+    body::Bytes,
+    client::conn,
+    header::{CONTENT_TYPE, HOST},
+    Body, Request, StatusCode,
+};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+
+/// Where the wallet's TCP listener runs.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:3321";
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be built, e.g. because of a bad header value.
+    InvalidRequest(hyper::http::Error),
+    /// The wallet is not running, or not reachable.
+    Connect(io::Error),
+    Http(hyper::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::InvalidRequest(e) => write!(f, "invalid request: {}", e),
+            ClientError::Connect(e) => write!(f, "could not reach the wallet: {}", e),
+            ClientError::Http(e) => write!(f, "request to the wallet failed: {}", e),
+        }
+    }
+}
+
+impl From<hyper::Error> for ClientError {
+    fn from(e: hyper::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
+
+/// A response from the wallet.
+#[derive(Debug, Clone)]
+pub struct ClientResponse {
+    pub status: StatusCode,
+    pub body: Bytes,
+}
+
+#[derive(Debug, Clone)]
+pub struct BridgeClient {
+    addr: String,
+    /// Only Linux has the Unix socket transport.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    use_socket: bool,
+    headers: Vec<(String, String)>,
+}
+
+impl Default for BridgeClient {
+    fn default() -> Self {
+        Self {
+            addr: DEFAULT_ADDR.to_string(),
+            use_socket: true,
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl BridgeClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Talk to the TCP listener at `addr` (`host:port`) only.
+    pub fn with_addr(mut self, addr: impl Into<String>) -> Self {
+        self.addr = addr.into();
+        self.use_socket = false;
+        self
+    }
+
+    /// Send `name: value` with every request, e.g. an `Origin`.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// POST a JSON `body` to `path`.
+    pub async fn post(&self, path: &str, body: String) -> Result<ClientResponse, ClientError> {
+        let mut request = Request::post(path)
+            .header(HOST, self.addr.as_str())
+            .header(CONTENT_TYPE, "application/json");
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        let request = request
+            .body(Body::from(body))
+            .map_err(ClientError::InvalidRequest)?;
+
+        #[cfg(target_os = "linux")]
+        if self.use_socket {
+            if let Some(path) = crate::uds::socket_path().filter(|p| p.exists()) {
+                let stream = tokio::net::UnixStream::connect(path)
+                    .await
+                    .map_err(ClientError::Connect)?;
+                return send(stream, request).await;
+            }
+        }
+        let stream = TcpStream::connect(&self.addr)
+            .await
+            .map_err(ClientError::Connect)?;
+        send(stream, request).await
+    }
+}
+
+async fn send<S>(stream: S, request: Request<Body>) -> Result<ClientResponse, ClientError>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = conn::handshake(stream).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Connection to the wallet failed: {}", e);
+        }
+    });
+    let response = sender.send_request(request).await?;
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await?;
+    Ok(ClientResponse { status, body })
+}
@@ -0,0 +1,151 @@
+//! De-duplication of identical concurrent reads.
+//!
+//! When several clients of one app ask for the same idempotent read at the
+//! same moment, only the first request is sent to the wallet and every
+//! waiter gets its answer. Successful answers that can never change, such
+//! as the header at a given height, are also kept for a short while.
+
+use std::time::{Duration, Instant};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use hyper::StatusCode;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use crate::brc100::Method;
+
+/// Reads whose identical in-flight requests share one wallet call.
+pub const COALESCED_METHODS: [Method; 5] = [
+    Method::GetHeight,
+    Method::GetHeaderForHeight,
+    Method::GetNetwork,
+    Method::GetVersion,
+    Method::IsAuthenticated,
+];
+
+/// Reads whose successful answers never change and may be cached.
+pub const CACHED_METHODS: [Method; 1] = [Method::GetHeaderForHeight];
+
+/// Expired cache entries are only swept once the cache grows past this.
+const CACHE_SWEEP_THRESHOLD: usize = 256;
+
+/// A complete answer that can be handed to several waiters.
+#[derive(Debug, Clone)]
+pub struct SharedResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+/// A request body in a form where equivalent JSON compares equal: object
+/// keys sorted, whitespace removed. Non-JSON bodies are only trimmed.
+pub fn canonical_body(body: &str) -> String {
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) => value.to_string(),
+        Err(_) => body.trim().to_string(),
+    }
+}
+
+pub struct ReadCoalescer {
+    in_flight: DashMap<String, broadcast::Sender<SharedResponse>>,
+    cache: DashMap<String, (Instant, SharedResponse)>,
+    ttl: Duration,
+}
+
+/// How a read should be answered.
+pub enum Join<'a> {
+    /// A cached answer is still fresh.
+    Cached(SharedResponse),
+    /// An identical request is in flight; wait for its answer. The channel
+    /// closes without one if that request fails.
+    Wait(broadcast::Receiver<SharedResponse>),
+    /// This request goes to the wallet and shares its answer.
+    Lead(Leader<'a>),
+}
+
+/// The request answering for everyone waiting on `key`. Dropping it without
+/// calling [`Leader::finish`] releases the waiters empty-handed.
+pub struct Leader<'a> {
+    coalescer: &'a ReadCoalescer,
+    key: String,
+    tx: broadcast::Sender<SharedResponse>,
+    cacheable: bool,
+}
+
+impl ReadCoalescer {
+    /// Coalesce reads, caching immutable answers for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            in_flight: DashMap::new(),
+            cache: DashMap::new(),
+            ttl,
+        }
+    }
+
+    /// The key identifying identical requests, or `None` if `method` is not
+    /// coalesced. Bodies are compared in canonical form; different origins
+    /// never share answers.
+    pub fn key(method: Method, origin: Option<&str>, body: &str) -> Option<String> {
+        if !COALESCED_METHODS.contains(&method) {
+            return None;
+        }
+        Some(format!(
+            "{}\n{}\n{}",
+            origin.unwrap_or_default(),
+            method.name(),
+            canonical_body(body)
+        ))
+    }
+
+    pub fn join(&self, method: Method, key: String) -> Join<'_> {
+        if let Some(entry) = self.cache.get(&key) {
+            let (stored_at, response) = entry.value();
+            if stored_at.elapsed() < self.ttl {
+                return Join::Cached(response.clone());
+            }
+        }
+
+        match self.in_flight.entry(key.clone()) {
+            Entry::Occupied(entry) => Join::Wait(entry.get().subscribe()),
+            Entry::Vacant(entry) => {
+                let tx = broadcast::channel(1).0;
+                entry.insert(tx.clone());
+                Join::Lead(Leader {
+                    coalescer: self,
+                    key,
+                    tx,
+                    cacheable: CACHED_METHODS.contains(&method),
+                })
+            }
+        }
+    }
+
+    fn store(&self, key: String, response: SharedResponse) {
+        if self.cache.len() >= CACHE_SWEEP_THRESHOLD {
+            self.cache
+                .retain(|_, (stored_at, _)| stored_at.elapsed() < self.ttl);
+        }
+        self.cache.insert(key, (Instant::now(), response));
+    }
+}
+
+impl Leader<'_> {
+    /// Hand `response` to every waiter, and cache it if it is immutable.
+    pub fn finish(self, response: &SharedResponse) {
+        // Stop taking waiters before answering so none can miss the answer.
+        self.coalescer.in_flight.remove(&self.key);
+        if self.cacheable && response.status.is_success() && !self.coalescer.ttl.is_zero() {
+            self.coalescer.store(self.key.clone(), response.clone());
+        }
+        // Nobody waiting is fine.
+        _ = self.tx.send(response.clone());
+    }
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        // Only still registered if `finish` was never called.
+        self.coalescer
+            .in_flight
+            .remove_if(&self.key, |_, tx| tx.same_channel(&self.tx));
+    }
+}
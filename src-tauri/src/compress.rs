@@ -0,0 +1,99 @@
+//! Response compression negotiated from `Accept-Encoding`.
+//!
+//! Bodies are compressed as a stream, so chunked wallet responses are
+//! encoded and sent part by part rather than buffered first.
+
+use std::io;
+
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder};
+use futures_util::{stream, Stream, StreamExt};
+use hyper::{body::Bytes, Body};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use crate::bridge::WalletBody;
+
+/// Bodies shorter than this are sent as is; compressing them saves little.
+pub const MIN_COMPRESS_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// The `Content-Encoding` token.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Pick the encoding to use for a client sending `accept_encoding`,
+/// preferring brotli when both are equally acceptable.
+pub fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let mut brotli = None;
+    let mut gzip = None;
+    let mut any = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let coding = parts.next().unwrap_or_default().trim();
+        let quality = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if coding.eq_ignore_ascii_case("br") {
+            brotli = Some(quality);
+        } else if coding.eq_ignore_ascii_case("gzip") || coding.eq_ignore_ascii_case("x-gzip") {
+            gzip = Some(quality);
+        } else if coding == "*" {
+            any = Some(quality);
+        }
+    }
+
+    // Codings not listed explicitly fall back to the wildcard, if any.
+    let brotli = brotli.or(any).unwrap_or(0.0);
+    let gzip = gzip.or(any).unwrap_or(0.0);
+    if brotli <= 0.0 && gzip <= 0.0 {
+        None
+    } else if brotli >= gzip {
+        Some(Encoding::Brotli)
+    } else {
+        Some(Encoding::Gzip)
+    }
+}
+
+/// Turn a wallet body into an HTTP body, compressing it with `encoding` when
+/// that is worthwhile. Returns the encoding actually applied.
+pub fn response_body(body: WalletBody, encoding: Option<Encoding>) -> (Body, Option<Encoding>) {
+    let parts = match body {
+        WalletBody::Full(body) => match encoding {
+            Some(_) if body.len() >= MIN_COMPRESS_SIZE => {
+                stream::once(async move { Ok(Bytes::from(body)) }).boxed()
+            }
+            _ => return (Body::from(body), None),
+        },
+        WalletBody::Chunked(chunks) => stream::unfold(chunks, |mut chunks| async move {
+            let chunk = chunks.next().await?;
+            Some((chunk.map(Bytes::from), chunks))
+        })
+        .boxed(),
+    };
+    match encoding {
+        Some(encoding) => (encode(encoding, parts), Some(encoding)),
+        None => (Body::wrap_stream(parts), None),
+    }
+}
+
+fn encode<S>(encoding: Encoding, parts: S) -> Body
+where
+    S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+{
+    let reader = StreamReader::new(parts);
+    match encoding {
+        Encoding::Brotli => Body::wrap_stream(ReaderStream::new(BrotliEncoder::new(reader))),
+        Encoding::Gzip => Body::wrap_stream(ReaderStream::new(GzipEncoder::new(reader))),
+    }
+}
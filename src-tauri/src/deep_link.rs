@@ -0,0 +1,244 @@
+//! Deep links into the wallet.
+//!
+//! The desktop entry registers the app as the handler for `metanet:` and
+//! `web+bsv:` URLs, so the link arrives as a command-line argument. Links
+//! are validated here before the frontend sees them:
+//!
+//! - `metanet:pay?to=<address or paymail>&amount=<satoshis>[&memo=<text>][&origin=<url>]`
+//! - `metanet:identity?origin=<url>[&fields=<name>,<name>...]`
+//! - `metanet:connect?origin=<url>`
+//!
+//! Both `metanet:pay?...` and `metanet://pay?...` forms are accepted.
+
+use std::fmt;
+
+use serde::Serialize;
+use url::Url;
+
+/// URL schemes the app handles.
+pub const SCHEMES: [&str; 2] = ["metanet", "web+bsv"];
+
+/// Longest link accepted.
+pub const MAX_LINK_LENGTH: usize = 4096;
+/// Longest payment memo accepted.
+pub const MAX_MEMO_LENGTH: usize = 256;
+/// Most identity fields one link may ask for.
+pub const MAX_IDENTITY_FIELDS: usize = 16;
+
+/// All the satoshis there will ever be.
+const MAX_SATOSHIS: u64 = 21_000_000 * 100_000_000;
+
+/// A validated deep link, as sent to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DeepLink {
+    /// Pay `satoshis` to an address or paymail.
+    Payment {
+        to: String,
+        satoshis: u64,
+        memo: Option<String>,
+        /// The app asking for the payment, if it said.
+        origin: Option<String>,
+    },
+    /// An app asks for identity details.
+    Identity { origin: String, fields: Vec<String> },
+    /// An app asks to be connected to the wallet.
+    Connect { origin: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeepLinkError {
+    TooLong,
+    Malformed(String),
+    UnsupportedScheme(String),
+    UnknownAction(String),
+    MissingParameter(&'static str),
+    DuplicateParameter(String),
+    InvalidParameter {
+        name: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for DeepLinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeepLinkError::TooLong => {
+                write!(f, "link is longer than {} characters", MAX_LINK_LENGTH)
+            }
+            DeepLinkError::Malformed(e) => write!(f, "malformed link: {}", e),
+            DeepLinkError::UnsupportedScheme(s) => write!(f, "unsupported scheme `{}`", s),
+            DeepLinkError::UnknownAction(a) => write!(f, "unknown action `{}`", a),
+            DeepLinkError::MissingParameter(p) => write!(f, "missing `{}` parameter", p),
+            DeepLinkError::DuplicateParameter(p) => write!(f, "`{}` is given more than once", p),
+            DeepLinkError::InvalidParameter { name, reason } => {
+                write!(f, "invalid `{}`: {}", name, reason)
+            }
+        }
+    }
+}
+
+/// Whether a command-line argument looks like one of our links.
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.split_once(':').is_some_and(|(scheme, _)| {
+        SCHEMES
+            .iter()
+            .any(|s| s.eq_ignore_ascii_case(scheme.trim()))
+    })
+}
+
+/// Parse and validate a deep link.
+pub fn parse(link: &str) -> Result<DeepLink, DeepLinkError> {
+    let link = link.trim();
+    if link.len() > MAX_LINK_LENGTH {
+        return Err(DeepLinkError::TooLong);
+    }
+    let url = Url::parse(link).map_err(|e| DeepLinkError::Malformed(e.to_string()))?;
+    if !SCHEMES.contains(&url.scheme()) {
+        return Err(DeepLinkError::UnsupportedScheme(url.scheme().to_string()));
+    }
+
+    // `metanet://pay` puts the action in the host, `metanet:pay` in the path.
+    let action = match url.host_str() {
+        Some(host) => host.to_ascii_lowercase(),
+        None => url.path().trim_matches('/').to_ascii_lowercase(),
+    };
+    let params = Params::new(&url)?;
+
+    match action.as_str() {
+        "pay" => {
+            let to = params.required("to")?;
+            if !is_address(to) && !is_paymail(to) {
+                return Err(DeepLinkError::InvalidParameter {
+                    name: "to",
+                    reason: "not an address or paymail",
+                });
+            }
+            let satoshis = params
+                .required("amount")?
+                .parse::<u64>()
+                .ok()
+                .filter(|&amount| amount > 0 && amount <= MAX_SATOSHIS)
+                .ok_or(DeepLinkError::InvalidParameter {
+                    name: "amount",
+                    reason: "must be a positive whole number of satoshis",
+                })?;
+            let memo = params.optional("memo").map(str::to_string);
+            if memo.as_ref().is_some_and(|m| {
+                m.chars().count() > MAX_MEMO_LENGTH || m.chars().any(char::is_control)
+            }) {
+                return Err(DeepLinkError::InvalidParameter {
+                    name: "memo",
+                    reason: "too long or contains control characters",
+                });
+            }
+            let origin = params.optional("origin").map(app_origin).transpose()?;
+            Ok(DeepLink::Payment {
+                to: to.to_string(),
+                satoshis,
+                memo,
+                origin,
+            })
+        }
+        "identity" => {
+            let origin = app_origin(params.required("origin")?)?;
+            let fields: Vec<String> = params
+                .optional("fields")
+                .map(|fields| {
+                    fields
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|f| !f.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if fields.len() > MAX_IDENTITY_FIELDS
+                || !fields.iter().all(|f| {
+                    f.len() <= 64 && f.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                })
+            {
+                return Err(DeepLinkError::InvalidParameter {
+                    name: "fields",
+                    reason: "must be a short list of field names",
+                });
+            }
+            Ok(DeepLink::Identity { origin, fields })
+        }
+        "connect" => Ok(DeepLink::Connect {
+            origin: app_origin(params.required("origin")?)?,
+        }),
+        _ => Err(DeepLinkError::UnknownAction(action)),
+    }
+}
+
+/// The query parameters of a link. Repeating a parameter is rejected rather
+/// than guessing which value was meant.
+struct Params(Vec<(String, String)>);
+
+impl Params {
+    fn new(url: &Url) -> Result<Self, DeepLinkError> {
+        let mut params: Vec<(String, String)> = Vec::new();
+        for (name, value) in url.query_pairs() {
+            if params.iter().any(|(n, _)| *n == name) {
+                return Err(DeepLinkError::DuplicateParameter(name.into_owned()));
+            }
+            params.push((name.into_owned(), value.trim().to_string()));
+        }
+        Ok(Self(params))
+    }
+
+    fn optional(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn required(&self, name: &'static str) -> Result<&str, DeepLinkError> {
+        self.optional(name)
+            .ok_or(DeepLinkError::MissingParameter(name))
+    }
+}
+
+/// The `scheme://host[:port]` origin of an app's `http(s)` URL.
+fn app_origin(value: &str) -> Result<String, DeepLinkError> {
+    let invalid = DeepLinkError::InvalidParameter {
+        name: "origin",
+        reason: "must be an http or https URL",
+    };
+    let url = Url::parse(value).map_err(|_| invalid.clone())?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(invalid);
+    }
+    Ok(url.origin().ascii_serialization())
+}
+
+/// A Base58 P2PKH or P2SH address. The checksum is left to the wallet.
+fn is_address(value: &str) -> bool {
+    const BASE58: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    (26..=35).contains(&value.len()) && value.chars().all(|c| BASE58.contains(c))
+}
+
+/// A paymail: `alias@domain.tld`.
+fn is_paymail(value: &str) -> bool {
+    let Some((alias, domain)) = value.split_once('@') else {
+        return false;
+    };
+    !alias.is_empty()
+        && alias.len() <= 64
+        && alias
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"._-+".contains(&b))
+        && domain.len() <= 253
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
@@ -0,0 +1,246 @@
+//! Coalescing the frontend's requests to bring the wallet forward.
+//!
+//! Apps firing many requests at once make the frontend ask for focus once
+//! per request. Each ask holds focus until it is released; the first one
+//! grabs focus after a short delay that soaks up the rest of the burst, and
+//! focus is given back shortly after the last one is released, unless
+//! another ask comes in first. An ask that is never released only holds
+//! focus for [`FocusConfig::hold_timeout`]. Showing the wallet for the user,
+//! from the tray or a notification, goes through the same loop without
+//! holding focus, and releases do not call it off. The platform work, which may block, is left to a
+//! [`FocusDriver`].
+
+use std::{sync::Arc, time::Duration};
+
+use serde::Serialize;
+use tokio::{
+    sync::{mpsc, watch},
+    time::{sleep_until, Instant},
+};
+
+/// Brings the wallet forward and moves it out of the way again.
+pub trait FocusDriver: Send + Sync + 'static {
+    /// Show and focus the wallet. May block.
+    fn grab(&self);
+    /// Hand focus back to what the user was doing. May block.
+    fn relinquish(&self);
+    /// Whether the wallet has focus right now.
+    fn is_focused(&self) -> bool;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FocusConfig {
+    /// How long to wait for more asks before grabbing focus.
+    pub grab_delay: Duration,
+    /// How long to keep focus after the last ask is released.
+    pub relinquish_delay: Duration,
+    /// How long focus is held without a new ask before the holders are
+    /// assumed gone and focus is given back.
+    pub hold_timeout: Duration,
+}
+
+impl Default for FocusConfig {
+    fn default() -> Self {
+        Self {
+            grab_delay: Duration::from_millis(50),
+            relinquish_delay: Duration::from_millis(400),
+            hold_timeout: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusState {
+    /// Nobody needs the wallet in front.
+    Idle,
+    /// Focus is about to be grabbed.
+    Grabbing,
+    /// The wallet was brought forward and is held there.
+    Focused,
+    /// Focus is about to be given back.
+    Relinquishing,
+}
+
+enum FocusMsg {
+    Request,
+    Release,
+    Show,
+}
+
+/// Handle for asking the focus loop to grab or give back focus.
+pub struct Focus {
+    tx: mpsc::UnboundedSender<FocusMsg>,
+    state: watch::Receiver<FocusState>,
+}
+
+impl Focus {
+    /// A handle and the loop serving it, which has to be spawned on the
+    /// async runtime.
+    pub fn new(config: FocusConfig) -> (Self, FocusLoop) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (state_tx, state) = watch::channel(FocusState::Idle);
+        let focus_loop = FocusLoop {
+            config,
+            rx,
+            state: state_tx,
+        };
+        (Self { tx, state }, focus_loop)
+    }
+
+    /// Ask for the wallet to be in front until the matching [`release`].
+    ///
+    /// [`release`]: Focus::release
+    pub fn request(&self) {
+        // Only fails once the loop is gone, when there is nothing to do.
+        _ = self.tx.send(FocusMsg::Request);
+    }
+
+    /// Release an earlier [`request`](Focus::request). Without one, focus
+    /// is given back right away.
+    pub fn release(&self) {
+        _ = self.tx.send(FocusMsg::Release);
+    }
+
+    /// Bring the wallet forward because the user asked for it. Nothing is
+    /// held, so focus stays until the user moves on.
+    pub fn show(&self) {
+        _ = self.tx.send(FocusMsg::Show);
+    }
+
+    pub fn state(&self) -> FocusState {
+        *self.state.borrow()
+    }
+
+    /// Follow the focus state.
+    pub fn watch(&self) -> watch::Receiver<FocusState> {
+        self.state.clone()
+    }
+}
+
+/// The state machine behind a [`Focus`] handle.
+pub struct FocusLoop {
+    config: FocusConfig,
+    rx: mpsc::UnboundedReceiver<FocusMsg>,
+    state: watch::Sender<FocusState>,
+}
+
+impl FocusLoop {
+    /// Serve the handle until it is dropped.
+    pub async fn run<D: FocusDriver>(mut self, driver: D) {
+        let driver = Arc::new(driver);
+        let mut holders: usize = 0;
+        // Whether the user asked to see the wallet since focus was last
+        // grabbed, which releases must not call off.
+        let mut shown = false;
+        let mut state = FocusState::Idle;
+        let mut deadline: Option<Instant> = None;
+
+        loop {
+            let msg = match deadline {
+                Some(at) => tokio::select! {
+                    msg = self.rx.recv() => match msg {
+                        Some(msg) => Some(msg),
+                        None => break,
+                    },
+                    _ = sleep_until(at) => None,
+                },
+                None => match self.rx.recv().await {
+                    Some(msg) => Some(msg),
+                    None => break,
+                },
+            };
+
+            match msg {
+                Some(msg @ (FocusMsg::Request | FocusMsg::Show)) => {
+                    match msg {
+                        FocusMsg::Request => holders += 1,
+                        _ => shown = true,
+                    }
+                    match state {
+                        FocusState::Idle => {
+                            state = FocusState::Grabbing;
+                            deadline = Some(Instant::now() + self.config.grab_delay);
+                        }
+                        // Still in front; no need to grab again.
+                        FocusState::Relinquishing => {
+                            state = FocusState::Focused;
+                            deadline = self.hold_deadline(holders);
+                        }
+                        // The user may have moved the wallet away since.
+                        FocusState::Focused if !driver.is_focused() => {
+                            state = FocusState::Grabbing;
+                            deadline = Some(Instant::now() + self.config.grab_delay);
+                        }
+                        FocusState::Focused => deadline = self.hold_deadline(holders),
+                        FocusState::Grabbing => {}
+                    }
+                }
+                // A release nobody asked for gives focus back, as the
+                // command always did.
+                Some(FocusMsg::Release) if holders == 0 => match state {
+                    FocusState::Idle | FocusState::Focused => {
+                        state = FocusState::Relinquishing;
+                        deadline = Some(Instant::now() + self.config.relinquish_delay);
+                    }
+                    FocusState::Grabbing => {
+                        state = FocusState::Idle;
+                        deadline = None;
+                        shown = false;
+                    }
+                    FocusState::Relinquishing => {}
+                },
+                Some(FocusMsg::Release) => {
+                    holders -= 1;
+                    if holders == 0 {
+                        match state {
+                            // The burst was over before focus was grabbed.
+                            FocusState::Grabbing if !shown => {
+                                state = FocusState::Idle;
+                                deadline = None;
+                            }
+                            FocusState::Focused => {
+                                state = FocusState::Relinquishing;
+                                deadline = Some(Instant::now() + self.config.relinquish_delay);
+                            }
+                            FocusState::Grabbing | FocusState::Idle | FocusState::Relinquishing => {
+                            }
+                        }
+                    }
+                }
+                // The deadline passed.
+                None => {
+                    deadline = None;
+                    let driver = driver.clone();
+                    match state {
+                        FocusState::Grabbing => {
+                            _ = tokio::task::spawn_blocking(move || driver.grab()).await;
+                            shown = false;
+                            state = FocusState::Focused;
+                            deadline = self.hold_deadline(holders);
+                        }
+                        // Whoever held focus for this long is not coming
+                        // back to release it.
+                        FocusState::Focused => {
+                            eprintln!("{} focus holder(s) never released focus", holders);
+                            holders = 0;
+                            _ = tokio::task::spawn_blocking(move || driver.relinquish()).await;
+                            state = FocusState::Idle;
+                        }
+                        FocusState::Relinquishing => {
+                            _ = tokio::task::spawn_blocking(move || driver.relinquish()).await;
+                            state = FocusState::Idle;
+                        }
+                        FocusState::Idle => {}
+                    }
+                }
+            }
+            self.state.send_replace(state);
+        }
+    }
+
+    /// When held focus is given up on, if anyone holds it.
+    fn hold_deadline(&self, holders: usize) -> Option<Instant> {
+        (holders > 0).then(|| Instant::now() + self.config.hold_timeout)
+    }
+}
@@ -0,0 +1,194 @@
+//! `Idempotency-Key` handling for spending calls.
+//!
+//! An app retrying `/createAction` after a timeout must not end up with two
+//! transactions. When a spending request carries an `Idempotency-Key`, the
+//! wallet's answer is remembered for a configurable window and replayed to
+//! retries with the same key; a retry arriving while the first attempt is
+//! still with the wallet waits for that attempt's answer. The first attempt
+//! runs on its own, so its answer is still recorded if the client that sent
+//! it gives up.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use tokio::sync::broadcast;
+
+use crate::brc100::Method;
+use crate::coalesce::{canonical_body, SharedResponse};
+
+/// The request header naming the idempotency key.
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Longest key accepted.
+pub const MAX_KEY_LENGTH: usize = 255;
+
+/// Expired answers are only swept once this many keys are remembered.
+const SWEEP_THRESHOLD: usize = 256;
+
+/// The `Idempotency-Key` of a request, if it sent one. Keys must be 1 to
+/// [`MAX_KEY_LENGTH`] visible ASCII characters.
+pub fn idempotency_key(headers: &[(String, String)]) -> Option<Result<String, String>> {
+    let (_, key) = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER))?;
+    let key = key.trim();
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        return Some(Err(format!(
+            "Idempotency-Key must be 1 to {} characters",
+            MAX_KEY_LENGTH
+        )));
+    }
+    if !key.bytes().all(|b| b.is_ascii_graphic()) {
+        return Some(Err(
+            "Idempotency-Key must only contain visible ASCII characters".to_string(),
+        ));
+    }
+    Some(Ok(key.to_string()))
+}
+
+enum Record {
+    InFlight {
+        fingerprint: String,
+        tx: broadcast::Sender<SharedResponse>,
+    },
+    Done {
+        fingerprint: String,
+        stored_at: Instant,
+        response: SharedResponse,
+    },
+}
+
+pub struct IdempotencyStore {
+    records: DashMap<String, Record>,
+    window: Duration,
+}
+
+/// What to do with a request carrying an idempotency key.
+pub enum Claim {
+    /// An earlier attempt already got this answer.
+    Replay(SharedResponse),
+    /// An earlier attempt is still with the wallet. The channel closes
+    /// without an answer if that attempt fails.
+    Wait(broadcast::Receiver<SharedResponse>),
+    /// The key was used before for a different request.
+    Conflict,
+    /// This is the first attempt; send it and record the answer.
+    Run(Reservation),
+}
+
+/// The first attempt for a key. Dropping it without calling
+/// [`Reservation::finish`] forgets the key, so a later retry runs again.
+pub struct Reservation {
+    store: Arc<IdempotencyStore>,
+    key: String,
+    tx: broadcast::Sender<SharedResponse>,
+}
+
+impl IdempotencyStore {
+    /// Remember answers for `window`. A zero window disables replays.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            records: DashMap::new(),
+            window,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+
+    /// Look up `key` for a `method` call from `origin`. Keys are scoped to
+    /// the origin and method, and a retry must send the same arguments.
+    pub fn claim(
+        self: &Arc<Self>,
+        method: Method,
+        origin: Option<&str>,
+        key: &str,
+        body: &str,
+    ) -> Claim {
+        if self.records.len() >= SWEEP_THRESHOLD {
+            self.records.retain(|_, record| match record {
+                Record::InFlight { .. } => true,
+                Record::Done { stored_at, .. } => stored_at.elapsed() < self.window,
+            });
+        }
+
+        let scoped_key = format!("{}\n{}\n{}", origin.unwrap_or_default(), method.name(), key);
+        let fingerprint = canonical_body(body);
+        let tx = broadcast::channel(1).0;
+        let in_flight = Record::InFlight {
+            fingerprint: fingerprint.clone(),
+            tx: tx.clone(),
+        };
+        match self.records.entry(scoped_key.clone()) {
+            Entry::Occupied(mut entry) => match entry.get() {
+                Record::InFlight {
+                    fingerprint: first,
+                    tx,
+                } => {
+                    return if *first == fingerprint {
+                        Claim::Wait(tx.subscribe())
+                    } else {
+                        Claim::Conflict
+                    };
+                }
+                Record::Done {
+                    fingerprint: first,
+                    stored_at,
+                    response,
+                } if stored_at.elapsed() < self.window => {
+                    return if *first == fingerprint {
+                        Claim::Replay(response.clone())
+                    } else {
+                        Claim::Conflict
+                    };
+                }
+                // The remembered answer expired; this is a new call.
+                Record::Done { .. } => {
+                    entry.insert(in_flight);
+                }
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(in_flight);
+            }
+        }
+        Claim::Run(Reservation {
+            store: self.clone(),
+            key: scoped_key,
+            tx,
+        })
+    }
+}
+
+impl Reservation {
+    /// Remember the wallet's answer and hand it to any waiting retries.
+    pub fn finish(self, response: &SharedResponse) {
+        if let Some(mut record) = self.store.records.get_mut(&self.key) {
+            if let Record::InFlight { fingerprint, tx } = record.value() {
+                if !tx.same_channel(&self.tx) {
+                    return;
+                }
+                *record = Record::Done {
+                    fingerprint: fingerprint.clone(),
+                    stored_at: Instant::now(),
+                    response: response.clone(),
+                };
+            }
+        }
+        // Nobody waiting is fine.
+        _ = self.tx.send(response.clone());
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        // Only still in flight if `finish` was never called.
+        self.store.records.remove_if(
+            &self.key,
+            |_, record| matches!(record, Record::InFlight { tx, .. } if tx.same_channel(&self.tx)),
+        );
+    }
+}
@@ -1 +1,36 @@
-// useless file
+//! Shared types for the Metanet Desktop wallet bridge.
+//!
+//! Everything in here is independent of Tauri so it can be reused by the
+//! desktop binary, command-line tooling and tests alike.
+
+pub mod approval;
+pub mod autostart;
+pub mod brc100;
+pub mod bridge;
+pub mod client;
+pub mod coalesce;
+pub mod compress;
+pub mod deep_link;
+pub mod focus;
+pub mod idempotency;
+#[cfg(target_os = "linux")]
+pub mod logind_idle;
+pub mod native_messaging;
+pub mod notifications;
+pub mod origin;
+pub mod presence;
+pub mod rpc;
+pub mod schedule;
+pub mod server;
+pub mod settings;
+pub mod sse;
+pub mod tls;
+pub mod tray;
+#[cfg(target_os = "linux")]
+pub mod uds;
+pub mod window_state;
+pub mod ws;
+#[cfg(target_os = "linux")]
+pub mod x11_focus;
+#[cfg(target_os = "linux")]
+pub mod x11_idle;
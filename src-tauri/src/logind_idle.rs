@@ -0,0 +1,61 @@
+//! How long the user has been away, from systemd-logind, where the X
+//! server can't be asked (Wayland sessions).
+//!
+//! The desktop marks the login session idle once nobody has touched the
+//! keyboard or mouse for its own idle delay, five minutes by default on
+//! GNOME, and logind records since when. Shorter absences read as no idle
+//! time at all, so the user counts as idle later than on X11, never
+//! earlier.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use zbus::Connection;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    // Read afresh on every poll rather than followed through signals.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn idle_hint(&self) -> zbus::Result<bool>;
+
+    /// Microseconds since the Unix epoch.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn idle_since_hint(&self) -> zbus::Result<u64>;
+}
+
+/// The app's login session on the system bus.
+pub struct IdleHint {
+    proxy: SessionProxy<'static>,
+}
+
+impl IdleHint {
+    /// Connect to the session the app runs in. `None` without logind or
+    /// outside a login session.
+    pub async fn connect() -> Option<Self> {
+        let connection = Connection::system().await.ok()?;
+        let proxy = SessionProxy::new(&connection).await.ok()?;
+        proxy.idle_hint().await.ok()?;
+        Some(Self { proxy })
+    }
+
+    /// Time since the session went idle, zero while it isn't.
+    pub async fn get(&self) -> Option<Duration> {
+        let idle = self.proxy.idle_hint().await.ok()?;
+        let since = self.proxy.idle_since_hint().await.ok()?;
+        Some(idle_time(idle, since, SystemTime::now()))
+    }
+}
+
+/// The idle time at `now` of a session whose idle hint is `idle`, set
+/// `since` microseconds after the Unix epoch.
+pub fn idle_time(idle: bool, since: u64, now: SystemTime) -> Duration {
+    if !idle {
+        return Duration::ZERO;
+    }
+    let since = UNIX_EPOCH + Duration::from_micros(since);
+    // A clock set back since counts as no time passed.
+    now.duration_since(since).unwrap_or_default()
+}
@@ -8,29 +8,52 @@ use std::{
     convert::Infallible,
     net::SocketAddr,
     sync::{
-        atomic::{AtomicU64, Ordering},
-        Arc,
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
     },
+    time::Duration,
 };
 
 // Third-party imports.
-use dashmap::DashMap;
 use hyper::{
     service::{make_service_fn, service_fn},
-    Body, Request, Response, Server, StatusCode,
+    Body, Request, Server,
 };
-use serde::{Deserialize, Serialize};
-use tauri::{Emitter, Listener, Window};
-use tokio::sync::oneshot;
+use metanet_desktop::{
+    approval,
+    autostart::MINIMIZED_ARG,
+    bridge::{Bridge, EVENT_LOCKED},
+    deep_link::{self, DeepLink},
+    focus::{Focus, FocusConfig, FocusDriver},
+    presence::{self, Presence, PresenceEvent, WindowState},
+    server,
+    settings::Settings,
+    tls::{self, LocalCa},
+    tray,
+    window_state::{MonitorArea, Rect, WindowState as SavedGeometry},
+};
+#[cfg(target_os = "linux")]
+use metanet_desktop::{
+    autostart,
+    logind_idle::IdleHint,
+    notifications::{self, Notifier, RateLimiter},
+    x11_focus::{self, ActiveWindow},
+    x11_idle::IdleTime,
+};
+use tauri::{
+    image::Image,
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
+    tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent},
+    Emitter, Listener, Monitor, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder,
+    Window, WindowEvent,
+};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 
 use std::path::{Path, PathBuf};
 use tauri::{command, AppHandle, Manager};
 
 use std::fs;
 
-// Import the Tauri plugins
-use tauri_plugin_dialog;
-
 // Add a command to save files using the standard Rust fs module
 #[tauri::command]
 async fn save_file(path: String, contents: Vec<u8>) -> Result<(), String> {
@@ -51,39 +74,19 @@ async fn save_file(path: String, contents: Vec<u8>) -> Result<(), String> {
 
 static MAIN_WINDOW_NAME: &str = "main";
 
-/// Payload sent from Rust to the frontend for each HTTP request.
-#[derive(Serialize)]
-struct HttpRequestEvent {
-    method: String,
-    path: String,
-    headers: Vec<(String, String)>,
-    body: String,
-    request_id: u64,
-}
-
-/// Expected payload sent back from the frontend.
-#[derive(Deserialize, Debug)]
-struct TsResponse {
-    request_id: u64,
-    status: u16,
-    body: String,
-}
-
-/// A type alias for our concurrent map of pending responses.
-type PendingMap = DashMap<u64, oneshot::Sender<TsResponse>>;
-
 #[cfg(target_os = "macos")]
 use once_cell::sync::Lazy;
 /// -----
 /// Tauri COMMANDS for focus management
 /// -----
 
-#[cfg(target_os = "macos")]
-use std::sync::Mutex;
-
 #[cfg(target_os = "macos")]
 static PREV_BUNDLE_ID: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
+/// The X11 window that was active before the wallet took focus.
+#[cfg(target_os = "linux")]
+static PREV_X11_WINDOW: Mutex<Option<u32>> = Mutex::new(None);
+
 #[tauri::command]
 fn is_focused(window: Window) -> bool {
     match window.is_focused() {
@@ -92,8 +95,23 @@ fn is_focused(window: Window) -> bool {
     }
 }
 
+/// Ask for the wallet to be brought forward, until the matching
+/// `relinquish_focus`. Bursts of asks lead to a single focus grab.
 #[tauri::command]
-fn request_focus(window: Window) {
+fn request_focus(focus: tauri::State<'_, Focus>) {
+    focus.request();
+}
+
+/// Release an earlier `request_focus`. Focus is given back shortly after the
+/// last one is released, or right away if none was asked for.
+#[tauri::command]
+fn relinquish_focus(focus: tauri::State<'_, Focus>) {
+    focus.release();
+}
+
+/// Show the window and bring it forward. Blocks for a little while on some
+/// platforms, to make sure it took.
+fn grab_focus(window: Window) {
     #[cfg(target_os = "macos")]
     {
         // Make window visible first - critical for macOS
@@ -171,6 +189,17 @@ fn request_focus(window: Window) {
 
     #[cfg(target_os = "linux")]
     {
+        // 0. Remember the window the user was in, unless it is ours already
+        if x11_focus::x11_session() {
+            match ActiveWindow::connect(None)
+                .and_then(|active| active.get_other(std::process::id()))
+            {
+                Ok(Some(window)) => *PREV_X11_WINDOW.lock().unwrap() = Some(window),
+                Ok(None) => {}
+                Err(e) => eprintln!("(Linux) active window error: {}", e),
+            }
+        }
+
         // First, unminimize the window if it's minimized
         if let Err(e) = window.unminimize() {
             eprintln!("(Linux) unminimize error: {}", e);
@@ -200,13 +229,25 @@ fn request_focus(window: Window) {
 
 /// Attempt to move the window out of the user's way so they can resume
 /// other tasks. The exact behavior (switch/minimize) differs per platform.
-#[tauri::command]
-fn relinquish_focus(window: Window) {
+fn give_back_focus(window: Window) {
     #[cfg(target_os = "linux")]
     {
-        // Minimize the window instead of hiding
-        if let Err(e) = window.minimize() {
-            eprintln!("Linux minimize error: {}", e);
+        // Try to hand focus back to the previous window
+        let prev_window = PREV_X11_WINDOW.lock().unwrap().take();
+        let restored = prev_window.is_some_and(|prev_window| {
+            match ActiveWindow::connect(None).and_then(|active| active.activate(prev_window)) {
+                Ok(activated) => activated,
+                Err(e) => {
+                    eprintln!("Linux failed to re-activate previous window: {}", e);
+                    false
+                }
+            }
+        });
+        // Otherwise minimize the window instead of hiding
+        if !restored {
+            if let Err(e) = window.minimize() {
+                eprintln!("Linux minimize error: {}", e);
+            }
         }
     }
 
@@ -238,6 +279,780 @@ fn relinquish_focus(window: Window) {
     }
 }
 
+/// The persisted user settings, with `launch_at_login` as reported by
+/// [`get_launch_at_login`].
+#[tauri::command]
+fn get_settings(app_handle: AppHandle) -> Result<Settings, String> {
+    let config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| e.to_string())?;
+    let mut settings = Settings::load(&config_dir);
+    settings.launch_at_login = get_launch_at_login(app_handle)?;
+    Ok(settings)
+}
+
+/// Persist new user settings. Transport changes apply on the next start.
+/// `launch_at_login` is left as it is, since only [`set_launch_at_login`]
+/// also installs or removes the autostart entry.
+#[tauri::command]
+fn set_settings(app_handle: AppHandle, mut settings: Settings) -> Result<(), String> {
+    let config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| e.to_string())?;
+    settings.launch_at_login = Settings::load(&config_dir).launch_at_login;
+    app_handle
+        .state::<AutoLock>()
+        .0
+        .store(settings.auto_lock_minutes, Ordering::Relaxed);
+    settings.save(&config_dir).map_err(|e| e.to_string())
+}
+
+/// Whether the app is set to start at login. On Linux this is whether the
+/// XDG autostart entry exists, since the user may have removed it.
+#[tauri::command]
+fn get_launch_at_login(app_handle: AppHandle) -> Result<bool, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let config_dir = app_handle.path().config_dir().map_err(|e| e.to_string())?;
+        Ok(autostart::is_enabled(&config_dir))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let config_dir = app_handle
+            .path()
+            .app_config_dir()
+            .map_err(|e| e.to_string())?;
+        Ok(Settings::load(&config_dir).launch_at_login)
+    }
+}
+
+/// Install or remove the autostart entry and persist the choice, returning
+/// whether the app now starts at login.
+#[tauri::command]
+fn set_launch_at_login(app_handle: AppHandle, enabled: bool) -> Result<bool, String> {
+    #[cfg(target_os = "linux")]
+    {
+        let config_dir = app_handle.path().config_dir().map_err(|e| e.to_string())?;
+        if enabled {
+            let exe = autostart::launcher().map_err(|e| e.to_string())?;
+            autostart::enable(&config_dir, &exe).map_err(|e| e.to_string())?;
+        } else {
+            autostart::disable(&config_dir).map_err(|e| e.to_string())?;
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    if enabled {
+        return Err("Launching at login is only supported on Linux".to_string());
+    }
+
+    let app_config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| e.to_string())?;
+    let mut settings = Settings::load(&app_config_dir);
+    settings.launch_at_login = enabled;
+    settings.save(&app_config_dir).map_err(|e| e.to_string())?;
+    get_launch_at_login(app_handle)
+}
+
+fn local_ca(app_handle: &AppHandle) -> Result<LocalCa, String> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    Ok(LocalCa::new(data_dir.join("tls")))
+}
+
+/// Write the local CA certificate to `path` so the user can choose to trust
+/// it. The CA is created first if HTTPS has never been used.
+#[tauri::command]
+fn export_local_ca(app_handle: AppHandle, path: String) -> Result<(), String> {
+    let ca = local_ca(&app_handle)?;
+    ca.ensure().map_err(|e| e.to_string())?;
+    fs::copy(ca.ca_cert_path(), &path).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Deep links waiting for the frontend to start listening.
+#[derive(Default)]
+struct DeepLinks(Mutex<DeepLinkQueue>);
+
+#[derive(Default)]
+struct DeepLinkQueue {
+    /// Set once the frontend has collected the queue; later links are
+    /// emitted straight away.
+    listening: bool,
+    queued: Vec<DeepLink>,
+}
+
+/// Validate the deep links among command-line `args` and hand them to the
+/// frontend as "deep-link" events, or queue them until it listens.
+fn open_deep_links(app_handle: &AppHandle, args: &[String]) {
+    let links: Vec<DeepLink> = args
+        .iter()
+        .filter(|arg| deep_link::is_deep_link(arg))
+        .filter_map(|arg| match deep_link::parse(arg) {
+            Ok(link) => Some(link),
+            Err(e) => {
+                eprintln!("Ignoring deep link: {}", e);
+                None
+            }
+        })
+        .collect();
+    if links.is_empty() {
+        return;
+    }
+
+    let state = app_handle.state::<DeepLinks>();
+    let mut queue = state.0.lock().unwrap();
+    if !queue.listening {
+        queue.queued.extend(links);
+        return;
+    }
+    if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_NAME) {
+        for link in links {
+            if let Err(e) = window.emit("deep-link", link) {
+                eprintln!("Failed to emit deep-link: {}", e);
+            }
+        }
+    }
+}
+
+/// The deep links that arrived before the frontend started listening for
+/// "deep-link" events. Later links are emitted as events.
+#[tauri::command]
+fn take_deep_links(deep_links: tauri::State<'_, DeepLinks>) -> Vec<DeepLink> {
+    let mut queue = deep_links.0.lock().unwrap();
+    queue.listening = true;
+    std::mem::take(&mut queue.queued)
+}
+
+/// Open a small window asking the user to approve request `request_id`, or
+/// bring it forward if it is open already. It closes by itself once the
+/// request is answered or cancelled.
+#[tauri::command]
+fn open_approval_window(
+    app_handle: AppHandle,
+    bridge: tauri::State<'_, Arc<Bridge>>,
+    request_id: u64,
+) -> Result<(), String> {
+    show_approval_window(&app_handle, &bridge, request_id)
+}
+
+/// What the approval window for request `request_id` asks the user about.
+#[tauri::command]
+fn get_approval_request(
+    bridge: tauri::State<'_, Arc<Bridge>>,
+    request_id: u64,
+) -> Result<approval::Prompt, String> {
+    bridge
+        .pending_request(request_id)
+        .map(|request| approval::Prompt::from(&request))
+        .ok_or_else(|| format!("Request {} is not pending", request_id))
+}
+
+/// Answer the request of the calling approval window, and close it.
+#[tauri::command]
+fn answer_approval(
+    window: Window,
+    bridge: tauri::State<'_, Arc<Bridge>>,
+    approved: bool,
+) -> Result<(), String> {
+    let request_id = approval::request_id(window.label())
+        .ok_or_else(|| format!("{} is not an approval window", window.label()))?;
+    let app_handle = window.app_handle().clone();
+    window.destroy().map_err(|e| e.to_string())?;
+    settle_approval(&app_handle, &bridge, request_id, approved);
+    Ok(())
+}
+
+/// Pass the user's answer to request `request_id` on to the main window,
+/// which settles the wallet's prompt. A denial is answered to the app here.
+fn settle_approval(app_handle: &AppHandle, bridge: &Bridge, request_id: u64, approved: bool) {
+    if !approved {
+        bridge.deny(request_id);
+    }
+    let answer = approval::Answer {
+        request_id,
+        approved,
+    };
+    if let Err(e) = app_handle.emit_to(MAIN_WINDOW_NAME, approval::EVENT_ANSWERED, answer) {
+        eprintln!("Failed to emit {}: {}", approval::EVENT_ANSWERED, e);
+    }
+}
+
+/// Bring the wallet window forward, e.g. to review a request in full.
+#[tauri::command]
+fn show_wallet(focus: tauri::State<'_, Focus>) {
+    focus.show();
+}
+
+fn show_approval_window(
+    app_handle: &AppHandle,
+    bridge: &Bridge,
+    request_id: u64,
+) -> Result<(), String> {
+    if !bridge.is_pending(request_id) {
+        return Err(format!("Request {} is not pending", request_id));
+    }
+    let label = approval::label(request_id);
+    if let Some(window) = app_handle.get_webview_window(&label) {
+        return window.set_focus().map_err(|e| e.to_string());
+    }
+    let window = WebviewWindowBuilder::new(
+        app_handle,
+        label,
+        WebviewUrl::App(approval::url(request_id).into()),
+    )
+    .title("Approve Request")
+    .inner_size(approval::WIDTH, approval::HEIGHT)
+    .resizable(false)
+    .minimizable(false)
+    .maximizable(false)
+    .always_on_top(true)
+    .center()
+    .focused(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+    // The request may have been settled while the window was being built.
+    if !bridge.is_pending(request_id) {
+        _ = window.destroy();
+    }
+    Ok(())
+}
+
+/// Close the approval windows of requests that are no longer pending.
+fn close_settled_approvals(app_handle: &AppHandle, bridge: &Bridge) {
+    for (label, window) in app_handle.webview_windows() {
+        if approval::request_id(&label).is_some_and(|id| !bridge.is_pending(id)) {
+            if let Err(e) = window.destroy() {
+                eprintln!("Failed to close approval window {}: {}", label, e);
+            }
+        }
+    }
+}
+
+/// Focus handling for the main window, driven by the focus state machine.
+struct MainWindowFocus(AppHandle);
+
+impl MainWindowFocus {
+    fn window(&self) -> Option<Window> {
+        self.0
+            .get_webview_window(MAIN_WINDOW_NAME)
+            .map(|window| window.as_ref().window())
+    }
+}
+
+impl FocusDriver for MainWindowFocus {
+    fn grab(&self) {
+        if let Some(window) = self.window() {
+            grab_focus(window);
+        }
+    }
+
+    fn relinquish(&self) {
+        if let Some(window) = self.window() {
+            give_back_focus(window);
+        }
+    }
+
+    fn is_focused(&self) -> bool {
+        self.window()
+            .is_some_and(|window| window.is_focused().unwrap_or(false))
+    }
+}
+
+/// Whether the user is at the wallet, as last checked.
+#[tauri::command]
+fn get_presence(presence: tauri::State<'_, Arc<Presence>>) -> PresenceEvent {
+    presence.current()
+}
+
+/// Record that the user interacted with the wallet's frontend.
+#[tauri::command]
+fn report_activity(presence: tauri::State<'_, Arc<Presence>>) {
+    presence.touch();
+}
+
+/// Tell the frontend the main window was focused, blurred, minimized or
+/// restored, unless it already knew.
+fn window_state_changed(window: &Window, state: WindowState) {
+    let presence = window.state::<Arc<Presence>>();
+    if let Some(event) = presence.window_changed(state) {
+        if let Err(e) = window.emit_to(MAIN_WINDOW_NAME, presence::EVENT_WINDOW_STATE, event) {
+            eprintln!("Failed to emit {}: {}", presence::EVENT_WINDOW_STATE, e);
+        }
+    }
+}
+
+/// Where the time since any input on the desktop comes from.
+#[cfg(target_os = "linux")]
+enum SystemIdle {
+    X11(IdleTime),
+    Logind(IdleHint),
+}
+
+/// Check every so often whether the user went idle or came back, and tell
+/// the frontend.
+async fn watch_presence(app_handle: AppHandle, presence: Arc<Presence>) {
+    // The time since any input on the desktop, where it can be known: from
+    // the X server, or from logind under Wayland.
+    #[cfg(target_os = "linux")]
+    let system_idle = match x11_focus::x11_session()
+        .then(|| IdleTime::connect(None))
+        .flatten()
+    {
+        Some(idle_time) => Some(SystemIdle::X11(idle_time)),
+        None => IdleHint::connect().await.map(SystemIdle::Logind),
+    };
+    #[cfg(target_os = "linux")]
+    if system_idle.is_none() {
+        eprintln!("The desktop's idle time is unavailable; only use of the wallet counts");
+    }
+
+    let mut interval = tokio::time::interval(presence::POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        #[cfg(target_os = "linux")]
+        let system_idle_time = match &system_idle {
+            Some(SystemIdle::X11(idle_time)) => idle_time.get(),
+            Some(SystemIdle::Logind(idle_hint)) => idle_hint.get().await,
+            None => None,
+        };
+        #[cfg(not(target_os = "linux"))]
+        let system_idle_time = None;
+        if let Some(event) = presence.update(system_idle_time) {
+            if let Err(e) = app_handle.emit_to(MAIN_WINDOW_NAME, presence::EVENT_PRESENCE, event) {
+                eprintln!("Failed to emit {}: {}", presence::EVENT_PRESENCE, e);
+            }
+        }
+    }
+}
+
+/// Raise a desktop notification for app requests needing the user while
+/// the main window is out of sight. Clicking one brings the wallet forward.
+#[cfg(target_os = "linux")]
+async fn notify_incoming(app_handle: AppHandle, bridge: Arc<Bridge>) {
+    use tokio::sync::broadcast::error::RecvError;
+
+    let notifier = match Notifier::connect().await {
+        Ok(notifier) => Arc::new(notifier),
+        Err(e) => {
+            eprintln!("Desktop notifications unavailable: {}", e);
+            return;
+        }
+    };
+    // The notification service tells everyone about every click, so only
+    // our own notifications are acted on. They are forgotten once closed.
+    let sent = Arc::new(Mutex::new(std::collections::VecDeque::<u32>::new()));
+    let forget = {
+        let sent = sent.clone();
+        move |id: u32| {
+            let mut sent = sent.lock().unwrap();
+            let index = sent.iter().position(|sent_id| *sent_id == id);
+            index.and_then(|index| sent.remove(index)).is_some()
+        }
+    };
+
+    {
+        let notifier = notifier.clone();
+        let forget = forget.clone();
+        let app_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let clicked = |id| {
+                if forget(id) {
+                    show_main_window(&app_handle);
+                }
+            };
+            if let Err(e) = notifier.watch_clicks(clicked).await {
+                eprintln!("Lost desktop notification clicks: {}", e);
+            }
+        });
+    }
+
+    {
+        let notifier = notifier.clone();
+        tauri::async_runtime::spawn(async move {
+            let closed = |id| {
+                forget(id);
+            };
+            if let Err(e) = notifier.watch_closed(closed).await {
+                eprintln!("Lost desktop notification closes: {}", e);
+            }
+        });
+    }
+
+    let mut incoming = bridge.subscribe_incoming();
+    let mut limiter = RateLimiter::default();
+    loop {
+        let request = match incoming.recv().await {
+            Ok(request) => request,
+            // Missed requests are not worth catching up on.
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        if !notifications::needs_attention(&request) {
+            continue;
+        }
+        let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_NAME) else {
+            continue;
+        };
+        if window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false) {
+            continue;
+        }
+        if !limiter.allow(request.origin.as_deref(), std::time::Instant::now()) {
+            continue;
+        }
+        let summary = notifications::summary(&request);
+        let body = notifications::body(&request);
+        match notifier.notify(&summary, &body).await {
+            Ok(id) => {
+                let mut sent = sent.lock().unwrap();
+                if sent.len() == notifications::MAX_TRACKED {
+                    sent.pop_front();
+                }
+                sent.push_back(id);
+            }
+            Err(e) => eprintln!("Failed to show desktop notification: {}", e),
+        }
+    }
+}
+
+/// Show the main window, bringing it forward, or hide it if it is shown.
+fn toggle_main_window(app_handle: &AppHandle) {
+    let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_NAME) else {
+        return;
+    };
+    if window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false) {
+        save_window_state(&window.as_ref().window());
+        if let Err(e) = window.hide() {
+            eprintln!("hide error: {}", e);
+        }
+    } else {
+        show_main_window(app_handle);
+    }
+}
+
+/// Bring the main window forward for the user, through the focus loop so
+/// it never blocks the caller or races a grab already under way.
+fn show_main_window(app_handle: &AppHandle) {
+    if let Some(focus) = app_handle.try_state::<Focus>() {
+        focus.show();
+    }
+}
+
+/// Remember the main window's geometry for the next start.
+fn save_window_state(window: &Window) {
+    // A minimized window's geometry is not worth going back to.
+    if window.is_minimized().unwrap_or(false) {
+        return;
+    }
+    let Ok(config_dir) = window.app_handle().path().app_config_dir() else {
+        return;
+    };
+    let (Ok(position), Ok(size), Ok(maximized)) = (
+        window.outer_position(),
+        window.inner_size(),
+        window.is_maximized(),
+    ) else {
+        return;
+    };
+    let monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .and_then(|monitor| monitor.name().cloned());
+    let state = match SavedGeometry::load(&config_dir) {
+        // Keep the geometry to go back to when it is unmaximized.
+        Some(previous) if maximized => SavedGeometry {
+            maximized,
+            monitor,
+            ..previous
+        },
+        _ => SavedGeometry {
+            x: position.x,
+            y: position.y,
+            width: size.width,
+            height: size.height,
+            maximized,
+            monitor,
+        },
+    };
+    if let Err(e) = state.save(&config_dir) {
+        eprintln!("Failed to save window state: {}", e);
+    }
+}
+
+fn monitor_area(monitor: &Monitor) -> MonitorArea {
+    let work_area = monitor.work_area();
+    MonitorArea {
+        name: monitor.name().cloned(),
+        work_area: Rect {
+            x: work_area.position.x,
+            y: work_area.position.y,
+            width: work_area.size.width,
+            height: work_area.size.height,
+        },
+    }
+}
+
+/// Put the main window back where it was last time, fitted to the monitors
+/// present now. The first time, the configured size is only fitted to the
+/// screen.
+fn restore_window_state(window: &Window) -> tauri::Result<()> {
+    let config_dir = window.app_handle().path().app_config_dir()?;
+    let primary = window.primary_monitor()?;
+    let mut monitors = window.available_monitors()?;
+    // The primary monitor goes first.
+    monitors.sort_by_key(|monitor| {
+        primary
+            .as_ref()
+            .is_none_or(|primary| monitor.name() != primary.name())
+    });
+    let monitors: Vec<MonitorArea> = monitors.iter().map(monitor_area).collect();
+
+    let saved = SavedGeometry::load(&config_dir);
+    let state = match &saved {
+        Some(saved) => saved.clone(),
+        None => {
+            let position = window.outer_position()?;
+            let size = window.inner_size()?;
+            SavedGeometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized: false,
+                monitor: None,
+            }
+        }
+    };
+    let fitted = state.fit_to(&monitors);
+    if saved.is_some() || (fitted.width, fitted.height) != (state.width, state.height) {
+        window.set_size(PhysicalSize::new(fitted.width, fitted.height))?;
+        window.set_position(PhysicalPosition::new(fitted.x, fitted.y))?;
+    }
+    if fitted.maximized {
+        window.maximize()?;
+    }
+    Ok(())
+}
+
+/// Hide the main window instead of closing it, so the bridge keeps serving
+/// connected apps, unless the user chose to quit on close. The first time,
+/// a notice explains where the app went.
+fn close_to_tray(window: &Window) -> bool {
+    let Ok(config_dir) = window.app_handle().path().app_config_dir() else {
+        return false;
+    };
+    let mut settings = Settings::load(&config_dir);
+    if settings.quit_on_close {
+        return false;
+    }
+    if let Err(e) = window.hide() {
+        eprintln!("hide error: {}", e);
+        return false;
+    }
+
+    if !settings.close_notice_shown {
+        settings.close_notice_shown = true;
+        if let Err(e) = settings.save(&config_dir) {
+            eprintln!("Failed to save settings: {}", e);
+        }
+        window
+            .dialog()
+            .message(
+                "Metanet Desktop keeps running in the system tray so connected apps can \
+                 still reach your wallet. Open it again from the tray icon, which also \
+                 lets you quit. To quit whenever the window is closed, change this in \
+                 the settings.",
+            )
+            .title("Metanet Desktop is still running")
+            .kind(MessageDialogKind::Info)
+            .show(|_| {});
+    }
+    true
+}
+
+/// Lock the wallet: fail the requests waiting on it, refuse requests using
+/// its keys until the user authenticates again, and reload the frontend, which forgets the
+/// unlocked wallet.
+fn lock(app_handle: &AppHandle, bridge: &Bridge) {
+    bridge.lock();
+    if let Err(e) = app_handle.emit(EVENT_LOCKED, ()) {
+        eprintln!("Failed to emit {}: {}", EVENT_LOCKED, e);
+    }
+    if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_NAME) {
+        if let Err(e) = window.reload() {
+            eprintln!("reload error: {}", e);
+        }
+    }
+}
+
+/// Lock the wallet now.
+#[tauri::command]
+fn lock_wallet(app_handle: AppHandle, bridge: tauri::State<'_, Arc<Bridge>>) {
+    lock(&app_handle, &bridge);
+}
+
+/// Whether the wallet is locked until the user authenticates again.
+#[tauri::command]
+fn is_locked(bridge: tauri::State<'_, Arc<Bridge>>) -> bool {
+    bridge.is_locked()
+}
+
+/// Minutes without the user before the wallet locks, from the settings.
+#[derive(Default)]
+struct AutoLock(AtomicU32);
+
+/// Lock the wallet once the user has been away longer than the auto-lock
+/// timeout, where that can be told.
+async fn auto_lock(app_handle: AppHandle, presence: Arc<Presence>, bridge: Arc<Bridge>) {
+    let mut idle_time = presence.watch_idle_time();
+    let mut was_locked = bridge.is_locked();
+    while idle_time.changed().await.is_ok() {
+        let locked = bridge.is_locked();
+        // Unlocking took the user, whether or not it was reported.
+        if was_locked && !locked {
+            presence.touch();
+            was_locked = false;
+            continue;
+        }
+        idle_time.mark_unchanged();
+        let minutes = app_handle.state::<AutoLock>().0.load(Ordering::Relaxed);
+        let timeout = Duration::from_secs(u64::from(minutes) * 60);
+        if !locked && minutes > 0 && presence.auto_lock_due(timeout) {
+            lock(&app_handle, &bridge);
+        }
+        was_locked = bridge.is_locked();
+    }
+}
+
+/// The tray icon and the parts of it that follow the bridge's state.
+struct Tray {
+    icon: TrayIcon,
+    pending_item: MenuItem,
+    idle_icon: Image<'static>,
+    busy_icon: Image<'static>,
+    bridge: Arc<Bridge>,
+}
+
+impl Tray {
+    /// Show the current number of pending requests and whether app access
+    /// is paused.
+    fn refresh(&self) {
+        let pending = self.bridge.pending_count();
+        let paused = self.bridge.is_paused();
+        if let Err(e) = self.pending_item.set_text(tray::pending_label(pending)) {
+            eprintln!("tray menu error: {}", e);
+        }
+        let icon = if pending > 0 {
+            &self.busy_icon
+        } else {
+            &self.idle_icon
+        };
+        if let Err(e) = self.icon.set_icon(Some(icon.clone())) {
+            eprintln!("tray icon error: {}", e);
+        }
+        if let Err(e) = self.icon.set_tooltip(Some(tray::tooltip(pending, paused))) {
+            eprintln!("tray tooltip error: {}", e);
+        }
+    }
+}
+
+/// Add the tray icon, with a menu to show the wallet, lock it, see and
+/// answer pending requests, pause app access and quit.
+fn setup_tray(app: &tauri::App, bridge: Arc<Bridge>) -> tauri::Result<()> {
+    let show_item = MenuItem::with_id(app, "show", "Show/Hide Wallet", true, None::<&str>)?;
+    let pending_item =
+        MenuItem::with_id(app, "pending", tray::pending_label(0), true, None::<&str>)?;
+    let lock_item = MenuItem::with_id(app, "lock", "Lock Now", true, None::<&str>)?;
+    let pause_item =
+        CheckMenuItem::with_id(app, "pause", "Pause App Access", true, false, None::<&str>)?;
+    let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &show_item,
+            &pending_item,
+            &PredefinedMenuItem::separator(app)?,
+            &lock_item,
+            &pause_item,
+            &PredefinedMenuItem::separator(app)?,
+            &quit_item,
+        ],
+    )?;
+
+    let idle_icon = app
+        .default_window_icon()
+        .cloned()
+        .expect("the bundle has an icon")
+        .to_owned();
+    let busy_icon = Image::new_owned(
+        tray::badged_icon(idle_icon.rgba(), idle_icon.width(), idle_icon.height()),
+        idle_icon.width(),
+        idle_icon.height(),
+    );
+    let icon = TrayIconBuilder::with_id("main")
+        .icon(idle_icon.clone())
+        .tooltip(tray::tooltip(0, false))
+        .menu(&menu)
+        // Left clicks show the window where the platform reports them.
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|icon, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                toggle_main_window(icon.app_handle());
+            }
+        })
+        .build(app)?;
+
+    let tray = Arc::new(Tray {
+        icon,
+        pending_item,
+        idle_icon,
+        busy_icon,
+        bridge,
+    });
+
+    {
+        let tray = tray.clone();
+        tray.icon.clone().on_menu_event(move |app_handle, event| {
+            match event.id().as_ref() {
+                "show" => toggle_main_window(app_handle),
+                // Pending requests are answered in the wallet window.
+                "pending" => show_main_window(app_handle),
+                "lock" => lock(app_handle, &tray.bridge),
+                "pause" => {
+                    tray.bridge
+                        .set_paused(pause_item.is_checked().unwrap_or(false));
+                    tray.refresh();
+                }
+                "quit" => app_handle.exit(0),
+                _ => {}
+            }
+        });
+    }
+
+    // Follow the number of requests waiting on the wallet.
+    let mut pending = tray.bridge.watch_pending();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tray.refresh();
+            if pending.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(())
+}
+
 #[command]
 async fn download(app_handle: AppHandle, filename: String, content: Vec<u8>) -> Result<(), String> {
     let downloads_dir = app_handle
@@ -276,50 +1091,173 @@ async fn download(app_handle: AppHandle, filename: String, content: Vec<u8>) ->
 
 fn main() {
     tauri::Builder::default()
+        // Launching the app again, e.g. by opening a deep link, hands its
+        // arguments to the running instance and brings its window forward.
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            open_deep_links(app, argv.get(1..).unwrap_or_default());
+            if argv.iter().any(|arg| arg == MINIMIZED_ARG) {
+                return;
+            }
+            show_main_window(app);
+        }))
         .plugin(tauri_plugin_dialog::init())
+        .manage(DeepLinks::default())
+        .manage(Arc::new(Presence::default()))
+        .manage(AutoLock::default())
+        .on_window_event(|window, event| match event {
+            WindowEvent::CloseRequested { api, .. } => {
+                if window.label() == MAIN_WINDOW_NAME {
+                    save_window_state(window);
+                    if close_to_tray(window) {
+                        api.prevent_close();
+                    }
+                }
+                // Closing an approval window unanswered denies its request.
+                if let Some(request_id) = approval::request_id(window.label()) {
+                    let bridge = window.state::<Arc<Bridge>>();
+                    settle_approval(window.app_handle(), &bridge, request_id, false);
+                }
+            }
+            WindowEvent::Focused(focused) if window.label() == MAIN_WINDOW_NAME => {
+                let state = if *focused {
+                    WindowState::Focused
+                } else {
+                    WindowState::Blurred
+                };
+                window_state_changed(window, state);
+            }
+            // Minimizing and restoring show up as resizes.
+            WindowEvent::Resized(_) if window.label() == MAIN_WINDOW_NAME => {
+                if let Ok(minimized) = window.is_minimized() {
+                    let state = if minimized {
+                        WindowState::Minimized
+                    } else {
+                        WindowState::Restored
+                    };
+                    window_state_changed(window, state);
+                }
+            }
+            _ => {}
+        })
         .setup(|app| {
             // Extract the main window.
             let main_window = app.get_webview_window(MAIN_WINDOW_NAME).unwrap();
 
-            // Shared, concurrent map to store pending responses.
-            let pending_requests: Arc<PendingMap> = Arc::new(DashMap::new());
-            // Atomic counter to generate unique request IDs.
-            let request_counter = Arc::new(AtomicU64::new(1));
+            // A deep link may have started the app.
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            open_deep_links(app.handle(), &args);
+
+            // The window starts hidden, to be shown where it was last time,
+            // unless started at login: then it stays in the tray until the
+            // user or an app asks for it.
+            if let Err(e) = restore_window_state(&main_window.as_ref().window()) {
+                eprintln!("Failed to restore window state: {}", e);
+            }
+            if !args.iter().any(|arg| arg == MINIMIZED_ARG) {
+                main_window.show()?;
+            }
+
+            let settings = Settings::load(&app.path().app_config_dir()?);
+
+            // The bridge delivers requests to the main window and tracks
+            // which ones are still waiting on the frontend.
+            // Apps polling the wallet do not count as the user being
+            // there, so it still locks while they run.
+            let emit_window = main_window.clone();
+            let bridge_config = settings.bridge_config();
+            let bridge = Arc::new(Bridge::with_config(
+                Box::new(move |event_json| {
+                    emit_window
+                        .emit("http-request", event_json)
+                        .map_err(|e| e.to_string())
+                }),
+                bridge_config,
+            ));
+
+            setup_tray(app, bridge.clone())?;
+            app.manage(bridge.clone());
+
+            // Focus asks from the frontend, and the user showing the
+            // wallet, are coalesced off the command thread.
+            let (focus, focus_loop) = Focus::new(FocusConfig::default());
+            app.manage(focus);
+            tauri::async_runtime::spawn(focus_loop.run(MainWindowFocus(app.handle().clone())));
+
+            // The frontend hears when the user goes idle or comes back.
+            let presence = app.state::<Arc<Presence>>().inner().clone();
+            tauri::async_runtime::spawn(watch_presence(app.handle().clone(), presence.clone()));
+
+            // The wallet locks itself once the user has been away long
+            // enough.
+            tauri::async_runtime::spawn(auto_lock(app.handle().clone(), presence, bridge.clone()));
+
+            // Requests arriving while the wallet is hidden show up as
+            // desktop notifications.
+            #[cfg(target_os = "linux")]
+            tauri::async_runtime::spawn(notify_incoming(app.handle().clone(), bridge.clone()));
+
+            {
+                // Approval windows close once their request is settled.
+                let app_handle = app.handle().clone();
+                let bridge = bridge.clone();
+                let mut pending = bridge.watch_pending();
+                tauri::async_runtime::spawn(async move {
+                    while pending.changed().await.is_ok() {
+                        close_settled_approvals(&app_handle, &bridge);
+                    }
+                });
+            }
 
             {
                 // Set up a listener for "ts-response" events coming from the frontend.
                 // We attach the listener to the main window (not globally) for security.
-                let pending_requests = pending_requests.clone();
+                let bridge = bridge.clone();
                 main_window.listen("ts-response", move |event| {
-                    let payload = event.payload();
-                    if payload.len() > 0 {
-                        match serde_json::from_str::<TsResponse>(payload) {
-                            Ok(ts_response) => {
-                                if let Some((req_id, tx)) = pending_requests.remove(&ts_response.request_id) {
-                                    if let Err(err) = tx.send(ts_response) {
-                                        eprintln!(
-                                            "Failed to send response via oneshot channel for request {}: {:?}",
-                                            req_id, err
-                                        );
-                                    }
-                                } else {
-                                    eprintln!("Received ts-response for unknown request_id: {}", ts_response.request_id);
-                                }
-                            }
-                            Err(err) => {
-                                eprintln!("Failed to parse ts-response payload: {:?}", err);
-                            }
-                        }
-                    } else {
-                        eprintln!("ts-response event did not include a payload");
-                    }
+                    bridge.resolve(event.payload());
+                });
+            }
+
+            {
+                // Large response bodies follow their "ts-response" in parts.
+                let bridge = bridge.clone();
+                main_window.listen("ts-response-chunk", move |event| {
+                    bridge.resolve_chunk(event.payload());
+                });
+            }
+
+            {
+                // Wallet state changes announced by the frontend are pushed
+                // to subscribed WebSocket clients.
+                let bridge = bridge.clone();
+                main_window.listen("wallet-event", move |event| {
+                    bridge.publish(event.payload());
                 });
             }
 
+            app.state::<AutoLock>()
+                .0
+                .store(settings.auto_lock_minutes, Ordering::Relaxed);
+
+            // Keep the autostart entry pointing at this executable, which
+            // moves when the app is reinstalled or its AppImage replaced.
+            #[cfg(target_os = "linux")]
+            if settings.launch_at_login {
+                let config_dir = app.path().config_dir()?;
+                if autostart::is_enabled(&config_dir) {
+                    if let Err(e) = autostart::enable(&config_dir, &autostart::launcher()?) {
+                        eprintln!("Failed to update the autostart entry: {}", e);
+                    }
+                }
+            }
+
+            // HTTPS is opt-in, since the local CA has to be trusted first.
+            let https_ca = if settings.https {
+                Some(local_ca(app.handle())?)
+            } else {
+                None
+            };
+
             // Spawn a separate thread to run our asynchronous HTTP server.
-            let main_window_clone = main_window.clone();
-            let pending_requests_clone = pending_requests.clone();
-            let request_counter_clone = request_counter.clone();
             std::thread::spawn(move || {
                 // Build a multi-threaded Tokio runtime.
                 let rt = tokio::runtime::Builder::new_multi_thread()
@@ -328,8 +1266,29 @@ fn main() {
                     .expect("Failed to create Tokio runtime");
 
                 rt.block_on(async move {
+                    // Native clients on Linux can also use a private Unix socket.
+                    #[cfg(target_os = "linux")]
+                    if let Some(path) = metanet_desktop::uds::socket_path() {
+                        let bridge = bridge.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = metanet_desktop::uds::serve(bridge, &path).await {
+                                eprintln!("Unix socket server error: {}", e);
+                            }
+                        });
+                    }
+
+                    if let Some(ca) = https_ca {
+                        let bridge = bridge.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = tls::serve(bridge, ca).await {
+                                eprintln!("HTTPS server error: {}", e);
+                            }
+                        });
+                    }
+
                     // Bind the Hyper server to 127.0.0.1:3321.
-                    let addr: SocketAddr = "127.0.0.1:3321".parse().expect("Invalid socket address");
+                    let addr: SocketAddr =
+                        "127.0.0.1:3321".parse().expect("Invalid socket address");
                     println!("HTTP server listening on http://{}", addr);
 
                     // Attempt to bind the server and check for address in use error
@@ -338,119 +1297,10 @@ fn main() {
                             // Create our Hyper service.
                             let make_svc = make_service_fn(move |_conn| {
                                 // Clone handles for each connection.
-                                let pending_requests = pending_requests_clone.clone();
-                                let main_window = main_window_clone.clone();
-                                let request_counter = request_counter_clone.clone();
-
+                                let bridge = bridge.clone();
                                 async move {
                                     Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
-                                        // Clone per-request handles.
-                                        let pending_requests = pending_requests.clone();
-                                        let main_window = main_window.clone();
-                                        let request_counter = request_counter.clone();
-
-                                        async move {
-
-                                            // Intercept any OPTIONS requests
-                                            if req.method() == hyper::Method::OPTIONS {
-                                                let mut res = Response::new(Body::empty());
-                                                res.headers_mut().insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Headers", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Methods", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Expose-Headers", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Private-Network", "true".parse().unwrap());
-                                                return Ok::<_, Infallible>(res);
-                                            }
-
-                                            // Generate a unique request ID.
-                                            let request_id = request_counter.fetch_add(1, Ordering::Relaxed);
-
-                                            // Extract the HTTP method, URI, and headers.
-                                            let method = req.method().clone();
-                                            let uri = req.uri().clone();
-                                            let headers = req.headers().iter()
-                                                .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
-                                                .collect::<Vec<(String, String)>>();
-
-                                            // Read the full request body.
-                                            let whole_body = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
-                                            let body_str = String::from_utf8_lossy(&whole_body).to_string();
-
-                                            // Create a oneshot channel for awaiting the frontend response.
-                                            let (tx, rx) = oneshot::channel::<TsResponse>();
-                                            pending_requests.insert(request_id, tx);
-
-                                            // Prepare the event payload.
-                                            let event_payload = HttpRequestEvent {
-                                                method: method.to_string(),
-                                                path: uri.to_string(),
-                                                headers,
-                                                body: body_str,
-                                                request_id,
-                                            };
-
-                                            // Serialize the payload to JSON.
-                                            let event_json = match serde_json::to_string(&event_payload) {
-                                                Ok(json) => json,
-                                                Err(e) => {
-                                                    eprintln!("Failed to serialize HTTP event: {:?}", e);
-                                                    let mut res = Response::new(Body::from("Internal Server Error"));
-                                                    *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                    // Append CORS headers
-                                                    res.headers_mut().insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Methods", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Expose-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Private-Network", "true".parse().unwrap());
-                                                    // Remove pending request since we cannot proceed.
-                                                    pending_requests.remove(&request_id);
-                                                    return Ok::<_, Infallible>(res);
-                                                }
-                                            };
-
-                                            // Emit the "http-request" event to the main window.
-                                            if let Err(err) = main_window.emit("http-request", event_json) {
-                                                eprintln!("Failed to emit http-request event: {:?}", err);
-                                                pending_requests.remove(&request_id);
-                                                let mut res = Response::new(Body::from("Internal Server Error"));
-                                                *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
-                                                // Append CORS headers
-                                                res.headers_mut().insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Headers", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Methods", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Expose-Headers", "*".parse().unwrap());
-                                                res.headers_mut().insert("Access-Control-Allow-Private-Network", "true".parse().unwrap());
-                                                return Ok::<_, Infallible>(res);
-                                            }
-
-                                            // Wait asynchronously for the frontend's response.
-                                            match rx.await {
-                                                Ok(ts_response) => {
-                                                    let mut res = Response::new(Body::from(ts_response.body));
-                                                    *res.status_mut() = StatusCode::from_u16(ts_response.status)
-                                                        .unwrap_or(StatusCode::OK);
-                                                    // Append CORS headers
-                                                    res.headers_mut().insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Methods", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Expose-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Private-Network", "true".parse().unwrap());
-                                                    Ok::<_, Infallible>(res)
-                                                }
-                                                Err(err) => {
-                                                    eprintln!("Error awaiting frontend response for request {}: {:?}", request_id, err);
-                                                    let mut res = Response::new(Body::from("Gateway Timeout"));
-                                                    *res.status_mut() = StatusCode::GATEWAY_TIMEOUT;
-                                                    // Append CORS headers
-                                                    res.headers_mut().insert("Access-Control-Allow-Origin", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Methods", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Expose-Headers", "*".parse().unwrap());
-                                                    res.headers_mut().insert("Access-Control-Allow-Private-Network", "true".parse().unwrap());
-                                                    Ok::<_, Infallible>(res)
-                                                }
-                                            }
-                                        }
+                                        server::handle(bridge.clone(), req)
                                     }))
                                 }
                             });
@@ -481,11 +1331,45 @@ fn main() {
             request_focus,
             relinquish_focus,
             download,
-            save_file
+            save_file,
+            get_settings,
+            set_settings,
+            export_local_ca,
+            open_approval_window,
+            get_approval_request,
+            answer_approval,
+            show_wallet,
+            get_presence,
+            report_activity,
+            lock_wallet,
+            is_locked,
+            get_launch_at_login,
+            set_launch_at_login,
+            take_deep_links
         ])
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
-        .run(tauri::generate_context!())
-        .expect("Error while running Tauri application");
+        .build(tauri::generate_context!())
+        .expect("Error while running Tauri application")
+        .run(|app_handle, event| {
+            // Quitting from the tray skips the window's close event.
+            if let tauri::RunEvent::Exit = event {
+                if let Some(window) = app_handle.get_webview_window(MAIN_WINDOW_NAME) {
+                    if window.is_visible().unwrap_or(false) {
+                        save_window_state(&window.as_ref().window());
+                    }
+                }
+            }
+
+            // Clicking the dock icon brings back a window closed to the tray.
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Reopen {
+                has_visible_windows: false,
+                ..
+            } = event
+            {
+                show_main_window(app_handle);
+            }
+        });
 }
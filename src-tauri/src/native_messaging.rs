@@ -0,0 +1,271 @@
+//! Browser native messaging host relaying extension messages to the wallet.
+//!
+//! Browsers start the host with the calling extension in its arguments and
+//! exchange messages on stdin/stdout, each a JSON document preceded by its
+//! length as a native-endian `u32`. Every message is a JSON-RPC 2.0 call (or
+//! batch) and is relayed to the running wallet's `/rpc` endpoint with the
+//! extension as its origin; the JSON-RPC reply is written back. Replies to
+//! concurrent calls are matched up by `id`, as on `/rpc`.
+//!
+//! Browsers find the host through a manifest in their profile directory,
+//! which [`install_manifests`] writes for the browsers found on Linux.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+    task::JoinSet,
+};
+
+use crate::client::BridgeClient;
+use crate::rpc;
+use crate::server::RPC_PATH;
+
+/// Name browsers know the host by.
+pub const HOST_NAME: &str = "org.bsvblockchain.metanetdesktop";
+
+/// Largest message accepted from an extension; browsers send at most 64 MiB.
+pub const MAX_INCOMING_SIZE: usize = 64 * 1024 * 1024;
+/// Largest message browsers accept from a host.
+pub const MAX_OUTGOING_SIZE: usize = 1024 * 1024;
+
+/// Read one length-prefixed message, or `None` once the browser closed the
+/// pipe.
+pub async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_INCOMING_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes is too large", length),
+        ));
+    }
+    let mut message = vec![0u8; length];
+    reader.read_exact(&mut message).await?;
+    Ok(Some(message))
+}
+
+/// Write one length-prefixed message.
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &[u8],
+) -> io::Result<()> {
+    let length = u32::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too large"))?;
+    writer.write_all(&length.to_ne_bytes()).await?;
+    writer.write_all(message).await?;
+    writer.flush().await
+}
+
+/// The header identifying the calling extension to the wallet, worked out
+/// from the host's command-line arguments (without the program name).
+///
+/// Chrome passes the extension's origin (`chrome-extension://<id>/`);
+/// Firefox passes the manifest path followed by the extension ID, which is
+/// turned into a `moz-extension` originator the wallet can key permissions
+/// by.
+pub fn caller_origin(args: &[String]) -> Option<(&'static str, String)> {
+    if let Some(origin) = args.iter().find(|a| a.starts_with("chrome-extension://")) {
+        return Some(("Origin", origin.trim_end_matches('/').to_string()));
+    }
+    let id = args.get(1)?;
+    let host: String = id
+        .to_ascii_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '.' | '-' => c,
+            _ => '-',
+        })
+        .collect();
+    let host = host.trim_matches('-');
+    if host.is_empty() {
+        return None;
+    }
+    Some(("Originator", format!("moz-extension://{}", host)))
+}
+
+/// Relay messages between the browser on stdin/stdout and the wallet until
+/// the browser closes the pipe.
+pub async fn run(client: BridgeClient) -> io::Result<()> {
+    let mut stdin = tokio::io::stdin();
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(reply) = reply_rx.recv().await {
+            write_message(&mut stdout, &reply).await?;
+        }
+        Ok::<_, io::Error>(())
+    });
+
+    let mut calls = JoinSet::new();
+    while let Some(message) = read_message(&mut stdin).await? {
+        let client = client.clone();
+        let reply_tx = reply_tx.clone();
+        calls.spawn(async move {
+            if let Some(reply) = relay(&client, message).await {
+                _ = reply_tx.send(reply);
+            }
+        });
+    }
+    // Answer everything already asked before exiting.
+    while calls.join_next().await.is_some() {}
+    drop(reply_tx);
+    writer.await.map_err(io::Error::other)?
+}
+
+/// Relay one message, returning the reply to send back, if any.
+async fn relay(client: &BridgeClient, message: Vec<u8>) -> Option<Vec<u8>> {
+    let id = serde_json::from_slice::<Value>(&message)
+        .ok()
+        .and_then(|v| v.get("id").cloned())
+        .unwrap_or(Value::Null);
+    let body = String::from_utf8_lossy(&message).into_owned();
+    let reply = match client.post(RPC_PATH, body).await {
+        // Only notifications were sent.
+        Ok(response) if response.body.is_empty() => return None,
+        Ok(response) => response.body.to_vec(),
+        Err(e) => rpc_error(id.clone(), rpc::INTERNAL_ERROR, &e.to_string()),
+    };
+    if reply.len() > MAX_OUTGOING_SIZE {
+        return Some(rpc_error(
+            id,
+            rpc::INTERNAL_ERROR,
+            "Response exceeds the browser's native messaging size limit",
+        ));
+    }
+    Some(reply)
+}
+
+fn rpc_error(id: Value, code: i64, message: &str) -> Vec<u8> {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": id,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+/// Browsers the host manifest can be installed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Browser {
+    Chrome,
+    Chromium,
+    Brave,
+    Edge,
+    Firefox,
+}
+
+impl Browser {
+    pub const ALL: [Browser; 5] = [
+        Browser::Chrome,
+        Browser::Chromium,
+        Browser::Brave,
+        Browser::Edge,
+        Browser::Firefox,
+    ];
+
+    /// The browser's per-user configuration directory on Linux, relative to
+    /// the home directory. The manifest is only installed if it exists.
+    fn profile_dir(self) -> &'static str {
+        match self {
+            Browser::Chrome => ".config/google-chrome",
+            Browser::Chromium => ".config/chromium",
+            Browser::Brave => ".config/BraveSoftware/Brave-Browser",
+            Browser::Edge => ".config/microsoft-edge",
+            Browser::Firefox => ".mozilla",
+        }
+    }
+
+    /// Where the browser looks for host manifests on Linux.
+    pub fn manifest_path(self, home: &Path) -> PathBuf {
+        let hosts_dir = match self {
+            Browser::Firefox => "native-messaging-hosts",
+            _ => "NativeMessagingHosts",
+        };
+        home.join(self.profile_dir())
+            .join(hosts_dir)
+            .join(format!("{}.json", HOST_NAME))
+    }
+
+    /// The host manifest for this browser. Chromium-based browsers list
+    /// allowed extension origins, Firefox lists extension IDs.
+    pub fn manifest(
+        self,
+        host_path: &Path,
+        chrome_ids: &[String],
+        firefox_ids: &[String],
+    ) -> Value {
+        let mut manifest = json!({
+            "name": HOST_NAME,
+            "description": "Metanet Desktop wallet",
+            "path": host_path,
+            "type": "stdio",
+        });
+        if self == Browser::Firefox {
+            manifest["allowed_extensions"] = json!(firefox_ids);
+        } else {
+            let origins: Vec<String> = chrome_ids
+                .iter()
+                .map(|id| format!("chrome-extension://{}/", id))
+                .collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+        manifest
+    }
+}
+
+/// Write the host manifest for every installed browser with extensions to
+/// allow, returning the files written.
+pub fn install_manifests(
+    home: &Path,
+    host_path: &Path,
+    chrome_ids: &[String],
+    firefox_ids: &[String],
+) -> io::Result<Vec<PathBuf>> {
+    let mut written = Vec::new();
+    for browser in Browser::ALL {
+        let ids = if browser == Browser::Firefox {
+            firefox_ids
+        } else {
+            chrome_ids
+        };
+        if ids.is_empty() || !home.join(browser.profile_dir()).is_dir() {
+            continue;
+        }
+        let path = browser.manifest_path(home);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let manifest = browser.manifest(host_path, chrome_ids, firefox_ids);
+        let json = serde_json::to_string_pretty(&manifest).map_err(io::Error::other)?;
+        fs::write(&path, json)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Remove every installed host manifest, returning the files removed.
+pub fn uninstall_manifests(home: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for browser in Browser::ALL {
+        let path = browser.manifest_path(home);
+        match fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(removed)
+}
@@ -0,0 +1,203 @@
+//! Desktop notifications for app requests that need the user.
+//!
+//! When the wallet window is out of sight, apps asking for anything beyond
+//! reads raise a notification naming the app and the call. At most one is
+//! shown per app within [`MIN_INTERVAL`], so a burst of calls doesn't flood
+//! the desktop. On Linux they go through the freedesktop notification
+//! service over D-Bus, see [`Notifier`].
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::brc100::{Method, MethodClass};
+use crate::bridge::IncomingRequest;
+
+/// Least time between two notifications for the same app.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Most notifications kept track of for clicks. Older ones are forgotten,
+/// in case the server never says they were closed.
+pub const MAX_TRACKED: usize = 32;
+
+/// Name of the notification action that brings the wallet forward.
+pub const ACTION_SHOW: &str = "default";
+
+/// Whether a request is worth interrupting the user for.
+pub fn needs_attention(request: &IncomingRequest) -> bool {
+    request.method.class() != MethodClass::ReadOnly
+}
+
+/// The notification's title.
+pub fn summary(request: &IncomingRequest) -> String {
+    match &request.origin {
+        Some(origin) => format!("Request from {}", origin),
+        None => "Request from an app".to_string(),
+    }
+}
+
+/// The notification's text: what the app asks to do.
+pub fn body(request: &IncomingRequest) -> String {
+    let method = request.method;
+    format!("{}: {}", method.name(), describe(method))
+}
+
+fn describe(method: Method) -> &'static str {
+    match method {
+        Method::CreateAction => "create a transaction",
+        Method::SignAction => "sign a transaction",
+        Method::AbortAction => "abort a transaction",
+        Method::InternalizeAction => "add a transaction to your wallet",
+        Method::RelinquishOutput => "remove an output from your wallet",
+        Method::GetPublicKey => "get a public key",
+        Method::RevealCounterpartyKeyLinkage | Method::RevealSpecificKeyLinkage => {
+            "reveal key linkage"
+        }
+        Method::Encrypt => "encrypt data",
+        Method::Decrypt => "decrypt data",
+        Method::CreateHmac => "create an HMAC",
+        Method::VerifyHmac => "verify an HMAC",
+        Method::CreateSignature => "sign data",
+        Method::VerifySignature => "verify a signature",
+        Method::AcquireCertificate => "add a certificate",
+        Method::ProveCertificate => "prove a certificate",
+        Method::RelinquishCertificate => "remove a certificate",
+        _ => "read from your wallet",
+    }
+}
+
+/// Limits notifications to one per app within an interval.
+pub struct RateLimiter {
+    interval: Duration,
+    /// When each app was last notified about.
+    last: HashMap<String, Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last: HashMap::new(),
+        }
+    }
+
+    /// Whether a notification about `origin` may be shown at `now`, and if
+    /// so, record it. Requests without an origin share one allowance.
+    pub fn allow(&mut self, origin: Option<&str>, now: Instant) -> bool {
+        let interval = self.interval;
+        // Apps that went quiet are forgotten.
+        self.last
+            .retain(|_, shown| now.saturating_duration_since(*shown) < interval);
+        let key = origin.unwrap_or_default();
+        if self.last.contains_key(key) {
+            return false;
+        }
+        self.last.insert(key.to_string(), now);
+        true
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(MIN_INTERVAL)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use freedesktop::Notifier;
+
+#[cfg(target_os = "linux")]
+mod freedesktop {
+    use std::collections::HashMap;
+
+    use futures_util::StreamExt;
+    use zbus::{zvariant::Value, Connection};
+
+    use super::ACTION_SHOW;
+
+    #[zbus::proxy(
+        interface = "org.freedesktop.Notifications",
+        default_service = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications"
+    )]
+    trait Notifications {
+        #[allow(clippy::too_many_arguments)]
+        fn notify(
+            &self,
+            app_name: &str,
+            replaces_id: u32,
+            app_icon: &str,
+            summary: &str,
+            body: &str,
+            actions: &[&str],
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> zbus::Result<u32>;
+
+        #[zbus(signal)]
+        fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+
+        #[zbus(signal)]
+        fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+    }
+
+    /// Sends notifications through the desktop's notification service on
+    /// the session bus.
+    pub struct Notifier {
+        proxy: NotificationsProxy<'static>,
+    }
+
+    impl Notifier {
+        pub async fn connect() -> zbus::Result<Self> {
+            let connection = Connection::session().await?;
+            let proxy = NotificationsProxy::new(&connection).await?;
+            Ok(Self { proxy })
+        }
+
+        /// Show a notification that brings the wallet forward when clicked,
+        /// returning its ID.
+        pub async fn notify(&self, summary: &str, body: &str) -> zbus::Result<u32> {
+            let hints = HashMap::from([
+                ("desktop-entry", Value::from("metanet-desktop")),
+                ("category", Value::from("im.received")),
+            ]);
+            self.proxy
+                .notify(
+                    "Metanet Desktop",
+                    0,
+                    "metanet-desktop",
+                    summary,
+                    body,
+                    &[ACTION_SHOW, "Show Wallet"],
+                    hints,
+                    // The server's default timeout.
+                    -1,
+                )
+                .await
+        }
+
+        /// Call `on_click` with the ID of every notification clicked, until
+        /// the connection to the service is lost.
+        pub async fn watch_clicks(&self, on_click: impl Fn(u32)) -> zbus::Result<()> {
+            let mut actions = self.proxy.receive_action_invoked().await?;
+            while let Some(action) = actions.next().await {
+                let args = action.args()?;
+                if args.action_key == ACTION_SHOW {
+                    on_click(args.id);
+                }
+            }
+            Ok(())
+        }
+
+        /// Call `on_close` with the ID of every notification that expired
+        /// or was dismissed, until the connection to the service is lost.
+        pub async fn watch_closed(&self, on_close: impl Fn(u32)) -> zbus::Result<()> {
+            let mut closed = self.proxy.receive_notification_closed().await?;
+            while let Some(notification) = closed.next().await {
+                on_close(notification.args()?.id);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Working out which app a request came from.
+
+/// The origin of a request as the frontend sees it: the `host[:port]` of the
+/// `Origin` header, falling back to the legacy `Originator` header used by
+/// non-browser clients. Mirrors `parseOrigin` in `onWalletReady.ts`.
+///
+/// Returns `None` when neither header names a usable host, including the
+/// opaque `null` origin sent by sandboxed frames and local files.
+pub fn request_origin(headers: &[(String, String)]) -> Option<String> {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim())
+            .filter(|v| !v.is_empty())
+    };
+
+    if let Some(origin) = header("origin") {
+        // Browsers always send a full scheme://host[:port] origin.
+        return origin.split_once("://").and_then(|(_, rest)| host(rest));
+    }
+    header("originator").and_then(|originator| match originator.split_once("://") {
+        Some((_, rest)) => host(rest),
+        None => host(originator),
+    })
+}
+
+fn host(authority_and_path: &str) -> Option<String> {
+    let authority = authority_and_path
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    // Drop any userinfo, as URL parsing would.
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    if authority.is_empty() || authority == "null" {
+        None
+    } else {
+        Some(authority.to_ascii_lowercase())
+    }
+}
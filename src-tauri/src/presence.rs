@@ -0,0 +1,159 @@
+//! Whether the user is at the wallet, for the frontend to decide whether to
+//! auto-approve within limits, notify, or lock.
+//!
+//! The desktop binary reports the main window's state changes and the
+//! user's interaction with the wallet here, and polls [`Presence::update`]
+//! with the system-wide idle time where the platform provides it. Without
+//! it, time spent in other apps looks like time away. Changes
+//! are sent to the frontend as [`EVENT_WINDOW_STATE`] and
+//! [`EVENT_PRESENCE`] events.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use tokio::sync::watch;
+
+/// Event carrying a [`WindowStateEvent`] when the main window is focused,
+/// blurred, minimized or restored.
+pub const EVENT_WINDOW_STATE: &str = "window-state";
+/// Event carrying a [`PresenceEvent`] when the user goes idle or returns.
+pub const EVENT_PRESENCE: &str = "user-presence";
+
+/// How long without interaction before the user counts as idle.
+pub const IDLE_AFTER: Duration = Duration::from_secs(60);
+/// How often the idle time is checked.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WindowState {
+    Focused,
+    Blurred,
+    Minimized,
+    Restored,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStateEvent {
+    pub state: WindowState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PresenceEvent {
+    pub idle: bool,
+    /// Whole seconds since the user's last interaction.
+    pub idle_seconds: u64,
+    pub focused: bool,
+    pub minimized: bool,
+    /// Whether interaction with other apps counts too, rather than only
+    /// interaction with the wallet.
+    pub system_idle_known: bool,
+}
+
+pub struct Presence {
+    last_activity: Mutex<Instant>,
+    idle_after: Duration,
+    idle: AtomicBool,
+    focused: AtomicBool,
+    minimized: AtomicBool,
+    system_idle_known: AtomicBool,
+    /// The latest idle time, for anyone timing the user out.
+    idle_time: watch::Sender<Duration>,
+}
+
+impl Presence {
+    pub fn new(idle_after: Duration) -> Self {
+        Self {
+            last_activity: Mutex::new(Instant::now()),
+            idle_after,
+            idle: AtomicBool::new(false),
+            focused: AtomicBool::new(false),
+            minimized: AtomicBool::new(false),
+            system_idle_known: AtomicBool::new(false),
+            idle_time: watch::channel(Duration::ZERO).0,
+        }
+    }
+
+    /// Record that the user interacted with the wallet.
+    pub fn touch(&self) {
+        *self.last_activity.lock().unwrap() = Instant::now();
+    }
+
+    /// Record a change of the main window's state, returning the event to
+    /// send if it is news.
+    pub fn window_changed(&self, state: WindowState) -> Option<WindowStateEvent> {
+        let changed = match state {
+            WindowState::Focused | WindowState::Blurred => {
+                let focused = state == WindowState::Focused;
+                if focused {
+                    self.touch();
+                }
+                self.focused.swap(focused, Ordering::Relaxed) != focused
+            }
+            WindowState::Minimized | WindowState::Restored => {
+                let minimized = state == WindowState::Minimized;
+                self.minimized.swap(minimized, Ordering::Relaxed) != minimized
+            }
+        };
+        changed.then_some(WindowStateEvent { state })
+    }
+
+    /// Time since the user last interacted with the wallet, or with anything
+    /// at all when `system_idle` is known and shorter.
+    pub fn idle_time(&self, system_idle: Option<Duration>) -> Duration {
+        let wallet_idle = self.last_activity.lock().unwrap().elapsed();
+        system_idle.map_or(wallet_idle, |system_idle| system_idle.min(wallet_idle))
+    }
+
+    /// Re-check the idle time, returning the event to send when the user
+    /// went idle or came back.
+    pub fn update(&self, system_idle: Option<Duration>) -> Option<PresenceEvent> {
+        let idle_time = self.idle_time(system_idle);
+        self.system_idle_known
+            .store(system_idle.is_some(), Ordering::Relaxed);
+        self.idle_time.send_replace(idle_time);
+        let idle = idle_time >= self.idle_after;
+        (self.idle.swap(idle, Ordering::Relaxed) != idle).then(|| self.event(idle_time))
+    }
+
+    /// Whether the wallet should lock itself, having been left alone for
+    /// `timeout`. Never without the system idle time, when time spent in
+    /// other apps would count as time away.
+    pub fn auto_lock_due(&self, timeout: Duration) -> bool {
+        self.system_idle_known.load(Ordering::Relaxed) && *self.idle_time.borrow() >= timeout
+    }
+
+    /// The current presence, as last updated.
+    pub fn current(&self) -> PresenceEvent {
+        self.event(*self.idle_time.borrow())
+    }
+
+    /// Follow the idle time as it is updated.
+    pub fn watch_idle_time(&self) -> watch::Receiver<Duration> {
+        self.idle_time.subscribe()
+    }
+
+    fn event(&self, idle_time: Duration) -> PresenceEvent {
+        PresenceEvent {
+            idle: self.idle.load(Ordering::Relaxed),
+            idle_seconds: idle_time.as_secs(),
+            focused: self.focused.load(Ordering::Relaxed),
+            minimized: self.minimized.load(Ordering::Relaxed),
+            system_idle_known: self.system_idle_known.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self::new(IDLE_AFTER)
+    }
+}
@@ -0,0 +1,297 @@
+//! JSON-RPC 2.0 front end for the bridge.
+//!
+//! Each call names a BRC-100 method and carries its arguments object as
+//! `params`. Calls are validated and forwarded exactly like requests to the
+//! per-method paths. A batch's calls are queued for the wallet in the order
+//! they were sent, run concurrently from there, and are answered in that
+//! order.
+
+use std::sync::Arc;
+
+use futures_util::future::join_all;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::brc100::{validate_request, Method, WalletError};
+use crate::bridge::{Bridge, BridgeError, PeerCredentials, WalletRequest};
+use crate::idempotency::IDEMPOTENCY_KEY_HEADER;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// The wallet handled the call and returned an error.
+pub const WALLET_ERROR: i64 = -32000;
+/// The user paused app access to the wallet.
+pub const ACCESS_PAUSED: i64 = -32001;
+/// The wallet is locked until the user authenticates again.
+pub const WALLET_LOCKED: i64 = -32002;
+/// The wallet never answered the call.
+pub const NO_RESPONSE: i64 = -32003;
+/// An `Idempotency-Key` was reused for a call with different arguments.
+pub const IDEMPOTENCY_KEY_REUSED: i64 = -32004;
+
+/// Most calls accepted in a single batch.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+impl RpcResponse {
+    fn result(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    fn error(id: Value, code: i64, message: impl Into<String>, data: Option<Value>) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data,
+            }),
+            id,
+        }
+    }
+}
+
+/// Handle a JSON-RPC body, returning the serialized response or `None` when
+/// the body held only notifications.
+///
+/// `headers` and `peer` describe the enclosing HTTP request; they are passed
+/// to the wallet with every call so origin checks still apply.
+pub async fn handle(
+    bridge: &Arc<Bridge>,
+    headers: Vec<(String, String)>,
+    peer: Option<PeerCredentials>,
+    body: &[u8],
+) -> Option<String> {
+    // An Idempotency-Key names one request, not every call in a batch or
+    // session, so it is not passed on.
+    let headers: Vec<(String, String)> = headers
+        .into_iter()
+        .filter(|(k, _)| {
+            !k.eq_ignore_ascii_case("content-length")
+                && !k.eq_ignore_ascii_case(IDEMPOTENCY_KEY_HEADER)
+        })
+        .collect();
+
+    let value: Value = match serde_json::from_slice(body) {
+        Ok(value) => value,
+        Err(e) => {
+            let response = RpcResponse::error(Value::Null, PARSE_ERROR, e.to_string(), None);
+            return Some(to_json(&response));
+        }
+    };
+
+    match value {
+        Value::Array(calls) => {
+            if calls.is_empty() {
+                let response =
+                    RpcResponse::error(Value::Null, INVALID_REQUEST, "Empty batch", None);
+                return Some(to_json(&response));
+            }
+            if calls.len() > MAX_BATCH_SIZE {
+                let response = RpcResponse::error(
+                    Value::Null,
+                    INVALID_REQUEST,
+                    format!("Batches are limited to {} calls", MAX_BATCH_SIZE),
+                    None,
+                );
+                return Some(to_json(&response));
+            }
+
+            // The calls are first polled in array order, which queues each
+            // one for its lane before the next, so they reach the wallet in
+            // the order they were sent. Responses come back in that order.
+            let calls = calls
+                .into_iter()
+                .map(|call| handle_call(bridge, &headers, peer, call));
+            let responses: Vec<RpcResponse> = join_all(calls).await.into_iter().flatten().collect();
+            if responses.is_empty() {
+                None
+            } else {
+                Some(to_json(&responses))
+            }
+        }
+        call => handle_call(bridge, &headers, peer, call)
+            .await
+            .map(|response| to_json(&response)),
+    }
+}
+
+/// Handle one call object. Returns `None` for notifications.
+async fn handle_call(
+    bridge: &Arc<Bridge>,
+    headers: &[(String, String)],
+    peer: Option<PeerCredentials>,
+    call: Value,
+) -> Option<RpcResponse> {
+    let Value::Object(mut call) = call else {
+        return Some(RpcResponse::error(
+            Value::Null,
+            INVALID_REQUEST,
+            "Call must be an object",
+            None,
+        ));
+    };
+
+    // A call without an id is a notification and gets no response. Invalid
+    // ids are answered with a null id, as the specification requires.
+    let id = call.remove("id");
+    let is_notification = id.is_none();
+    let id = match id {
+        None => Value::Null,
+        Some(id @ (Value::Null | Value::Number(_) | Value::String(_))) => id,
+        Some(_) => {
+            return Some(RpcResponse::error(
+                Value::Null,
+                INVALID_REQUEST,
+                "id must be a string, number or null",
+                None,
+            ));
+        }
+    };
+
+    let response = call_method(bridge, headers, peer, id, call).await;
+    if is_notification {
+        None
+    } else {
+        Some(response)
+    }
+}
+
+async fn call_method(
+    bridge: &Arc<Bridge>,
+    headers: &[(String, String)],
+    peer: Option<PeerCredentials>,
+    id: Value,
+    mut call: serde_json::Map<String, Value>,
+) -> RpcResponse {
+    if call.get("jsonrpc") != Some(&json!("2.0")) {
+        return RpcResponse::error(id, INVALID_REQUEST, "jsonrpc must be \"2.0\"", None);
+    }
+    let method = match call.get("method") {
+        Some(Value::String(name)) => match Method::from_name(name) {
+            Some(method) => method,
+            None => {
+                return RpcResponse::error(
+                    id,
+                    METHOD_NOT_FOUND,
+                    format!("Unknown method: {}", name),
+                    None,
+                );
+            }
+        },
+        _ => return RpcResponse::error(id, INVALID_REQUEST, "method must be a string", None),
+    };
+
+    // BRC-100 methods take a single arguments object, passed either by name
+    // or as the only positional parameter.
+    let args = match call.remove("params") {
+        None => json!({}),
+        Some(params @ Value::Object(_)) => params,
+        Some(Value::Array(mut params)) if params.len() == 1 && params[0].is_object() => {
+            params.remove(0)
+        }
+        Some(_) => {
+            return RpcResponse::error(
+                id,
+                INVALID_PARAMS,
+                "params must be an arguments object",
+                None,
+            );
+        }
+    };
+    let body = args.to_string();
+
+    if let Err(fields) = validate_request(method, &body) {
+        let error = WalletError::invalid_parameters(method.name(), fields);
+        return RpcResponse::error(
+            id,
+            INVALID_PARAMS,
+            error.description.clone(),
+            serde_json::to_value(&error).ok(),
+        );
+    }
+
+    let request = WalletRequest {
+        method: "POST".to_string(),
+        path: method.path(),
+        headers: headers.to_vec(),
+        body,
+        peer,
+    };
+    match bridge.forward(request).await {
+        Ok(response) => {
+            // Wallet bodies are JSON; anything else is relayed as a string.
+            let body = match response.body.into_string().await {
+                Ok(body) => body,
+                Err(err) => return bridge_error(id, err),
+            };
+            let value = serde_json::from_str::<Value>(&body).unwrap_or(Value::String(body));
+            if response.status.is_success() {
+                RpcResponse::result(id, value)
+            } else {
+                let message = ["description", "message"]
+                    .iter()
+                    .find_map(|key| value.get(key).and_then(Value::as_str))
+                    .unwrap_or("Wallet error")
+                    .to_string();
+                RpcResponse::error(id, WALLET_ERROR, message, Some(value))
+            }
+        }
+        Err(err) => bridge_error(id, err),
+    }
+}
+
+fn bridge_error(id: Value, err: BridgeError) -> RpcResponse {
+    let error = err.to_wallet_error();
+    RpcResponse::error(
+        id,
+        error_code(&err),
+        error.description.clone(),
+        serde_json::to_value(&error).ok(),
+    )
+}
+
+/// The JSON-RPC error code for a call the bridge failed, so clients can
+/// tell the wallet being unavailable from the bridge breaking.
+fn error_code(err: &BridgeError) -> i64 {
+    match err {
+        BridgeError::Paused => ACCESS_PAUSED,
+        BridgeError::Locked => WALLET_LOCKED,
+        BridgeError::NoResponse | BridgeError::IncompleteBody(_) => NO_RESPONSE,
+        BridgeError::IdempotencyKeyReused => IDEMPOTENCY_KEY_REUSED,
+        BridgeError::InvalidIdempotencyKey(_) => INVALID_PARAMS,
+        BridgeError::Serialize(_) | BridgeError::Emit(_) | BridgeError::InvalidStatus(_) => {
+            INTERNAL_ERROR
+        }
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).expect("JSON-RPC responses are always serializable")
+}
@@ -0,0 +1,127 @@
+//! Scheduling lanes for requests forwarded to the wallet.
+//!
+//! Every [`MethodClass`] gets its own lane with a concurrency limit, so a
+//! `createAction` waiting on the user only holds up other spending calls
+//! while reads and key operations keep flowing. Waiting requests are let
+//! through in arrival order.
+//!
+//! Optionally, the calls that build and settle transactions are also
+//! serialized per origin, so two parallel `createAction`s from one app
+//! cannot race over UTXO selection.
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::{Mutex, OwnedMutexGuard, Semaphore, SemaphorePermit};
+
+use crate::brc100::{Method, MethodClass};
+
+/// Methods that are run one at a time, in arrival order, for each origin
+/// when per-origin ordering is enabled.
+pub const ORDERED_METHODS: [Method; 4] = [
+    Method::CreateAction,
+    Method::SignAction,
+    Method::AbortAction,
+    Method::InternalizeAction,
+];
+
+/// How many requests of each class may be with the wallet at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneLimits {
+    pub read_only: usize,
+    pub crypto: usize,
+    pub spending: usize,
+}
+
+impl Default for LaneLimits {
+    fn default() -> Self {
+        Self {
+            read_only: 32,
+            crypto: 8,
+            // Spending calls are interactive; one approval at a time.
+            spending: 1,
+        }
+    }
+}
+
+pub struct Scheduler {
+    read_only: Semaphore,
+    crypto: Semaphore,
+    spending: Semaphore,
+    /// One FIFO queue per origin with an ordered call in flight, when
+    /// per-origin ordering is enabled.
+    origin_queues: Option<DashMap<String, Arc<Mutex<()>>>>,
+}
+
+/// A request's place with the wallet. Dropping it lets the next request in
+/// the same lane (and origin queue) through.
+pub struct Admission<'a> {
+    _permit: Option<SemaphorePermit<'a>>,
+    _turn: Option<OriginTurn<'a>>,
+}
+
+struct OriginTurn<'a> {
+    queues: &'a DashMap<String, Arc<Mutex<()>>>,
+    origin: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for OriginTurn<'_> {
+    fn drop(&mut self) {
+        self.guard.take();
+        // Forget the queue once nobody else is waiting in it.
+        self.queues
+            .remove_if(&self.origin, |_, queue| Arc::strong_count(queue) == 1);
+    }
+}
+
+impl Scheduler {
+    pub fn new(limits: LaneLimits, order_per_origin: bool) -> Self {
+        Self {
+            read_only: Semaphore::new(limits.read_only.max(1)),
+            crypto: Semaphore::new(limits.crypto.max(1)),
+            spending: Semaphore::new(limits.spending.max(1)),
+            origin_queues: order_per_origin.then(DashMap::new),
+        }
+    }
+
+    /// Wait until a `method` request from `origin` may be sent to the
+    /// wallet. It keeps its place until the returned admission is dropped.
+    ///
+    /// `waitForAuthentication` is not scheduled: it can be outstanding for as
+    /// long as the user takes to log in and would otherwise pin a read slot.
+    pub async fn admit(&self, method: Method, origin: Option<&str>) -> Admission<'_> {
+        if method == Method::WaitForAuthentication {
+            return Admission {
+                _permit: None,
+                _turn: None,
+            };
+        }
+
+        // Queue per origin first, so a request waiting behind its own app's
+        // previous call does not hold a lane slot other apps could use.
+        let turn = match (&self.origin_queues, origin) {
+            (Some(queues), Some(origin)) if ORDERED_METHODS.contains(&method) => {
+                let queue = queues.entry(origin.to_string()).or_default().clone();
+                let guard = queue.lock_owned().await;
+                Some(OriginTurn {
+                    queues,
+                    origin: origin.to_string(),
+                    guard: Some(guard),
+                })
+            }
+            _ => None,
+        };
+
+        let lane = match method.class() {
+            MethodClass::ReadOnly => &self.read_only,
+            MethodClass::Crypto => &self.crypto,
+            MethodClass::Spending => &self.spending,
+        };
+        Admission {
+            // The semaphores are never closed.
+            _permit: lane.acquire().await.ok(),
+            _turn: turn,
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! The hyper service serving the wallet JSON API on localhost.
+
+use std::{convert::Infallible, sync::Arc};
+
+use hyper::{
+    body::{Bytes, HttpBody},
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY},
+    Body, Request, Response, StatusCode,
+};
+
+use crate::brc100::{
+    validate_request, Method, WalletError, ERR_INVALID_BODY, ERR_METHOD_NOT_ALLOWED,
+    ERR_UNKNOWN_METHOD,
+};
+use crate::bridge::{Bridge, PeerCredentials, WalletBody, WalletRequest};
+use crate::compress::{self, Encoding};
+use crate::rpc;
+use crate::sse;
+use crate::ws;
+
+/// Path of the JSON-RPC 2.0 endpoint.
+pub const RPC_PATH: &str = "/rpc";
+
+/// Largest request body accepted. Wallet arguments, even a full JSON-RPC
+/// batch of them, stay well below this.
+pub const MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+/// Append the permissive CORS headers every bridge response carries.
+pub fn with_cors(mut res: Response<Body>) -> Response<Body> {
+    let headers = res.headers_mut();
+    headers.insert("Access-Control-Allow-Origin", "*".parse().unwrap());
+    headers.insert("Access-Control-Allow-Headers", "*".parse().unwrap());
+    headers.insert("Access-Control-Allow-Methods", "*".parse().unwrap());
+    headers.insert("Access-Control-Expose-Headers", "*".parse().unwrap());
+    headers.insert(
+        "Access-Control-Allow-Private-Network",
+        "true".parse().unwrap(),
+    );
+    res
+}
+
+/// Build a JSON response in the BRC-100 error shape for failures generated
+/// by the bridge itself.
+pub fn error_response(
+    status: StatusCode,
+    code: &str,
+    description: impl Into<String>,
+) -> Response<Body> {
+    wallet_error_response(status, WalletError::new(code, description))
+}
+
+pub fn wallet_error_response(status: StatusCode, error: WalletError) -> Response<Body> {
+    json_response(status, error.to_json())
+}
+
+pub fn json_response(status: StatusCode, body: String) -> Response<Body> {
+    let mut res = Response::new(Body::from(body));
+    *res.status_mut() = status;
+    res.headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    with_cors(res)
+}
+
+/// Collect request headers into the `(name, value)` pairs the frontend expects.
+pub fn header_pairs(req: &Request<Body>) -> Vec<(String, String)> {
+    req.headers()
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
+        .collect()
+}
+
+/// The credentials of the peer process, for requests received over a
+/// transport that knows them.
+pub fn peer_credentials(req: &Request<Body>) -> Option<PeerCredentials> {
+    req.extensions().get::<PeerCredentials>().copied()
+}
+
+/// The response encoding the client accepts, if any.
+fn accepted_encoding(req: &Request<Body>) -> Option<Encoding> {
+    req.headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(compress::negotiate)
+}
+
+/// Build a response for a wallet body, compressed when the client allows.
+fn body_response(
+    status: StatusCode,
+    body: WalletBody,
+    encoding: Option<Encoding>,
+) -> Response<Body> {
+    let (body, applied) = compress::response_body(body, encoding);
+    let mut res = Response::new(body);
+    *res.status_mut() = status;
+    let headers = res.headers_mut();
+    if encoding.is_some() {
+        headers.insert(VARY, "Accept-Encoding".parse().unwrap());
+    }
+    if let Some(applied) = applied {
+        headers.insert(CONTENT_ENCODING, applied.name().parse().unwrap());
+    }
+    with_cors(res)
+}
+
+/// Read a whole request body, up to [`MAX_BODY_SIZE`]. Bodies that fail to
+/// arrive or are too large are answered with a BRC-100 error.
+async fn read_body(req: Request<Body>) -> Result<Bytes, Response<Body>> {
+    let too_large = || {
+        error_response(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            ERR_INVALID_BODY,
+            format!("Request bodies are limited to {} bytes", MAX_BODY_SIZE),
+        )
+    };
+    let declared = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|len| len > MAX_BODY_SIZE as u64) {
+        return Err(too_large());
+    }
+
+    let mut body = req.into_body();
+    let mut whole_body = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| {
+            error_response(
+                StatusCode::BAD_REQUEST,
+                ERR_INVALID_BODY,
+                format!("Failed to read the request body: {}", e),
+            )
+        })?;
+        if whole_body.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(too_large());
+        }
+        whole_body.extend_from_slice(&chunk);
+    }
+    Ok(whole_body.into())
+}
+
+/// Serve a single HTTP request.
+pub async fn handle(bridge: Arc<Bridge>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    // Intercept any OPTIONS requests
+    if req.method() == hyper::Method::OPTIONS {
+        return Ok(with_cors(Response::new(Body::empty())));
+    }
+
+    if req.uri().path() == RPC_PATH {
+        return Ok(handle_rpc(bridge, req).await);
+    }
+    if req.uri().path() == ws::WS_PATH {
+        return Ok(ws::upgrade(bridge, req));
+    }
+    if req.uri().path() == sse::EVENTS_PATH {
+        return Ok(sse::stream(bridge, req));
+    }
+
+    // Only BRC-100 methods are forwarded to the wallet.
+    let wallet_method = match Method::from_path(req.uri().path()) {
+        Some(wallet_method) => wallet_method,
+        None => {
+            return Ok(error_response(
+                StatusCode::NOT_FOUND,
+                ERR_UNKNOWN_METHOD,
+                format!("Unknown wallet path: {}", req.uri().path()),
+            ));
+        }
+    };
+    if !wallet_method.allows_http_method(req.method().as_str()) {
+        return Ok(error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            ERR_METHOD_NOT_ALLOWED,
+            format!(
+                "{} does not accept {} requests",
+                wallet_method.path(),
+                req.method()
+            ),
+        ));
+    }
+
+    // Extract the HTTP method, headers and peer.
+    let method = req.method().to_string();
+    let headers = header_pairs(&req);
+    let peer = peer_credentials(&req);
+    let encoding = accepted_encoding(&req);
+
+    // Read the full request body.
+    let whole_body = match read_body(req).await {
+        Ok(whole_body) => whole_body,
+        Err(response) => return Ok(response),
+    };
+    let body = String::from_utf8_lossy(&whole_body).to_string();
+
+    // Reject malformed arguments before they reach the wallet.
+    if let Err(fields) = validate_request(wallet_method, &body) {
+        return Ok(wallet_error_response(
+            StatusCode::BAD_REQUEST,
+            WalletError::invalid_parameters(wallet_method.name(), fields),
+        ));
+    }
+
+    let request = WalletRequest {
+        method,
+        path: wallet_method.path(),
+        headers,
+        body,
+        peer,
+    };
+    match bridge.forward(request).await {
+        Ok(response) => Ok(body_response(response.status, response.body, encoding)),
+        Err(err) => Ok(wallet_error_response(err.status(), err.to_wallet_error())),
+    }
+}
+
+async fn handle_rpc(bridge: Arc<Bridge>, req: Request<Body>) -> Response<Body> {
+    if req.method() != hyper::Method::POST {
+        return error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            ERR_METHOD_NOT_ALLOWED,
+            format!("{} only accepts POST requests", RPC_PATH),
+        );
+    }
+    let headers = header_pairs(&req);
+    let peer = peer_credentials(&req);
+    let encoding = accepted_encoding(&req);
+    let whole_body = match read_body(req).await {
+        Ok(whole_body) => whole_body,
+        Err(response) => return response,
+    };
+
+    match rpc::handle(&bridge, headers, peer, &whole_body).await {
+        Some(body) => {
+            let mut res = body_response(StatusCode::OK, WalletBody::Full(body), encoding);
+            res.headers_mut()
+                .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+            res
+        }
+        // Only notifications were sent, so there is nothing to answer.
+        None => {
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::NO_CONTENT;
+            with_cors(res)
+        }
+    }
+}
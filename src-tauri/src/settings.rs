@@ -0,0 +1,98 @@
+//! User settings persisted as JSON in the app config dir.
+
+use std::{fs, io, path::Path, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::bridge::BridgeConfig;
+use crate::schedule::LaneLimits;
+
+/// File name of the settings file inside the app config dir.
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Minutes of inactivity before the wallet locks, unless changed.
+pub const DEFAULT_AUTO_LOCK_MINUTES: u32 = 15;
+
+/// Seconds the answer to a spending call is replayed to retries with the
+/// same `Idempotency-Key`, unless changed.
+pub const DEFAULT_IDEMPOTENCY_WINDOW_SECONDS: u64 = 10 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Also serve the API over HTTPS with the local CA. Applied at startup.
+    pub https: bool,
+    /// Quit when the main window is closed instead of hiding it in the
+    /// tray, which stops the bridge connected apps rely on.
+    pub quit_on_close: bool,
+    /// Whether the user was told that closing the window keeps the app
+    /// running in the tray.
+    pub close_notice_shown: bool,
+    /// Start the app, minimized to the tray, when the user logs in.
+    pub launch_at_login: bool,
+    /// Lock the wallet after this many minutes without the user; 0 never
+    /// locks. Has no effect where the desktop's idle time is unknown.
+    pub auto_lock_minutes: u32,
+    /// Run each app's spending calls one at a time, in the order they
+    /// arrived, while other apps' calls may run alongside them. Applied at
+    /// startup.
+    pub order_per_origin: bool,
+    /// How many spending calls, from all apps together, may be with the
+    /// wallet at once; the rest wait in arrival order. With 1, the default,
+    /// every spending call already runs alone and `order_per_origin` changes
+    /// nothing. Applied at startup.
+    pub concurrent_spending_calls: usize,
+    /// How long, in seconds, the answer to a spending call is replayed to
+    /// retries with the same `Idempotency-Key`; 0 ignores the header.
+    /// Applied at startup.
+    pub idempotency_window_seconds: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            https: false,
+            quit_on_close: false,
+            close_notice_shown: false,
+            launch_at_login: false,
+            auto_lock_minutes: DEFAULT_AUTO_LOCK_MINUTES,
+            order_per_origin: false,
+            concurrent_spending_calls: LaneLimits::default().spending,
+            idempotency_window_seconds: DEFAULT_IDEMPOTENCY_WINDOW_SECONDS,
+        }
+    }
+}
+
+impl Settings {
+    /// Read the settings from `dir`, falling back to the defaults when the
+    /// file is missing or unreadable.
+    pub fn load(dir: &Path) -> Self {
+        let path = dir.join(SETTINGS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("Ignoring invalid settings in {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(dir.join(SETTINGS_FILE), json)
+    }
+
+    /// How the bridge is set up with these settings.
+    pub fn bridge_config(&self) -> BridgeConfig {
+        BridgeConfig {
+            lanes: LaneLimits {
+                spending: self.concurrent_spending_calls,
+                ..LaneLimits::default()
+            },
+            order_per_origin: self.order_per_origin,
+            idempotency_window: Duration::from_secs(self.idempotency_window_seconds),
+            ..BridgeConfig::default()
+        }
+    }
+}
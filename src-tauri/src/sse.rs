@@ -0,0 +1,108 @@
+//! Server-Sent Events stream of wallet state changes.
+//!
+//! `GET /events` first replays the current wallet state (authentication,
+//! network and height) and then streams every event as it is published, so
+//! apps can react without polling `/isAuthenticated`. An optional
+//! `?events=authenticated,networkChanged` query limits the stream to those
+//! event names.
+
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use hyper::{
+    body::{Bytes, Sender},
+    header::{CACHE_CONTROL, CONTENT_TYPE},
+    Body, Request, Response, StatusCode,
+};
+use tokio::sync::broadcast;
+use url::form_urlencoded;
+
+use crate::brc100::ERR_METHOD_NOT_ALLOWED;
+use crate::bridge::{Bridge, WalletEvent};
+use crate::origin::request_origin;
+use crate::server::{error_response, header_pairs, with_cors};
+
+/// Path of the event stream.
+pub const EVENTS_PATH: &str = "/events";
+
+/// How often a comment is sent to keep idle connections (and proxies) open.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Open an event stream for `req`.
+pub fn stream(bridge: Arc<Bridge>, req: Request<Body>) -> Response<Body> {
+    if req.method() != hyper::Method::GET {
+        return error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            ERR_METHOD_NOT_ALLOWED,
+            format!("{} only accepts GET requests", EVENTS_PATH),
+        );
+    }
+
+    let origin = request_origin(&header_pairs(&req));
+    let wanted = req.uri().query().and_then(event_filter);
+    let filter = move |event: &WalletEvent| {
+        let visible = match &origin {
+            Some(origin) => event.visible_to(origin),
+            None => event.origin.is_none(),
+        };
+        visible && wanted.as_ref().is_none_or(|w| w.contains(&event.event))
+    };
+
+    // Subscribe before taking the snapshot so nothing falls in between.
+    let mut events = bridge.subscribe_events();
+    let snapshot = bridge.current_state();
+    let (mut sender, body) = Body::channel();
+
+    tokio::spawn(async move {
+        for event in snapshot.iter().filter(|e| filter(e)) {
+            if send_event(&mut sender, event).await.is_err() {
+                return;
+            }
+        }
+
+        let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+        keepalive.tick().await;
+        loop {
+            let sent = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) if filter(&event) => send_event(&mut sender, &event).await,
+                    Ok(_) => Ok(()),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        eprintln!("Event stream missed {} events", missed);
+                        Ok(())
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                },
+                _ = keepalive.tick() => sender.send_data(Bytes::from_static(b": keepalive\n\n")).await,
+            };
+            // The client went away.
+            if sent.is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut res = Response::new(body);
+    *res.status_mut() = StatusCode::OK;
+    let headers = res.headers_mut();
+    headers.insert(CONTENT_TYPE, "text/event-stream".parse().unwrap());
+    headers.insert(CACHE_CONTROL, "no-cache".parse().unwrap());
+    with_cors(res)
+}
+
+async fn send_event(sender: &mut Sender, event: &WalletEvent) -> Result<(), hyper::Error> {
+    let frame = format!("event: {}\ndata: {}\n\n", event.event, event.data);
+    sender.send_data(Bytes::from(frame)).await
+}
+
+/// Parse the `events` query parameter into a set of event names.
+fn event_filter(query: &str) -> Option<HashSet<String>> {
+    form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "events")
+        .map(|(_, value)| {
+            value
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+}
@@ -0,0 +1,313 @@
+//! Optional HTTPS listener backed by a locally generated CA.
+//!
+//! The first time HTTPS is enabled a private CA and a `localhost` leaf
+//! certificate signed by it are written to the `tls` directory of the app
+//! data dir. Users can export the CA and choose to trust it. The leaf is
+//! reissued well before it expires; the CA is only replaced when it nears
+//! the end of its much longer lifetime, after which it must be trusted again.
+//!
+//! The CA is name-constrained to `localhost`, `127.0.0.1` and `::1`, so
+//! even if its key leaks it cannot vouch for any other site. The keys are
+//! kept in a directory only the user can enter, in files only the user can
+//! read (mode 0700 and 0600 on Unix; on Windows the app data dir is already
+//! private to the user). CAs issued before the constraint existed are
+//! replaced.
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use hyper::{server::conn::Http, service::service_fn};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CidrSubnet, DnType, ExtendedKeyUsagePurpose,
+    GeneralSubtree, IsCa, KeyPair, KeyUsagePurpose, NameConstraints, SanType,
+};
+use time::{Duration, OffsetDateTime};
+use tokio::net::TcpListener;
+use tokio_rustls::{rustls, TlsAcceptor};
+
+use crate::bridge::Bridge;
+use crate::server;
+
+/// Address of the HTTPS listener, next to the plain HTTP one on 3321.
+pub const HTTPS_ADDR: &str = "127.0.0.1:3322";
+
+const CA_CERT_FILE: &str = "ca.pem";
+const CA_KEY_FILE: &str = "ca-key.pem";
+const LEAF_CERT_FILE: &str = "localhost.pem";
+const LEAF_KEY_FILE: &str = "localhost-key.pem";
+
+const CA_LIFETIME: Duration = Duration::days(3650);
+const LEAF_LIFETIME: Duration = Duration::days(90);
+/// Certificates are reissued once they are this close to expiring.
+const RENEW_BEFORE: Duration = Duration::days(30);
+/// How often the running listener checks whether a renewal is due.
+const RENEW_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(12 * 60 * 60);
+
+#[derive(Debug)]
+pub enum TlsError {
+    Io(io::Error),
+    Certificate(rcgen::Error),
+    Config(rustls::Error),
+    NoPrivateKey,
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Io(e) => write!(f, "{}", e),
+            TlsError::Certificate(e) => write!(f, "certificate generation failed: {}", e),
+            TlsError::Config(e) => write!(f, "invalid TLS configuration: {}", e),
+            TlsError::NoPrivateKey => write!(f, "no private key found for the leaf certificate"),
+        }
+    }
+}
+
+impl From<io::Error> for TlsError {
+    fn from(e: io::Error) -> Self {
+        TlsError::Io(e)
+    }
+}
+
+impl From<rcgen::Error> for TlsError {
+    fn from(e: rcgen::Error) -> Self {
+        TlsError::Certificate(e)
+    }
+}
+
+impl From<rustls::Error> for TlsError {
+    fn from(e: rustls::Error) -> Self {
+        TlsError::Config(e)
+    }
+}
+
+/// The CA and leaf certificate kept in a directory.
+#[derive(Debug, Clone)]
+pub struct LocalCa {
+    dir: PathBuf,
+}
+
+impl LocalCa {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the PEM-encoded CA certificate users can choose to trust.
+    pub fn ca_cert_path(&self) -> PathBuf {
+        self.dir.join(CA_CERT_FILE)
+    }
+
+    /// Make sure a valid CA and leaf exist, issuing whichever is missing or
+    /// close to expiry. Returns whether anything was (re)issued.
+    pub fn ensure(&self) -> Result<bool, TlsError> {
+        create_private_dir(&self.dir)?;
+        let renew_at = OffsetDateTime::now_utc() + RENEW_BEFORE;
+
+        let (ca, ca_key, ca_issued) = match self.load_ca(renew_at) {
+            Some((ca, ca_key)) => (ca, ca_key, false),
+            None => {
+                let (ca, ca_key) = issue_ca()?;
+                write_private(&self.dir.join(CA_KEY_FILE), &ca_key.serialize_pem())?;
+                fs::write(self.ca_cert_path(), ca.pem())?;
+                (ca, ca_key, true)
+            }
+        };
+
+        // A new CA invalidates the old leaf even if it has not expired.
+        if !ca_issued && self.leaf_is_current(renew_at) {
+            return Ok(false);
+        }
+        let (leaf, leaf_key) = issue_leaf(&ca, &ca_key)?;
+        write_private(&self.dir.join(LEAF_KEY_FILE), &leaf_key.serialize_pem())?;
+        fs::write(self.dir.join(LEAF_CERT_FILE), leaf.pem())?;
+        Ok(true)
+    }
+
+    /// Build a rustls config serving the current leaf certificate.
+    pub fn server_config(&self) -> Result<Arc<rustls::ServerConfig>, TlsError> {
+        let cert_pem = fs::read(self.dir.join(LEAF_CERT_FILE))?;
+        let key_pem = fs::read(self.dir.join(LEAF_KEY_FILE))?;
+        let certs = rustls_pemfile::certs(&mut cert_pem.as_slice()).collect::<Result<_, _>>()?;
+        let key =
+            rustls_pemfile::private_key(&mut key_pem.as_slice())?.ok_or(TlsError::NoPrivateKey)?;
+
+        // Pin the provider so another crate enabling a different rustls
+        // backend cannot make the process default ambiguous.
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+
+    /// The stored CA, unless it is missing, unreadable or due for renewal.
+    /// The returned certificate is re-signed from the stored parameters; it
+    /// is only used as the issuer when signing leaves.
+    fn load_ca(&self, renew_at: OffsetDateTime) -> Option<(Certificate, KeyPair)> {
+        let cert_pem = fs::read_to_string(self.ca_cert_path()).ok()?;
+        let key_pem = fs::read_to_string(self.dir.join(CA_KEY_FILE)).ok()?;
+        let params = CertificateParams::from_ca_cert_pem(&cert_pem).ok()?;
+        if params.not_after <= renew_at || params.name_constraints != Some(name_constraints()) {
+            return None;
+        }
+        let key = KeyPair::from_pem(&key_pem).ok()?;
+        let ca = params.self_signed(&key).ok()?;
+        Some((ca, key))
+    }
+
+    fn leaf_is_current(&self, renew_at: OffsetDateTime) -> bool {
+        fs::read_to_string(self.dir.join(LEAF_CERT_FILE))
+            .ok()
+            .and_then(|pem| CertificateParams::from_ca_cert_pem(&pem).ok())
+            .is_some_and(|params| params.not_after > renew_at)
+            && self.dir.join(LEAF_KEY_FILE).exists()
+    }
+}
+
+fn issue_ca() -> Result<(Certificate, KeyPair), TlsError> {
+    let now = OffsetDateTime::now_utc();
+    let mut params = CertificateParams::default();
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "Metanet Desktop Local CA");
+    params
+        .distinguished_name
+        .push(DnType::OrganizationName, "Metanet Desktop");
+    params.is_ca = IsCa::Ca(BasicConstraints::Constrained(0));
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    params.name_constraints = Some(name_constraints());
+    params.not_before = now - Duration::days(1);
+    params.not_after = now + CA_LIFETIME;
+
+    let key = KeyPair::generate()?;
+    let cert = params.self_signed(&key)?;
+    Ok((cert, key))
+}
+
+/// The only names the CA may issue certificates for.
+fn name_constraints() -> NameConstraints {
+    NameConstraints {
+        permitted_subtrees: vec![
+            GeneralSubtree::DnsName("localhost".to_string()),
+            GeneralSubtree::IpAddress(CidrSubnet::from_addr_prefix(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                32,
+            )),
+            GeneralSubtree::IpAddress(CidrSubnet::from_addr_prefix(
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+                128,
+            )),
+        ],
+        excluded_subtrees: Vec::new(),
+    }
+}
+
+fn issue_leaf(ca: &Certificate, ca_key: &KeyPair) -> Result<(Certificate, KeyPair), TlsError> {
+    let now = OffsetDateTime::now_utc();
+    let mut params = CertificateParams::new(vec!["localhost".to_string()])?;
+    params.subject_alt_names.extend([
+        SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+        SanType::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+    ]);
+    params
+        .distinguished_name
+        .push(DnType::CommonName, "localhost");
+    params.key_usages = vec![
+        KeyUsagePurpose::DigitalSignature,
+        KeyUsagePurpose::KeyEncipherment,
+    ];
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+    params.not_before = now - Duration::days(1);
+    params.not_after = now + LEAF_LIFETIME;
+
+    let key = KeyPair::generate()?;
+    let cert = params.signed_by(&key, ca, ca_key)?;
+    Ok((cert, key))
+}
+
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Write a private key readable only by the current user.
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    // The mode only applies to new files; an existing one may be wider.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents.as_bytes())
+}
+
+/// Issue certificates as needed and serve HTTPS on [`HTTPS_ADDR`] until an
+/// accept fails. Certificates are renewed while the listener runs; new
+/// connections pick up the renewed leaf.
+pub async fn serve(bridge: Arc<Bridge>, ca: LocalCa) -> Result<(), TlsError> {
+    ca.ensure()?;
+    let config = Arc::new(RwLock::new(ca.server_config()?));
+    let listener = TcpListener::bind(HTTPS_ADDR).await?;
+
+    {
+        let config = config.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RENEW_CHECK_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let renewed = match ca.ensure() {
+                    Ok(true) => ca.server_config(),
+                    Ok(false) => continue,
+                    Err(e) => Err(e),
+                };
+                match renewed {
+                    Ok(renewed) => *config.write().unwrap() = renewed,
+                    Err(e) => eprintln!("Failed to renew the localhost certificate: {}", e),
+                }
+            }
+        });
+    }
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let acceptor = TlsAcceptor::from(config.read().unwrap().clone());
+        let bridge = bridge.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("TLS handshake failed: {}", e);
+                    return;
+                }
+            };
+            let service = service_fn(move |req| server::handle(bridge.clone(), req));
+            if let Err(e) = Http::new()
+                .serve_connection(stream, service)
+                .with_upgrades()
+                .await
+            {
+                eprintln!("HTTPS connection error: {}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,50 @@
+//! Labels and icons for the system tray.
+//!
+//! The tray itself is built in the desktop binary; what it shows for a
+//! given bridge state is worked out here.
+
+/// The tooltip for the tray icon.
+pub fn tooltip(pending: usize, paused: bool) -> String {
+    let mut tooltip = "Metanet Desktop".to_string();
+    if paused {
+        tooltip.push_str(" — app access paused");
+    }
+    if pending > 0 {
+        tooltip.push_str(" — ");
+        tooltip.push_str(&pending_label(pending));
+    }
+    tooltip
+}
+
+/// The menu label for the number of requests waiting on the wallet.
+pub fn pending_label(pending: usize) -> String {
+    match pending {
+        0 => "No pending requests".to_string(),
+        1 => "1 pending request".to_string(),
+        n => format!("{} pending requests", n),
+    }
+}
+
+/// Color of the badge drawn on the icon while requests are waiting.
+const BADGE_RGBA: [u8; 4] = [0xe5, 0x39, 0x35, 0xff];
+
+/// A copy of an RGBA icon with a badge in its top-right corner, marking
+/// that approvals are waiting.
+pub fn badged_icon(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut badged = rgba.to_vec();
+    let (width, height) = (width as i64, height as i64);
+    let radius = width.min(height) / 4;
+    let (cx, cy) = (width - radius - 1, radius);
+    for y in (cy - radius).max(0)..=(cy + radius).min(height - 1) {
+        for x in (cx - radius).max(0)..=(cx + radius).min(width - 1) {
+            let (dx, dy) = (x - cx, y - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * width + x) * 4) as usize;
+                if let Some(pixel) = badged.get_mut(offset..offset + 4) {
+                    pixel.copy_from_slice(&BADGE_RGBA);
+                }
+            }
+        }
+    }
+    badged
+}
@@ -0,0 +1,106 @@
+//! Unix domain socket transport for local native clients (Linux only).
+//!
+//! The socket serves the same API as the TCP listener, but lives in a
+//! `0700` directory under `$XDG_RUNTIME_DIR` and is itself `0600`, so only
+//! the user running the wallet can connect. The kernel-reported credentials
+//! of the connecting process are attached to every request event.
+
+use std::{
+    fs, io,
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use hyper::{server::conn::Http, service::service_fn, Body, Request};
+use tokio::net::{UnixListener, UnixStream};
+
+use crate::bridge::{Bridge, PeerCredentials};
+use crate::server;
+
+/// Directory under the runtime dir holding the socket.
+const SOCKET_DIR: &str = "metanet-desktop";
+/// File name of the socket.
+const SOCKET_NAME: &str = "bridge.sock";
+
+/// Where the socket lives, or `None` when `$XDG_RUNTIME_DIR` is not set.
+pub fn socket_path() -> Option<PathBuf> {
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")?;
+    Some(
+        PathBuf::from(runtime_dir)
+            .join(SOCKET_DIR)
+            .join(SOCKET_NAME),
+    )
+}
+
+/// Bind the socket at `path` and serve connections until an accept fails.
+pub async fn serve(bridge: Arc<Bridge>, path: &Path) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "socket path has no parent"))?;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+    // The directory may predate us; make sure it is still private.
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+    let owner = fs::metadata(dir)?.uid();
+
+    // A socket left behind by a previous run would make bind fail.
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    serve_listener(bridge, listener, owner).await
+}
+
+/// Serve connections on `listener` from processes of user `owner` until an
+/// accept fails. Anyone else's connections are closed unanswered.
+pub async fn serve_listener(
+    bridge: Arc<Bridge>,
+    listener: UnixListener,
+    owner: u32,
+) -> io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let Some(peer) = peer_credentials(&stream) else {
+            continue;
+        };
+        // Permissions already keep other users out; this guards against
+        // the socket being reachable some other way.
+        if peer.uid != owner {
+            eprintln!("Rejected Unix socket connection from uid {}", peer.uid);
+            continue;
+        }
+
+        let bridge = bridge.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |mut req: Request<Body>| {
+                req.extensions_mut().insert(peer);
+                server::handle(bridge.clone(), req)
+            });
+            if let Err(e) = Http::new()
+                .serve_connection(stream, service)
+                .with_upgrades()
+                .await
+            {
+                eprintln!("Unix socket connection error: {}", e);
+            }
+        });
+    }
+}
+
+fn peer_credentials(stream: &UnixStream) -> Option<PeerCredentials> {
+    match stream.peer_cred() {
+        Ok(cred) => Some(PeerCredentials {
+            pid: cred.pid(),
+            uid: cred.uid(),
+            gid: cred.gid(),
+        }),
+        Err(e) => {
+            eprintln!("Failed to read Unix socket peer credentials: {}", e);
+            None
+        }
+    }
+}
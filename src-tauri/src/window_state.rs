@@ -0,0 +1,139 @@
+//! The main window's size, position and monitor, remembered across runs.
+//!
+//! Geometry is saved as JSON in the app config dir when the window is
+//! closed or the app quits, and restored at startup after being fitted to
+//! the monitors present then, which may not be the ones it was saved on.
+//! All coordinates are physical pixels.
+
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// File name of the window state inside the app config dir.
+const WINDOW_STATE_FILE: &str = "window-state.json";
+
+/// How much of the window, in pixels each way, has to be on a monitor for
+/// it to count as being there; less than this and it is moved back.
+const MIN_VISIBLE: u32 = 100;
+
+/// Smallest size a restored window is given.
+pub const MIN_WIDTH: u32 = 400;
+pub const MIN_HEIGHT: u32 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Rect {
+    /// Width and height of the overlap with `other`.
+    fn overlap(&self, other: &Rect) -> (u32, u32) {
+        let span = |a: i32, a_len: u32, b: i32, b_len: u32| {
+            let start = a.max(b) as i64;
+            let end = (a as i64 + a_len as i64).min(b as i64 + b_len as i64);
+            (end - start).max(0) as u32
+        };
+        (
+            span(self.x, self.width, other.x, other.width),
+            span(self.y, self.height, other.y, other.height),
+        )
+    }
+}
+
+/// A monitor's name and the part of it windows may use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorArea {
+    pub name: Option<String>,
+    pub work_area: Rect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowState {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// Maximized windows keep the geometry they had before, to go back to.
+    pub maximized: bool,
+    /// Name of the monitor the window was on, if the platform names it.
+    #[serde(default)]
+    pub monitor: Option<String>,
+}
+
+impl WindowState {
+    /// Read the window state from `dir`, if it was saved and is readable.
+    pub fn load(dir: &Path) -> Option<Self> {
+        let json = fs::read_to_string(dir.join(WINDOW_STATE_FILE)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(dir.join(WINDOW_STATE_FILE), json)
+    }
+
+    fn rect(&self) -> Rect {
+        Rect {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// This state fitted to `monitors`, the first being the primary one.
+    ///
+    /// A window still showing on its monitor, or else on another one, is
+    /// only shrunk and moved as far as needed to fit there. One that would
+    /// be off-screen is centered on the primary monitor.
+    pub fn fit_to(&self, monitors: &[MonitorArea]) -> Self {
+        let Some(primary) = monitors.first() else {
+            return self.clone();
+        };
+        let rect = self.rect();
+        let visible = |monitor: &&MonitorArea| {
+            let (width, height) = rect.overlap(&monitor.work_area);
+            width >= MIN_VISIBLE.min(rect.width) && height >= MIN_VISIBLE.min(rect.height)
+        };
+        let same_monitor = monitors
+            .iter()
+            .find(|monitor| self.monitor.is_some() && monitor.name == self.monitor)
+            .filter(visible);
+        let most_overlap = monitors.iter().filter(visible).max_by_key(|monitor| {
+            let (width, height) = rect.overlap(&monitor.work_area);
+            u64::from(width) * u64::from(height)
+        });
+        let (monitor, center) = match same_monitor.or(most_overlap) {
+            Some(monitor) => (monitor, false),
+            None => (primary, true),
+        };
+
+        let area = monitor.work_area;
+        let width = self.width.clamp(MIN_WIDTH.min(area.width), area.width);
+        let height = self.height.clamp(MIN_HEIGHT.min(area.height), area.height);
+        let (x, y) = if center {
+            (
+                area.x + ((area.width - width) / 2) as i32,
+                area.y + ((area.height - height) / 2) as i32,
+            )
+        } else {
+            (
+                self.x.clamp(area.x, area.x + (area.width - width) as i32),
+                self.y.clamp(area.y, area.y + (area.height - height) as i32),
+            )
+        };
+        Self {
+            x,
+            y,
+            width,
+            height,
+            maximized: self.maximized,
+            monitor: monitor.name.clone(),
+        }
+    }
+}
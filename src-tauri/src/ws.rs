@@ -0,0 +1,304 @@
+//! WebSocket transport for long-lived app sessions.
+//!
+//! A client upgrades `GET /ws` and then exchanges JSON-RPC 2.0 messages:
+//! every BRC-100 method can be called (singly or in batches) and calls run
+//! concurrently, answered by `id`. Two extra methods manage event delivery:
+//!
+//! - `subscribe` with `{ "events": ["authenticated", ...] }` (`"*"` for all)
+//! - `unsubscribe` with the same shape
+//!
+//! Subscribed wallet events are pushed as `event` notifications whose params
+//! are the [`WalletEvent`].
+//!
+//! The origin is fixed when the connection is opened and applied to every
+//! call made over it.
+
+use std::{collections::HashSet, sync::Arc, time::SystemTime};
+
+use futures_util::{SinkExt, StreamExt};
+use hyper::{
+    header::{CONNECTION, SEC_WEBSOCKET_ACCEPT, SEC_WEBSOCKET_KEY, UPGRADE},
+    upgrade::Upgraded,
+    Body, Request, Response, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::{
+    tungstenite::{handshake::derive_accept_key, protocol::Role, Message},
+    WebSocketStream,
+};
+
+use crate::brc100::{ERR_INVALID_PARAMETER, ERR_METHOD_NOT_ALLOWED};
+use crate::bridge::{Bridge, PeerCredentials, WalletEvent};
+use crate::origin::request_origin;
+use crate::rpc;
+use crate::server::{error_response, header_pairs, peer_credentials};
+
+/// Path of the WebSocket endpoint.
+pub const WS_PATH: &str = "/ws";
+
+/// Subscription name matching every event.
+const ALL_EVENTS: &str = "*";
+
+/// A connected WebSocket client.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub origin: String,
+    pub connected_at: SystemTime,
+    pub subscriptions: HashSet<String>,
+}
+
+impl Session {
+    pub fn new(origin: String) -> Self {
+        Self {
+            origin,
+            connected_at: SystemTime::now(),
+            subscriptions: HashSet::new(),
+        }
+    }
+
+    fn is_subscribed(&self, event: &WalletEvent) -> bool {
+        event.visible_to(&self.origin)
+            && (self.subscriptions.contains(ALL_EVENTS)
+                || self.subscriptions.contains(&event.event))
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscriptionParams {
+    events: Vec<String>,
+}
+
+/// Whether `req` asks to be upgraded to a WebSocket.
+fn is_websocket_upgrade(req: &Request<Body>) -> bool {
+    let has_token = |name, token: &str| {
+        req.headers()
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case(token))
+    };
+    req.method() == hyper::Method::GET
+        && has_token(CONNECTION, "upgrade")
+        && has_token(UPGRADE, "websocket")
+}
+
+/// Validate an upgrade request and, if acceptable, switch protocols and
+/// start serving the session in the background.
+pub fn upgrade(bridge: Arc<Bridge>, mut req: Request<Body>) -> Response<Body> {
+    if !is_websocket_upgrade(&req) {
+        return error_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            ERR_METHOD_NOT_ALLOWED,
+            format!("{} only accepts WebSocket upgrades", WS_PATH),
+        );
+    }
+    let Some(key) = req.headers().get(SEC_WEBSOCKET_KEY) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_INVALID_PARAMETER,
+            "Missing Sec-WebSocket-Key header",
+        );
+    };
+    let accept = derive_accept_key(key.as_bytes());
+
+    // Browsers do not apply CORS to WebSockets, so the origin must be known
+    // up front: it is pinned for the lifetime of the session. It is worked
+    // out as for every HTTP request, and the same headers go with each call
+    // so the wallet sees the same origin.
+    let headers = header_pairs(&req);
+    let peer = peer_credentials(&req);
+    let Some(origin) = request_origin(&headers) else {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            ERR_INVALID_PARAMETER,
+            "An Origin or Originator header naming the app is required",
+        );
+    };
+
+    tokio::spawn(async move {
+        match hyper::upgrade::on(&mut req).await {
+            Ok(upgraded) => {
+                let socket = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                serve_session(bridge, socket, origin, headers, peer).await;
+            }
+            Err(e) => eprintln!("WebSocket upgrade failed: {}", e),
+        }
+    });
+
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let response_headers = res.headers_mut();
+    response_headers.insert(CONNECTION, "Upgrade".parse().unwrap());
+    response_headers.insert(UPGRADE, "websocket".parse().unwrap());
+    response_headers.insert(SEC_WEBSOCKET_ACCEPT, accept.parse().unwrap());
+    res
+}
+
+async fn serve_session(
+    bridge: Arc<Bridge>,
+    socket: WebSocketStream<Upgraded>,
+    origin: String,
+    headers: Vec<(String, String)>,
+    peer: Option<PeerCredentials>,
+) {
+    let session_id = bridge.open_session(origin);
+
+    let (mut sink, mut stream) = socket.split();
+    // Replies from concurrently running calls are funnelled through here.
+    let (reply_tx, mut reply_rx) = mpsc::unbounded_channel::<String>();
+    let mut events = bridge.subscribe_events();
+
+    loop {
+        tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let bridge = bridge.clone();
+                    let headers = headers.clone();
+                    let reply_tx = reply_tx.clone();
+                    tokio::spawn(async move {
+                        for reply in handle_message(session_id, &bridge, headers, peer, text.as_bytes()).await {
+                            _ = reply_tx.send(reply);
+                        }
+                    });
+                }
+                Some(Ok(Message::Binary(_))) => {
+                    _ = reply_tx.send(rpc_error(rpc::INVALID_REQUEST, "Binary messages are not supported"));
+                }
+                // Pings are answered by tungstenite itself.
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Err(e)) => {
+                    eprintln!("WebSocket session {} error: {}", session_id, e);
+                    break;
+                }
+            },
+            Some(reply) = reply_rx.recv() => {
+                if sink.send(Message::Text(reply)).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let subscribed = bridge.with_session(session_id, |session| session.is_subscribed(&event));
+                    if subscribed == Some(true)
+                        && sink.send(Message::Text(event_notification(&event))).await.is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    eprintln!("WebSocket session {} missed {} events", session_id, missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        }
+    }
+
+    bridge.close_session(session_id);
+}
+
+/// Handle one text message: a subscription change or JSON-RPC call(s).
+/// Returns the messages to send back, in order.
+async fn handle_message(
+    session_id: u64,
+    bridge: &Arc<Bridge>,
+    headers: Vec<(String, String)>,
+    peer: Option<PeerCredentials>,
+    message: &[u8],
+) -> Vec<String> {
+    if let Ok(Value::Object(call)) = serde_json::from_slice::<Value>(message) {
+        let subscribe = match call.get("method").and_then(Value::as_str) {
+            Some("subscribe") => Some(true),
+            Some("unsubscribe") => Some(false),
+            _ => None,
+        };
+        if let Some(subscribe) = subscribe {
+            return update_subscriptions(bridge, session_id, subscribe, call);
+        }
+    }
+    rpc::handle(bridge, headers, peer, message)
+        .await
+        .into_iter()
+        .collect()
+}
+
+fn update_subscriptions(
+    bridge: &Bridge,
+    session_id: u64,
+    subscribe: bool,
+    call: serde_json::Map<String, Value>,
+) -> Vec<String> {
+    let id = call.get("id").cloned();
+    // Newly subscribed events start with the current wallet state, as on
+    // the event stream.
+    let state = bridge.current_state();
+    let mut replay = Vec::new();
+    let params = call
+        .get("params")
+        .cloned()
+        .map(serde_json::from_value::<SubscriptionParams>);
+    let response = match params {
+        Some(Ok(params)) => {
+            let subscriptions = bridge.with_session(session_id, |session| {
+                let was_subscribed: Vec<bool> =
+                    state.iter().map(|e| session.is_subscribed(e)).collect();
+                for event in params.events {
+                    if subscribe {
+                        session.subscriptions.insert(event);
+                    } else {
+                        session.subscriptions.remove(&event);
+                    }
+                }
+                replay = state
+                    .iter()
+                    .zip(was_subscribed)
+                    .filter(|(e, was)| !was && session.is_subscribed(e))
+                    .map(|(e, _)| event_notification(e))
+                    .collect();
+                let mut current: Vec<_> = session.subscriptions.iter().cloned().collect();
+                current.sort();
+                current
+            });
+            json!({
+                "jsonrpc": "2.0",
+                "result": { "subscriptions": subscriptions.unwrap_or_default() },
+                "id": id.clone().unwrap_or(Value::Null),
+            })
+        }
+        _ => json!({
+            "jsonrpc": "2.0",
+            "error": {
+                "code": rpc::INVALID_PARAMS,
+                "message": "params must be { \"events\": [...] }",
+            },
+            "id": id.clone().unwrap_or(Value::Null),
+        }),
+    };
+    // Subscription changes sent as notifications get no reply.
+    id.map(|_| response.to_string())
+        .into_iter()
+        .chain(replay)
+        .collect()
+}
+
+/// An `event` notification carrying `event`.
+fn event_notification(event: &WalletEvent) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "event",
+        "params": event,
+    })
+    .to_string()
+}
+
+fn rpc_error(code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": { "code": code, "message": message },
+        "id": Value::Null,
+    })
+    .to_string()
+}
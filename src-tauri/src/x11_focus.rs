@@ -0,0 +1,175 @@
+//! Handing focus back to the window the user was in, on X11.
+//!
+//! When the wallet comes forward for an approval, the window that was
+//! active (the root window's `_NET_ACTIVE_WINDOW`) is recorded, and once the
+//! approval is done it is asked to be activated again, the way a pager or
+//! taskbar would. This relies on an EWMH window manager advertising
+//! `_NET_ACTIVE_WINDOW`; Wayland compositors don't let clients do this, so
+//! the caller falls back to minimizing there.
+
+use std::fmt;
+
+use x11rb::{
+    connection::Connection,
+    errors::{ConnectError, ConnectionError, ReplyError},
+    protocol::xproto::{AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, Window},
+    rust_connection::RustConnection,
+    CURRENT_TIME, NONE,
+};
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_SUPPORTED,
+        _NET_ACTIVE_WINDOW,
+        _NET_WM_PID,
+    }
+}
+
+/// Source indication for `_NET_ACTIVE_WINDOW` requests: a pager, acting on
+/// the user's behalf, which window managers don't treat as focus stealing.
+const SOURCE_PAGER: u32 = 2;
+
+#[derive(Debug)]
+pub enum FocusError {
+    Connect(ConnectError),
+    Connection(ConnectionError),
+    Reply(ReplyError),
+    /// The window manager doesn't support `_NET_ACTIVE_WINDOW`.
+    Unsupported,
+}
+
+impl fmt::Display for FocusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FocusError::Connect(e) => write!(f, "cannot connect to the X server: {}", e),
+            FocusError::Connection(e) => write!(f, "X connection error: {}", e),
+            FocusError::Reply(e) => write!(f, "X request failed: {}", e),
+            FocusError::Unsupported => {
+                write!(f, "the window manager does not support _NET_ACTIVE_WINDOW")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FocusError {}
+
+impl From<ConnectError> for FocusError {
+    fn from(e: ConnectError) -> Self {
+        FocusError::Connect(e)
+    }
+}
+
+impl From<ConnectionError> for FocusError {
+    fn from(e: ConnectionError) -> Self {
+        FocusError::Connection(e)
+    }
+}
+
+impl From<ReplyError> for FocusError {
+    fn from(e: ReplyError) -> Self {
+        FocusError::Reply(e)
+    }
+}
+
+/// Whether the wallet's own windows are X11 windows, so the window manager
+/// can be asked about them. Under Wayland only XWayland clients would be
+/// visible, which says nothing about what the user is looking at.
+pub fn x11_session() -> bool {
+    let gdk_backend = std::env::var("GDK_BACKEND").unwrap_or_default();
+    std::env::var_os("DISPLAY").is_some()
+        && (std::env::var_os("WAYLAND_DISPLAY").is_none()
+            || gdk_backend.split(',').next() == Some("x11"))
+}
+
+/// A connection to the X server for reading and changing the active window.
+pub struct ActiveWindow {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+impl ActiveWindow {
+    /// Connect to `display`, or `$DISPLAY` when `None`, and check that the
+    /// window manager supports `_NET_ACTIVE_WINDOW`.
+    pub fn connect(display: Option<&str>) -> Result<Self, FocusError> {
+        let (conn, screen) = x11rb::connect(display)?;
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms::new(&conn)?.reply()?;
+        let active_window = Self { conn, root, atoms };
+        let supported = active_window.property32(root, atoms._NET_SUPPORTED, AtomEnum::ATOM)?;
+        if !supported.contains(&atoms._NET_ACTIVE_WINDOW) {
+            return Err(FocusError::Unsupported);
+        }
+        Ok(active_window)
+    }
+
+    /// The window the window manager reports as active, if any.
+    pub fn get(&self) -> Result<Option<Window>, FocusError> {
+        let active = self.property32(self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)?;
+        Ok(active.first().copied().filter(|&window| window != NONE))
+    }
+
+    /// The active window, unless it belongs to the process `own_pid`.
+    pub fn get_other(&self, own_pid: u32) -> Result<Option<Window>, FocusError> {
+        let Some(window) = self.get()? else {
+            return Ok(None);
+        };
+        if self.pid(window)? == Some(own_pid) {
+            return Ok(None);
+        }
+        Ok(Some(window))
+    }
+
+    /// The process owning `window`, as it advertises in `_NET_WM_PID`.
+    pub fn pid(&self, window: Window) -> Result<Option<u32>, FocusError> {
+        let pid = self.property32(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?;
+        Ok(pid.first().copied())
+    }
+
+    /// Whether `window` still exists.
+    pub fn exists(&self, window: Window) -> Result<bool, FocusError> {
+        match self.conn.get_window_attributes(window)?.reply() {
+            Ok(_) => Ok(true),
+            Err(ReplyError::X11Error(_)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Ask the window manager to activate `window`. Returns `false`, without
+    /// asking, when the window has since been closed.
+    pub fn activate(&self, window: Window) -> Result<bool, FocusError> {
+        if !self.exists(window)? {
+            return Ok(false);
+        }
+        let event = ClientMessageEvent::new(
+            32,
+            window,
+            self.atoms._NET_ACTIVE_WINDOW,
+            [SOURCE_PAGER, CURRENT_TIME, NONE, 0, 0],
+        );
+        self.conn.send_event(
+            false,
+            self.root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        self.conn.flush()?;
+        Ok(true)
+    }
+
+    fn property32(
+        &self,
+        window: Window,
+        property: u32,
+        type_: AtomEnum,
+    ) -> Result<Vec<u32>, FocusError> {
+        let reply = self
+            .conn
+            .get_property(false, window, property, type_, 0, 1024)?
+            .reply()?;
+        Ok(reply
+            .value32()
+            .map(|values| values.collect())
+            .unwrap_or_default())
+    }
+}
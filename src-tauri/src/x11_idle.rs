@@ -0,0 +1,42 @@
+//! How long the user has been away from the keyboard and mouse, on X11.
+//!
+//! Read from the X server's MIT-SCREEN-SAVER extension, which counts the
+//! time since the last input anywhere on the display, not just in the
+//! wallet.
+
+use std::time::Duration;
+
+use x11rb::{
+    connection::Connection,
+    protocol::{screensaver::ConnectionExt as _, xproto::Window},
+    rust_connection::RustConnection,
+};
+
+/// A connection to the X server for reading the user's idle time.
+pub struct IdleTime {
+    conn: RustConnection,
+    root: Window,
+}
+
+impl IdleTime {
+    /// Connect to `display`, or `$DISPLAY` when `None`. `None` when there
+    /// is no X server or it lacks the screen saver extension.
+    pub fn connect(display: Option<&str>) -> Option<Self> {
+        let (conn, screen) = x11rb::connect(display).ok()?;
+        let root = conn.setup().roots[screen].root;
+        let idle_time = Self { conn, root };
+        idle_time.get()?;
+        Some(idle_time)
+    }
+
+    /// Time since the user last touched the keyboard or mouse.
+    pub fn get(&self) -> Option<Duration> {
+        let info = self
+            .conn
+            .screensaver_query_info(self.root)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(Duration::from_millis(info.ms_since_user_input.into()))
+    }
+}
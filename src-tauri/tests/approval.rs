@@ -0,0 +1,97 @@
+//! Answering requests from their approval windows.
+
+use std::{sync::Arc, time::Duration};
+
+use metanet_desktop::{
+    approval::{self, Prompt},
+    brc100::{Method, WalletError, ERR_USER_DENIED},
+    bridge::{Bridge, WalletRequest},
+};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, time::timeout};
+
+/// A listening bridge whose emitted `http-request` payloads arrive on the
+/// returned channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+fn create_action(origin: &str) -> WalletRequest {
+    WalletRequest {
+        method: "POST".to_string(),
+        path: "/createAction".to_string(),
+        headers: vec![("origin".to_string(), format!("https://{}", origin))],
+        body: "{}".to_string(),
+        peer: None,
+    }
+}
+
+async fn next_request_id(events: &mut mpsc::UnboundedReceiver<Value>) -> u64 {
+    let event = timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap();
+    event["request_id"].as_u64().unwrap()
+}
+
+#[test]
+fn window_labels_name_their_request() {
+    assert_eq!(approval::request_id(&approval::label(42)), Some(42));
+    assert_eq!(approval::request_id("main"), None);
+    assert_eq!(approval::request_id("approval-"), None);
+    assert_eq!(approval::request_id("approval-x"), None);
+}
+
+#[tokio::test]
+async fn the_prompt_describes_the_pending_request() {
+    let (bridge, mut events) = bridge();
+    let forward = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { bridge.forward(create_action("app.example")).await }
+    });
+    let request_id = next_request_id(&mut events).await;
+
+    let prompt = Prompt::from(&bridge.pending_request(request_id).unwrap());
+    assert_eq!(prompt.request_id, request_id);
+    assert_eq!(prompt.origin.as_deref(), Some("app.example"));
+    assert_eq!(prompt.method, Method::CreateAction.name());
+    assert_eq!(prompt.summary, "Request from app.example");
+
+    bridge.deny(request_id);
+    forward.await.unwrap().unwrap();
+    assert!(bridge.pending_request(request_id).is_none());
+}
+
+#[tokio::test]
+async fn a_denial_answers_the_app_with_a_wallet_error() {
+    let (bridge, mut events) = bridge();
+    let forward = tokio::spawn({
+        let bridge = bridge.clone();
+        async move {
+            let response = bridge.forward(create_action("app.example")).await.unwrap();
+            (response.status, response.body.into_string().await.unwrap())
+        }
+    });
+    let request_id = next_request_id(&mut events).await;
+
+    assert!(bridge.deny(request_id));
+    let (status, body) = forward.await.unwrap();
+    assert_eq!(status, 400);
+    let error: WalletError = serde_json::from_str(&body).unwrap();
+    assert!(error.is_error);
+    assert_eq!(error.code, ERR_USER_DENIED);
+
+    // The wallet's own answer to the cancelled prompt comes too late.
+    assert!(!bridge.deny(request_id));
+    bridge.resolve(&json!({ "request_id": request_id, "status": 200, "body": "{}" }).to_string());
+    assert_eq!(bridge.pending_count(), 0);
+}
@@ -0,0 +1,78 @@
+//! The XDG autostart entry written for launching at login.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use metanet_desktop::autostart::{self, MINIMIZED_ARG};
+
+/// An empty directory to stand in for `$XDG_CONFIG_HOME`.
+fn temp_config(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metanet-{}-{}", name, std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn exec_line(entry: &str) -> &str {
+    let mut lines = entry.lines().filter(|line| line.starts_with("Exec="));
+    let line = lines.next().expect("no Exec line");
+    assert!(lines.next().is_none(), "more than one Exec line");
+    line
+}
+
+#[test]
+fn the_entry_starts_the_executable_minimized() {
+    let entry = autostart::entry(Path::new("/opt/metanet/metanet-desktop"));
+    assert_eq!(
+        exec_line(&entry),
+        format!("Exec=/opt/metanet/metanet-desktop {}", MINIMIZED_ARG)
+    );
+    assert!(entry.starts_with("[Desktop Entry]\n"));
+    assert!(entry.contains("\nName=Metanet-Desktop\n"));
+    assert!(entry.contains("\nX-GNOME-Autostart-enabled=true\n"));
+}
+
+#[test]
+fn the_entry_leaves_scheme_handlers_to_the_installed_one() {
+    let entry = autostart::entry(Path::new("/usr/bin/metanet-desktop"));
+    assert!(!entry.contains("MimeType="));
+    assert!(!entry.contains("%u"));
+}
+
+#[test]
+fn executable_paths_are_quoted_when_they_need_it() {
+    let entry = autostart::entry(Path::new("/home/me/My Apps/metanet$1.AppImage"));
+    assert_eq!(
+        exec_line(&entry),
+        format!(
+            r#"Exec="/home/me/My Apps/metanet\\$1.AppImage" {}"#,
+            MINIMIZED_ARG
+        )
+    );
+
+    let entry = autostart::entry(Path::new("/opt/100%/metanet-desktop"));
+    assert_eq!(
+        exec_line(&entry),
+        format!("Exec=/opt/100%%/metanet-desktop {}", MINIMIZED_ARG)
+    );
+}
+
+#[test]
+fn enabling_writes_the_entry_and_disabling_removes_it() {
+    let config = temp_config("autostart");
+    let exe = Path::new("/opt/metanet/metanet-desktop");
+    assert!(!autostart::is_enabled(&config));
+
+    autostart::enable(&config, exe).unwrap();
+    assert!(autostart::is_enabled(&config));
+    let path = autostart::entry_path(&config);
+    assert_eq!(path, config.join("autostart/metanet-desktop.desktop"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), autostart::entry(exe));
+
+    autostart::disable(&config).unwrap();
+    assert!(!autostart::is_enabled(&config));
+    // Nothing left to remove is not an error.
+    autostart::disable(&config).unwrap();
+}
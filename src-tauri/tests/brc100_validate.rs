@@ -0,0 +1,311 @@
+//! Field-level validation of BRC-100 request bodies.
+
+use metanet_desktop::brc100::{parse_args, validate_request, CreateActionArgs, FieldError, Method};
+use serde_json::{json, Value};
+
+const TXID: &str = "0000000000000000000000000000000000000000000000000000000000000001";
+const PUB_KEY: &str = "02a1633cafcc01ebfb6d78e39f687a1f0995c62fc95f51ead10a02ee0be551b5dc";
+
+/// The fields `body` is rejected for, or nothing if it is valid.
+fn invalid_fields(method: Method, body: Value) -> Vec<String> {
+    match validate_request(method, &body.to_string()) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().map(|e| e.field).collect(),
+    }
+}
+
+fn create_action(output: Value) -> Value {
+    json!({
+        "description": "pay for coffee",
+        "outputs": [output],
+    })
+}
+
+fn output() -> Value {
+    json!({
+        "lockingScript": "76a914",
+        "satoshis": 1000,
+        "outputDescription": "coffee payment",
+    })
+}
+
+fn key_args() -> Value {
+    json!({
+        "protocolID": [1, "todo list"],
+        "keyID": "1",
+        "counterparty": "self",
+        "plaintext": [1, 2, 3],
+    })
+}
+
+#[test]
+fn accepts_valid_create_action() {
+    assert_eq!(
+        invalid_fields(Method::CreateAction, create_action(output())),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn reports_structural_errors_by_path() {
+    let mut body = output();
+    body["satoshis"] = json!("lots");
+    assert_eq!(
+        invalid_fields(Method::CreateAction, create_action(body)),
+        ["outputs[0].satoshis"]
+    );
+    assert_eq!(
+        invalid_fields(Method::CreateAction, json!({ "outputs": [] })),
+        [""]
+    );
+}
+
+#[test]
+fn rejects_satoshis_over_the_supply() {
+    let mut body = output();
+    body["satoshis"] = json!(2_100_000_000_000_000u64);
+    assert!(invalid_fields(Method::CreateAction, create_action(body.clone())).is_empty());
+    body["satoshis"] = json!(2_100_000_000_000_001u64);
+    assert_eq!(
+        invalid_fields(Method::CreateAction, create_action(body)),
+        ["outputs[0].satoshis"]
+    );
+}
+
+#[test]
+fn rejects_bad_hex_and_descriptions() {
+    let mut body = output();
+    body["lockingScript"] = json!("76a91");
+    body["outputDescription"] = json!("tiny");
+    assert_eq!(
+        invalid_fields(Method::CreateAction, create_action(body)),
+        ["outputs[0].lockingScript", "outputs[0].outputDescription"]
+    );
+    assert_eq!(
+        invalid_fields(
+            Method::CreateAction,
+            json!({ "description": "x".repeat(51) })
+        ),
+        ["description"]
+    );
+}
+
+#[test]
+fn rejects_bad_outpoints() {
+    for outpoint in [
+        TXID.to_string(),
+        format!("{}.x", TXID),
+        format!("{}.0", &TXID[2..]),
+        format!("{}.0", TXID.replace('0', "g")),
+    ] {
+        let body = json!({ "basket": "default", "output": outpoint });
+        assert_eq!(
+            invalid_fields(Method::RelinquishOutput, body),
+            ["output"],
+            "{}",
+            outpoint
+        );
+    }
+    let body = json!({ "basket": "default", "output": format!("{}.3", TXID) });
+    assert!(invalid_fields(Method::RelinquishOutput, body).is_empty());
+}
+
+#[test]
+fn requires_an_unlocking_script_or_its_length() {
+    let input = json!({ "outpoint": format!("{}.0", TXID), "inputDescription": "spend a coin" });
+    let body = json!({ "description": "spend a coin", "inputs": [input] });
+    assert_eq!(
+        invalid_fields(Method::CreateAction, body.clone()),
+        ["inputs[0].unlockingScript"]
+    );
+    let mut with_length = body;
+    with_length["inputs"][0]["unlockingScriptLength"] = json!(107);
+    assert!(invalid_fields(Method::CreateAction, with_length).is_empty());
+}
+
+#[test]
+fn rejects_labels_and_tags_that_are_too_long() {
+    let mut body = create_action(output());
+    body["labels"] = json!(["ok", "x".repeat(301), ""]);
+    body["outputs"][0]["tags"] = json!(["x".repeat(300), "x".repeat(301)]);
+    assert_eq!(
+        invalid_fields(Method::CreateAction, body),
+        ["outputs[0].tags[1]", "labels[1]", "labels[2]"]
+    );
+}
+
+#[test]
+fn checks_create_action_options() {
+    let mut body = create_action(output());
+    body["options"] = json!({
+        "knownTxids": [TXID, "abcd"],
+        "noSendChange": ["nope"],
+        "sendWith": [TXID],
+    });
+    assert_eq!(
+        invalid_fields(Method::CreateAction, body),
+        ["options.knownTxids[1]", "options.noSendChange[0]"]
+    );
+}
+
+#[test]
+fn checks_protocol_security_level_and_name() {
+    for level in [0, 1, 2] {
+        let mut body = key_args();
+        body["protocolID"][0] = json!(level);
+        assert!(invalid_fields(Method::Encrypt, body).is_empty());
+    }
+    let mut body = key_args();
+    body["protocolID"] = json!([3, "todo list"]);
+    assert_eq!(invalid_fields(Method::Encrypt, body), ["protocolID[0]"]);
+
+    for name in [
+        "todo".to_string(),
+        "x".repeat(401),
+        "todo protocol".to_string(),
+    ] {
+        let mut body = key_args();
+        body["protocolID"][1] = json!(name);
+        assert_eq!(invalid_fields(Method::Encrypt, body), ["protocolID[1]"]);
+    }
+}
+
+#[test]
+fn checks_key_id_and_counterparty() {
+    let mut body = key_args();
+    body["keyID"] = json!("");
+    body["counterparty"] = json!("someone");
+    assert_eq!(
+        invalid_fields(Method::Encrypt, body),
+        ["keyID", "counterparty"]
+    );
+    let mut body = key_args();
+    body["counterparty"] = json!(PUB_KEY);
+    assert!(invalid_fields(Method::Encrypt, body).is_empty());
+}
+
+#[test]
+fn get_public_key_needs_protocol_unless_identity_key() {
+    assert!(invalid_fields(Method::GetPublicKey, json!({ "identityKey": true })).is_empty());
+    assert_eq!(
+        invalid_fields(Method::GetPublicKey, json!({})),
+        ["protocolID", "keyID"]
+    );
+}
+
+#[test]
+fn checks_privileged_reason_length() {
+    let body = json!({ "identityKey": true, "privilegedReason": "why" });
+    assert_eq!(
+        invalid_fields(Method::GetPublicKey, body),
+        ["privilegedReason"]
+    );
+}
+
+#[test]
+fn checks_hmac_and_signature_hash_lengths() {
+    let mut body = key_args();
+    body["data"] = json!([1]);
+    body["hmac"] = json!(vec![0u8; 31]);
+    assert_eq!(invalid_fields(Method::VerifyHmac, body), ["hmac"]);
+
+    let mut body = key_args();
+    body["hashToDirectlySign"] = json!(vec![0u8; 16]);
+    assert_eq!(
+        invalid_fields(Method::CreateSignature, body),
+        ["hashToDirectlySign"]
+    );
+    let mut body = key_args();
+    body["data"] = json!([1]);
+    body["hashToDirectlySign"] = json!(vec![0u8; 32]);
+    assert_eq!(invalid_fields(Method::CreateSignature, body), ["data"]);
+}
+
+#[test]
+fn checks_base64_references() {
+    assert!(invalid_fields(Method::AbortAction, json!({ "reference": "YWJjZA==" })).is_empty());
+    for reference in ["YWJjZA=", "YW=jZA==", "YWJj*A=="] {
+        assert_eq!(
+            invalid_fields(Method::AbortAction, json!({ "reference": reference })),
+            ["reference"],
+            "{}",
+            reference
+        );
+    }
+}
+
+#[test]
+fn checks_internalize_remittances() {
+    let body = json!({
+        "tx": [1, 2, 3],
+        "description": "incoming payment",
+        "outputs": [
+            { "outputIndex": 0, "protocol": "wallet payment" },
+            {
+                "outputIndex": 1,
+                "protocol": "basket insertion",
+                "insertionRemittance": { "basket": "" },
+            },
+        ],
+    });
+    assert_eq!(
+        invalid_fields(Method::InternalizeAction, body),
+        [
+            "outputs[0].paymentRemittance",
+            "outputs[1].insertionRemittance.basket"
+        ]
+    );
+}
+
+#[test]
+fn checks_list_limits() {
+    for limit in [0, 10_001] {
+        let body = json!({ "labels": [], "limit": limit });
+        assert_eq!(invalid_fields(Method::ListActions, body), ["limit"]);
+    }
+    let body = json!({ "labels": [], "limit": 10_000 });
+    assert!(invalid_fields(Method::ListActions, body).is_empty());
+}
+
+#[test]
+fn checks_direct_certificate_acquisition() {
+    let body = json!({
+        "type": "dGVzdA==",
+        "certifier": PUB_KEY,
+        "acquisitionProtocol": "direct",
+        "fields": {},
+        "signature": "abc",
+    });
+    assert_eq!(
+        invalid_fields(Method::AcquireCertificate, body),
+        [
+            "serialNumber",
+            "revocationOutpoint",
+            "keyringRevealer",
+            "keyringForSubject",
+            "signature",
+        ]
+    );
+}
+
+#[test]
+fn methods_without_arguments_take_an_empty_object() {
+    assert!(invalid_fields(Method::GetHeight, json!({})).is_empty());
+    assert!(validate_request(Method::GetHeight, "").is_ok());
+    assert_eq!(invalid_fields(Method::GetHeight, json!("nope")), [""]);
+}
+
+#[test]
+fn parse_args_returns_the_typed_arguments() {
+    let args: CreateActionArgs = parse_args(&create_action(output()).to_string()).unwrap();
+    assert_eq!(args.outputs.unwrap()[0].satoshis, 1000);
+
+    let errors = parse_args::<CreateActionArgs>(r#"{"description":"tiny"}"#).unwrap_err();
+    assert_eq!(
+        errors,
+        [FieldError::new(
+            "description",
+            "must be between 5 and 50 bytes"
+        )]
+    );
+}
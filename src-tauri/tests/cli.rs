@@ -0,0 +1,242 @@
+//! Argument parsing and exit codes of `metanet-cli`, against a stand-in
+//! wallet listener.
+
+use std::{
+    convert::Infallible,
+    net::{SocketAddr, TcpListener},
+    path::PathBuf,
+    process::{Command, Output},
+    sync::{Arc, Mutex},
+};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use serde_json::{json, Value};
+
+/// A call the stand-in wallet received.
+#[derive(Debug, Clone)]
+struct Received {
+    path: String,
+    originator: Option<String>,
+    idempotency_key: Option<String>,
+    body: Value,
+}
+
+/// A wallet listener answering every call with `status` and `answer`, and
+/// recording what it was sent.
+struct Wallet {
+    addr: SocketAddr,
+    received: Arc<Mutex<Vec<Received>>>,
+}
+
+impl Wallet {
+    fn start(status: StatusCode, answer: Value) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        let make_service = make_service_fn(move |_| {
+            let log = log.clone();
+            let answer = answer.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let log = log.clone();
+                    let answer = answer.clone();
+                    async move {
+                        let header = |name: &str| {
+                            req.headers()
+                                .get(name)
+                                .map(|v| v.to_str().unwrap().to_string())
+                        };
+                        let path = req.uri().path().to_string();
+                        let originator = header("originator");
+                        let idempotency_key = header("idempotency-key");
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        log.lock().unwrap().push(Received {
+                            path,
+                            originator,
+                            idempotency_key,
+                            body: serde_json::from_slice(&body).unwrap(),
+                        });
+                        let mut response = Response::new(Body::from(answer.to_string()));
+                        *response.status_mut() = status;
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                let server = Server::from_tcp(listener).unwrap().serve(make_service);
+                server.await.unwrap()
+            })
+        });
+        Self { addr, received }
+    }
+
+    fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Run `metanet-cli` against this wallet.
+    fn run(&self, args: &[&str]) -> Output {
+        cli(&self.addr.to_string(), args)
+    }
+}
+
+/// A config directory of our own, so no paired origin is picked up.
+fn config_home() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metanet-cli-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn cli(addr: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_metanet-cli"))
+        .env("XDG_CONFIG_HOME", config_home())
+        .env_remove("METANET_ORIGIN")
+        .args(["--addr", addr])
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout_json(output: &Output) -> Value {
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn flags_become_the_method_arguments() {
+    let wallet = Wallet::start(StatusCode::OK, json!({ "publicKey": "02ab" }));
+    let output = wallet.run(&[
+        "--origin",
+        "scripts.example",
+        "get-public-key",
+        "--protocol",
+        "2:message signing",
+        "--key-id",
+        "1",
+        "--counterparty",
+        "self",
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout_json(&output), json!({ "publicKey": "02ab" }));
+
+    let received = wallet.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].path, "/getPublicKey");
+    assert_eq!(received[0].originator.as_deref(), Some("scripts.example"));
+    assert_eq!(
+        received[0].body,
+        json!({ "protocolID": [2, "message signing"], "keyID": "1", "counterparty": "self" })
+    );
+}
+
+#[test]
+fn byte_arguments_accept_text_and_hex() {
+    let wallet = Wallet::start(StatusCode::OK, json!({ "ciphertext": [1, 2] }));
+    for (plaintext, bytes) in [("hi", json!([104, 105])), ("hex:00ff", json!([0, 255]))] {
+        let output = wallet.run(&[
+            "encrypt",
+            "--protocol",
+            "1:test cipher",
+            "--key-id",
+            "k",
+            "--plaintext",
+            plaintext,
+        ]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let received = wallet.received();
+        assert_eq!(received.last().unwrap().body["plaintext"], bytes);
+    }
+}
+
+#[test]
+fn raw_calls_and_global_flags_are_passed_on() {
+    let wallet = Wallet::start(StatusCode::OK, json!({ "height": 850000 }));
+    let output = wallet.run(&[
+        "--compact",
+        "--idempotency-key",
+        "retry-1",
+        "call",
+        "getHeaderForHeight",
+        r#"{"height":5}"#,
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        r#"{"height":850000}"#
+    );
+
+    let received = &wallet.received()[0];
+    assert_eq!(received.path, "/getHeaderForHeight");
+    assert_eq!(received.originator.as_deref(), Some("metanet-cli"));
+    assert_eq!(received.idempotency_key.as_deref(), Some("retry-1"));
+    assert_eq!(received.body, json!({ "height": 5 }));
+}
+
+#[test]
+fn invalid_arguments_exit_with_2_before_calling() {
+    let wallet = Wallet::start(StatusCode::OK, json!({}));
+    let cases: [&[&str]; 5] = [
+        // Missing the key ID that --protocol requires.
+        &["get-public-key", "--protocol", "2:message signing"],
+        &["get-public-key", "--protocol", "nope", "--key-id", "1"],
+        &[
+            "encrypt",
+            "--protocol",
+            "1:test cipher",
+            "--key-id",
+            "k",
+            "--plaintext",
+            "hex:0",
+        ],
+        &["call", "noSuchMethod"],
+        // Accepted by the parser, refused by the argument schema.
+        &[
+            "get-public-key",
+            "--protocol",
+            "2:message signing",
+            "--key-id",
+            "",
+        ],
+    ];
+    for args in cases {
+        let output = wallet.run(args);
+        assert_eq!(
+            output.status.code(),
+            Some(2),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+    }
+    assert!(wallet.received().is_empty());
+}
+
+#[test]
+fn wallet_errors_exit_with_1() {
+    let error = json!({ "isError": true, "code": "ERR_USER_DENIED", "description": "No" });
+    let wallet = Wallet::start(StatusCode::BAD_REQUEST, error);
+    let output = wallet.run(&["get-height"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("ERR_USER_DENIED: No"));
+}
+
+#[test]
+fn an_unreachable_wallet_exits_with_3() {
+    // Nothing listens on a port we just let go of.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let output = cli(&addr.to_string(), &["get-height"]);
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+}
@@ -0,0 +1,121 @@
+//! Sharing identical in-flight reads and caching immutable answers.
+
+use std::{thread, time::Duration};
+
+use hyper::StatusCode;
+use metanet_desktop::{
+    brc100::Method,
+    coalesce::{Join, Leader, ReadCoalescer, SharedResponse},
+};
+
+const TTL: Duration = Duration::from_secs(60);
+
+fn answer(status: StatusCode, body: &str) -> SharedResponse {
+    SharedResponse {
+        status,
+        body: body.to_string(),
+    }
+}
+
+fn key(method: Method, body: &str) -> String {
+    ReadCoalescer::key(method, Some("app.example"), body).unwrap()
+}
+
+fn lead<'a>(reads: &'a ReadCoalescer, method: Method, body: &str) -> Leader<'a> {
+    match reads.join(method, key(method, body)) {
+        Join::Lead(leader) => leader,
+        _ => panic!("expected to lead"),
+    }
+}
+
+#[test]
+fn equivalent_requests_share_a_key() {
+    let a = key(Method::GetHeaderForHeight, r#"{"height": 5}"#);
+    let b = key(Method::GetHeaderForHeight, r#" {"height":5} "#);
+    assert_eq!(a, b);
+    assert_ne!(a, key(Method::GetHeaderForHeight, r#"{"height":6}"#));
+
+    // Apps never share answers.
+    let other = ReadCoalescer::key(Method::GetHeaderForHeight, Some("other.example"), "{}");
+    assert_ne!(
+        other,
+        ReadCoalescer::key(Method::GetHeaderForHeight, None, "{}")
+    );
+    assert_ne!(Some(key(Method::GetHeaderForHeight, "{}")), other);
+
+    // Anything but the listed reads goes to the wallet every time.
+    assert_eq!(ReadCoalescer::key(Method::CreateAction, None, "{}"), None);
+    assert_eq!(ReadCoalescer::key(Method::ListOutputs, None, "{}"), None);
+}
+
+#[tokio::test]
+async fn identical_reads_wait_for_the_first() {
+    let reads = ReadCoalescer::new(TTL);
+    let leader = lead(&reads, Method::GetHeight, "{}");
+    let mut waiters: Vec<_> = (0..3)
+        .map(
+            |_| match reads.join(Method::GetHeight, key(Method::GetHeight, "{}")) {
+                Join::Wait(rx) => rx,
+                _ => panic!("expected to wait"),
+            },
+        )
+        .collect();
+
+    leader.finish(&answer(StatusCode::OK, r#"{"height":850000}"#));
+    for rx in &mut waiters {
+        let shared = rx.recv().await.unwrap();
+        assert_eq!(shared.status, StatusCode::OK);
+        assert_eq!(shared.body, r#"{"height":850000}"#);
+    }
+
+    // The height changes, so the next read goes to the wallet again.
+    lead(&reads, Method::GetHeight, "{}");
+}
+
+#[tokio::test]
+async fn a_failed_leader_releases_its_waiters() {
+    let reads = ReadCoalescer::new(TTL);
+    let leader = lead(&reads, Method::GetNetwork, "{}");
+    let Join::Wait(mut rx) = reads.join(Method::GetNetwork, key(Method::GetNetwork, "{}")) else {
+        panic!("expected to wait");
+    };
+    drop(leader);
+    assert!(rx.recv().await.is_err());
+    lead(&reads, Method::GetNetwork, "{}");
+}
+
+#[test]
+fn headers_are_cached_until_they_expire() {
+    let reads = ReadCoalescer::new(Duration::from_millis(20));
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#)
+        .finish(&answer(StatusCode::OK, r#"{"header":"00"}"#));
+
+    match reads.join(
+        Method::GetHeaderForHeight,
+        key(Method::GetHeaderForHeight, r#"{ "height": 5 }"#),
+    ) {
+        Join::Cached(shared) => assert_eq!(shared.body, r#"{"header":"00"}"#),
+        _ => panic!("expected the cached header"),
+    }
+    // Another height is not cached.
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":6}"#);
+
+    thread::sleep(Duration::from_millis(30));
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#);
+}
+
+#[test]
+fn errors_are_not_cached() {
+    let reads = ReadCoalescer::new(TTL);
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#)
+        .finish(&answer(StatusCode::INTERNAL_SERVER_ERROR, "{}"));
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#);
+}
+
+#[test]
+fn a_zero_ttl_caches_nothing() {
+    let reads = ReadCoalescer::new(Duration::ZERO);
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#)
+        .finish(&answer(StatusCode::OK, "{}"));
+    lead(&reads, Method::GetHeaderForHeight, r#"{"height":5}"#);
+}
@@ -0,0 +1,171 @@
+//! Response compression, and streaming of chunked wallet responses.
+
+use std::{sync::Arc, time::Duration};
+
+use async_compression::tokio::bufread::{BrotliDecoder, GzipDecoder};
+use hyper::Body;
+use metanet_desktop::{
+    bridge::{Bridge, WalletBody, WalletRequest},
+    compress::{self, Encoding, MIN_COMPRESS_SIZE},
+};
+use serde_json::{json, Value};
+use tokio::{io::AsyncReadExt, sync::mpsc, time::timeout};
+
+async fn bytes(body: Body) -> Vec<u8> {
+    hyper::body::to_bytes(body).await.unwrap().to_vec()
+}
+
+async fn decode(encoding: Encoding, compressed: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::new();
+    match encoding {
+        Encoding::Brotli => BrotliDecoder::new(compressed)
+            .read_to_end(&mut decoded)
+            .await
+            .unwrap(),
+        Encoding::Gzip => GzipDecoder::new(compressed)
+            .read_to_end(&mut decoded)
+            .await
+            .unwrap(),
+    };
+    decoded
+}
+
+/// A JSON list result of about `len` bytes.
+fn large_result(len: usize) -> String {
+    let outputs: Vec<Value> = (0..len / 40)
+        .map(|i| json!({ "outpoint": format!("{:064x}.{}", i, i % 4) }))
+        .collect();
+    json!({ "totalOutputs": outputs.len(), "outputs": outputs }).to_string()
+}
+
+#[test]
+fn the_preferred_accepted_encoding_is_negotiated() {
+    let cases = [
+        ("gzip", Some(Encoding::Gzip)),
+        ("gzip, deflate, br", Some(Encoding::Brotli)),
+        ("br;q=0.5, gzip", Some(Encoding::Gzip)),
+        ("x-gzip", Some(Encoding::Gzip)),
+        ("*", Some(Encoding::Brotli)),
+        ("*;q=0.2, gzip;q=0.8", Some(Encoding::Gzip)),
+        ("br;q=0, gzip;q=0", None),
+        ("identity", None),
+        ("", None),
+    ];
+    for (accept_encoding, expected) in cases {
+        assert_eq!(
+            compress::negotiate(accept_encoding),
+            expected,
+            "{}",
+            accept_encoding
+        );
+    }
+}
+
+#[tokio::test]
+async fn small_bodies_are_sent_as_is() {
+    let body = "x".repeat(MIN_COMPRESS_SIZE - 1);
+    let (sent, applied) =
+        compress::response_body(WalletBody::Full(body.clone()), Some(Encoding::Gzip));
+    assert_eq!(applied, None);
+    assert_eq!(bytes(sent).await, body.as_bytes());
+}
+
+#[tokio::test]
+async fn large_bodies_are_compressed() {
+    let body = large_result(16 * 1024);
+    for encoding in [Encoding::Brotli, Encoding::Gzip] {
+        let (sent, applied) =
+            compress::response_body(WalletBody::Full(body.clone()), Some(encoding));
+        assert_eq!(applied, Some(encoding));
+        let compressed = bytes(sent).await;
+        assert!(compressed.len() < body.len() / 4);
+        assert_eq!(decode(encoding, &compressed).await, body.as_bytes());
+    }
+
+    let (sent, applied) = compress::response_body(WalletBody::Full(body.clone()), None);
+    assert_eq!(applied, None);
+    assert_eq!(bytes(sent).await, body.as_bytes());
+}
+
+/// A listening bridge whose emitted `http-request` payloads arrive on the
+/// returned channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+/// Have the wallet answer a `listOutputs` call with `parts`, sent as a
+/// chunked response, and return the body the bridge relays.
+async fn chunked_answer(parts: &[&str]) -> WalletBody {
+    let (bridge, mut events) = bridge();
+    let forward = tokio::spawn({
+        let bridge = bridge.clone();
+        async move {
+            let request = WalletRequest {
+                method: "POST".to_string(),
+                path: "/listOutputs".to_string(),
+                headers: vec![("origin".to_string(), "https://app.example".to_string())],
+                body: json!({ "basket": "default" }).to_string(),
+                peer: None,
+            };
+            bridge.forward(request).await
+        }
+    });
+    let event = timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap();
+    let request_id = &event["request_id"];
+
+    bridge
+        .resolve(&json!({ "request_id": request_id, "status": 200, "chunked": true }).to_string());
+    let response = forward.await.unwrap().unwrap();
+    for (i, data) in parts.iter().enumerate() {
+        let done = i == parts.len() - 1;
+        bridge.resolve_chunk(
+            &json!({ "request_id": request_id, "data": data, "done": done }).to_string(),
+        );
+    }
+    response.body
+}
+
+#[tokio::test]
+async fn chunked_responses_arrive_whole() {
+    let body = large_result(16 * 1024);
+    let parts: Vec<&str> = [&body[..100], &body[100..5000], &body[5000..]].to_vec();
+
+    let relayed = chunked_answer(&parts).await;
+    assert!(matches!(relayed, WalletBody::Chunked(_)));
+    assert_eq!(relayed.into_string().await.unwrap(), body);
+
+    let (sent, applied) = compress::response_body(chunked_answer(&parts).await, None);
+    assert_eq!(applied, None);
+    assert_eq!(bytes(sent).await, body.as_bytes());
+}
+
+#[tokio::test]
+async fn chunked_responses_are_compressed_as_a_stream() {
+    let body = large_result(16 * 1024);
+    let parts: Vec<&str> = body
+        .as_bytes()
+        .chunks(1000)
+        .map(|part| std::str::from_utf8(part).unwrap())
+        .collect();
+
+    for encoding in [Encoding::Brotli, Encoding::Gzip] {
+        let relayed = chunked_answer(&parts).await;
+        // Even short chunked bodies are compressed, since their length is
+        // not known up front.
+        let (sent, applied) = compress::response_body(relayed, Some(encoding));
+        assert_eq!(applied, Some(encoding));
+        assert_eq!(decode(encoding, &bytes(sent).await).await, body.as_bytes());
+    }
+}
@@ -0,0 +1,213 @@
+//! Validating `metanet:` and `web+bsv:` links before the frontend sees
+//! them.
+
+use metanet_desktop::deep_link::{
+    is_deep_link, parse, DeepLink, DeepLinkError, MAX_LINK_LENGTH, MAX_MEMO_LENGTH,
+};
+
+const PAYMAIL: &str = "alice@example.com";
+const ADDRESS: &str = "1BoatSLRHtKNngkdXEeobR76b53LETtpyT";
+const MAX_SATOSHIS: u64 = 21_000_000 * 100_000_000;
+
+fn payment(to: &str, satoshis: u64) -> DeepLink {
+    DeepLink::Payment {
+        to: to.to_string(),
+        satoshis,
+        memo: None,
+        origin: None,
+    }
+}
+
+/// `value` as a query parameter value.
+fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+#[track_caller]
+fn assert_invalid(result: Result<DeepLink, DeepLinkError>, name: &str) {
+    match result {
+        Err(DeepLinkError::InvalidParameter { name: n, .. }) if n == name => {}
+        other => panic!("expected an invalid `{}`, got {:?}", name, other),
+    }
+}
+
+#[test]
+fn both_url_forms_and_both_schemes_are_accepted() {
+    for link in [
+        "metanet:pay?to=alice@example.com&amount=1000",
+        "metanet://pay?to=alice@example.com&amount=1000",
+        "metanet:///pay?to=alice%40example.com&amount=1000",
+        "web+bsv:pay?to=alice@example.com&amount=1000",
+        "METANET://PAY?to=alice@example.com&amount=1000",
+        "  metanet:pay?to=alice@example.com&amount=1000\n",
+    ] {
+        assert_eq!(parse(link), Ok(payment(PAYMAIL, 1000)), "{}", link);
+    }
+    assert_eq!(
+        parse(&format!("metanet:pay?to={}&amount=1", ADDRESS)),
+        Ok(payment(ADDRESS, 1))
+    );
+}
+
+#[test]
+fn identity_and_connect_links_name_the_app() {
+    assert_eq!(
+        parse("metanet:identity?origin=https://app.example&fields=name,%20email,,avatar_url"),
+        Ok(DeepLink::Identity {
+            origin: "https://app.example".to_string(),
+            fields: vec!["name".into(), "email".into(), "avatar_url".into()],
+        })
+    );
+    assert_eq!(
+        parse("metanet://connect?origin=http%3A%2F%2Flocalhost%3A3000%2Fapp"),
+        Ok(DeepLink::Connect {
+            origin: "http://localhost:3000".to_string()
+        })
+    );
+    assert_eq!(
+        parse("metanet:connect"),
+        Err(DeepLinkError::MissingParameter("origin"))
+    );
+    assert_invalid(
+        parse("metanet:identity?origin=https://app.example&fields=name,e-mail"),
+        "fields",
+    );
+}
+
+#[test]
+fn origins_are_reduced_to_scheme_host_and_port() {
+    for (given, origin) in [
+        ("https://App.Example/pay?x=1#y", "https://app.example"),
+        ("https://app.example:443", "https://app.example"),
+        ("https://app.example:8443/", "https://app.example:8443"),
+        ("http://127.0.0.1:3000", "http://127.0.0.1:3000"),
+    ] {
+        let link = format!(
+            "metanet:pay?to={}&amount=1&origin={}",
+            PAYMAIL,
+            encode(given)
+        );
+        match parse(&link) {
+            Ok(DeepLink::Payment {
+                origin: Some(parsed),
+                ..
+            }) => assert_eq!(parsed, origin, "{}", given),
+            other => panic!("{}: {:?}", given, other),
+        }
+    }
+    for given in [
+        "ftp://app.example",
+        "javascript:alert(1)",
+        "app.example",
+        "file:///etc",
+    ] {
+        assert_invalid(
+            parse(&format!("metanet:connect?origin={}", encode(given))),
+            "origin",
+        );
+    }
+}
+
+#[test]
+fn amounts_are_whole_satoshis_within_the_supply() {
+    assert_eq!(
+        parse(&format!(
+            "metanet:pay?to={}&amount={}",
+            PAYMAIL, MAX_SATOSHIS
+        )),
+        Ok(payment(PAYMAIL, MAX_SATOSHIS))
+    );
+    for amount in [
+        "0".to_string(),
+        "-1".to_string(),
+        "1.5".to_string(),
+        "1e3".to_string(),
+        "ten".to_string(),
+        (MAX_SATOSHIS + 1).to_string(),
+        "99999999999999999999999".to_string(),
+    ] {
+        assert_invalid(
+            parse(&format!("metanet:pay?to={}&amount={}", PAYMAIL, amount)),
+            "amount",
+        );
+    }
+    assert_eq!(
+        parse(&format!("metanet:pay?to={}&amount=", PAYMAIL)),
+        Err(DeepLinkError::MissingParameter("amount"))
+    );
+}
+
+#[test]
+fn parameters_may_only_be_given_once() {
+    assert_eq!(
+        parse("metanet:pay?to=alice@example.com&amount=1&amount=1000000"),
+        Err(DeepLinkError::DuplicateParameter("amount".to_string()))
+    );
+    assert_eq!(
+        parse("metanet:pay?to=alice@example.com&amount=1&to=mallory@example.com"),
+        Err(DeepLinkError::DuplicateParameter("to".to_string()))
+    );
+}
+
+#[test]
+fn memos_are_short_plain_text() {
+    let link = |memo: &str| format!("metanet:pay?to={}&amount=1&memo={}", PAYMAIL, encode(memo));
+    let longest = "é".repeat(MAX_MEMO_LENGTH);
+    for memo in ["Coffee for two ☕", longest.as_str()] {
+        match parse(&link(memo)) {
+            Ok(DeepLink::Payment { memo: parsed, .. }) => assert_eq!(parsed.as_deref(), Some(memo)),
+            other => panic!("{:?}", other),
+        }
+    }
+    let too_long = "a".repeat(MAX_MEMO_LENGTH + 1);
+    for memo in [
+        "line\nbreak",
+        "bell\u{7}",
+        "nul\0",
+        "\u{1b}[31mred",
+        too_long.as_str(),
+    ] {
+        assert_invalid(parse(&link(memo)), "memo");
+    }
+}
+
+#[test]
+fn other_links_are_rejected() {
+    assert!(matches!(
+        parse("bitcoin:1BoatSLRHtKNngkdXEeobR76b53LETtpyT?amount=1"),
+        Err(DeepLinkError::UnsupportedScheme(_))
+    ));
+    assert_eq!(
+        parse("metanet:sweep?to=alice@example.com"),
+        Err(DeepLinkError::UnknownAction("sweep".to_string()))
+    );
+    assert_eq!(
+        parse("metanet:pay?amount=1"),
+        Err(DeepLinkError::MissingParameter("to"))
+    );
+    for to in [
+        "alice",
+        "@example.com",
+        "alice@localhost",
+        "alice@-bad.com",
+        "0OIl0OIl0OIl0OIl0OIl0OIl0OIl",
+    ] {
+        assert_invalid(parse(&format!("metanet:pay?to={}&amount=1", to)), "to");
+    }
+    let long = format!(
+        "metanet:pay?to={}&amount=1&memo={}",
+        PAYMAIL,
+        "a".repeat(MAX_LINK_LENGTH)
+    );
+    assert_eq!(parse(&long), Err(DeepLinkError::TooLong));
+    assert!(matches!(parse("metanet"), Err(DeepLinkError::Malformed(_))));
+}
+
+#[test]
+fn only_our_schemes_look_like_links() {
+    assert!(is_deep_link("metanet:pay?to=a@b.c&amount=1"));
+    assert!(is_deep_link("Web+BSV://connect"));
+    assert!(!is_deep_link("--minimized"));
+    assert!(!is_deep_link("https://app.example"));
+    assert!(!is_deep_link("/home/me/metanet:notes.txt"));
+}
@@ -0,0 +1,150 @@
+//! The focus state machine, driven against a driver that records calls.
+//! The tests run on a paused clock, so delays pass without waiting.
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use metanet_desktop::focus::{Focus, FocusConfig, FocusDriver, FocusState};
+
+#[derive(Clone, Default)]
+struct Recorder {
+    calls: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Recorder {
+    fn calls(&self) -> Vec<&'static str> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl FocusDriver for Recorder {
+    fn grab(&self) {
+        self.calls.lock().unwrap().push("grab");
+    }
+
+    fn relinquish(&self) {
+        self.calls.lock().unwrap().push("relinquish");
+    }
+
+    fn is_focused(&self) -> bool {
+        self.calls.lock().unwrap().last() == Some(&"grab")
+    }
+}
+
+const HOLD_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn start() -> (Focus, Recorder) {
+    let (focus, focus_loop) = Focus::new(FocusConfig {
+        grab_delay: Duration::from_millis(20),
+        relinquish_delay: Duration::from_millis(20),
+        hold_timeout: HOLD_TIMEOUT,
+    });
+    let driver = Recorder::default();
+    tokio::spawn(focus_loop.run(driver.clone()));
+    (focus, driver)
+}
+
+/// Let the loop handle what was sent and any delay it started run out.
+/// The clock only moves on once the loop and the driver are idle.
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(100)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_burst_of_asks_grabs_once() {
+    let (focus, driver) = start();
+    for _ in 0..5 {
+        focus.request();
+    }
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+    assert_eq!(focus.state(), FocusState::Focused);
+
+    for _ in 0..4 {
+        focus.release();
+    }
+    settle().await;
+    assert_eq!(focus.state(), FocusState::Focused);
+    focus.release();
+    settle().await;
+    assert_eq!(driver.calls(), ["grab", "relinquish"]);
+    assert_eq!(focus.state(), FocusState::Idle);
+}
+
+#[tokio::test(start_paused = true)]
+async fn an_ask_released_before_the_grab_grabs_nothing() {
+    let (focus, driver) = start();
+    focus.request();
+    focus.release();
+    settle().await;
+    assert!(driver.calls().is_empty());
+    assert_eq!(focus.state(), FocusState::Idle);
+}
+
+#[tokio::test(start_paused = true)]
+async fn releasing_without_an_ask_gives_focus_back() {
+    let (focus, driver) = start();
+    focus.release();
+    settle().await;
+    assert_eq!(driver.calls(), ["relinquish"]);
+    assert_eq!(focus.state(), FocusState::Idle);
+}
+
+#[tokio::test(start_paused = true)]
+async fn unreleased_asks_time_out() {
+    let (focus, driver) = start();
+    focus.request();
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+
+    tokio::time::advance(HOLD_TIMEOUT - Duration::from_secs(1)).await;
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+    assert_eq!(focus.state(), FocusState::Focused);
+
+    tokio::time::advance(Duration::from_secs(1)).await;
+    settle().await;
+    assert_eq!(driver.calls(), ["grab", "relinquish"]);
+    assert_eq!(focus.state(), FocusState::Idle);
+
+    // The stale holder no longer counts.
+    focus.request();
+    focus.release();
+    settle().await;
+    assert_eq!(focus.state(), FocusState::Idle);
+}
+
+#[tokio::test(start_paused = true)]
+async fn showing_holds_nothing() {
+    let (focus, driver) = start();
+    focus.show();
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+
+    tokio::time::advance(HOLD_TIMEOUT * 2).await;
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+    assert_eq!(focus.state(), FocusState::Focused);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_release_does_not_call_off_showing() {
+    let (focus, driver) = start();
+    focus.show();
+    focus.request();
+    focus.release();
+    settle().await;
+    assert_eq!(driver.calls(), ["grab"]);
+    assert_eq!(focus.state(), FocusState::Focused);
+
+    // Once shown, a later burst is called off as usual.
+    focus.release();
+    settle().await;
+    focus.request();
+    focus.release();
+    settle().await;
+    assert_eq!(driver.calls(), ["grab", "relinquish"]);
+    assert_eq!(focus.state(), FocusState::Idle);
+}
@@ -0,0 +1,142 @@
+//! Replaying spending calls retried with the same `Idempotency-Key`.
+
+use std::{sync::Arc, time::Duration};
+
+use metanet_desktop::bridge::{Bridge, BridgeError, WalletRequest};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, time::timeout};
+
+/// A bridge whose emitted `http-request` payloads arrive on the returned
+/// channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    // The frontend listens once the user has authenticated.
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+fn create_action(key: &str, description: &str) -> WalletRequest {
+    WalletRequest {
+        method: "POST".to_string(),
+        path: "/createAction".to_string(),
+        headers: vec![
+            ("origin".to_string(), "https://app.example".to_string()),
+            ("Idempotency-Key".to_string(), key.to_string()),
+        ],
+        body: json!({ "description": description }).to_string(),
+        peer: None,
+    }
+}
+
+async fn send(bridge: &Arc<Bridge>, request: WalletRequest) -> Result<String, BridgeError> {
+    let response = bridge.forward(request).await?;
+    response.body.into_string().await
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap()
+}
+
+fn answer(bridge: &Bridge, event: &Value, body: &str) {
+    let response = json!({
+        "request_id": event["request_id"],
+        "status": 200,
+        "body": body,
+    });
+    bridge.resolve(&response.to_string());
+}
+
+#[tokio::test]
+async fn retries_are_replayed() {
+    let (bridge, mut events) = bridge();
+    let first = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    let event = next_event(&mut events).await;
+    answer(&bridge, &event, "{\"txid\":\"a\"}");
+    assert_eq!(first.await.unwrap().unwrap(), "{\"txid\":\"a\"}");
+
+    let retry = send(&bridge, create_action("k1", "pay for coffee")).await;
+    assert_eq!(retry.unwrap(), "{\"txid\":\"a\"}");
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn a_retry_after_the_first_caller_gave_up_is_replayed() {
+    let (bridge, mut events) = bridge();
+    let first = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    let event = next_event(&mut events).await;
+
+    // The client times out while the wallet is still asking the user.
+    first.abort();
+    assert!(first.await.unwrap_err().is_cancelled());
+    assert_eq!(bridge.pending_count(), 1);
+
+    // Its retry waits for the first attempt rather than sending another.
+    let retry = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(events.try_recv().is_err());
+
+    answer(&bridge, &event, "{\"txid\":\"a\"}");
+    assert_eq!(retry.await.unwrap().unwrap(), "{\"txid\":\"a\"}");
+
+    // Later retries are replayed too.
+    let again = send(&bridge, create_action("k1", "pay for coffee")).await;
+    assert_eq!(again.unwrap(), "{\"txid\":\"a\"}");
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn a_reused_key_with_other_arguments_is_refused() {
+    let (bridge, mut events) = bridge();
+    let first = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    let event = next_event(&mut events).await;
+    answer(&bridge, &event, "{}");
+    first.await.unwrap().unwrap();
+
+    let other = send(&bridge, create_action("k1", "pay for lunch")).await;
+    assert!(matches!(other, Err(BridgeError::IdempotencyKeyReused)));
+}
+
+#[tokio::test]
+async fn a_first_attempt_the_wallet_never_answered_is_forgotten() {
+    let (bridge, mut events) = bridge();
+    let first = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    next_event(&mut events).await;
+    // Locking fails every pending request without an answer.
+    bridge.lock();
+    assert!(matches!(first.await.unwrap(), Err(BridgeError::NoResponse)));
+
+    // Once unlocked, the retry goes to the wallet again.
+    bridge.publish(&json!({ "event": "unlocked" }).to_string());
+    let retry = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { send(&bridge, create_action("k1", "pay for coffee")).await }
+    });
+    let event = next_event(&mut events).await;
+    answer(&bridge, &event, "{}");
+    assert_eq!(retry.await.unwrap().unwrap(), "{}");
+}
@@ -0,0 +1,165 @@
+//! Scheduling lanes, and how long requests may hold them.
+
+use std::{sync::Arc, time::Duration};
+
+use metanet_desktop::{
+    brc100::Method,
+    bridge::{Bridge, BridgeConfig, BridgeError, WalletRequest},
+    schedule::{LaneLimits, Scheduler},
+};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, time::timeout};
+
+const LIMITS: LaneLimits = LaneLimits {
+    read_only: 2,
+    crypto: 1,
+    spending: 1,
+};
+
+/// Whether `admit` finishes promptly.
+async fn admitted<F: std::future::Future>(admit: F) -> Option<F::Output> {
+    timeout(Duration::from_millis(50), admit).await.ok()
+}
+
+#[tokio::test]
+async fn each_class_waits_only_on_its_own_lane() {
+    let scheduler = Scheduler::new(LIMITS, false);
+    let create = scheduler.admit(Method::CreateAction, Some("a")).await;
+    assert!(admitted(scheduler.admit(Method::SignAction, Some("b")))
+        .await
+        .is_none());
+
+    // Reads and key operations keep flowing past the pending approval.
+    let _height = scheduler.admit(Method::GetHeight, Some("a")).await;
+    let _network = scheduler.admit(Method::GetNetwork, Some("b")).await;
+    let _key = scheduler.admit(Method::GetPublicKey, Some("a")).await;
+
+    // Until their own lane is full.
+    assert!(admitted(scheduler.admit(Method::GetVersion, Some("c")))
+        .await
+        .is_none());
+    assert!(admitted(scheduler.admit(Method::Encrypt, Some("c")))
+        .await
+        .is_none());
+
+    // Dropping an admission lets the next request through.
+    drop(create);
+    assert!(admitted(scheduler.admit(Method::SignAction, Some("b")))
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn waiting_requests_are_let_through_in_arrival_order() {
+    let scheduler = Arc::new(Scheduler::new(LIMITS, false));
+    let first = scheduler.admit(Method::CreateAction, None).await;
+    let (tx, mut order) = mpsc::unbounded_channel();
+    for i in 0..3 {
+        let scheduler = scheduler.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let _admission = scheduler.admit(Method::CreateAction, None).await;
+            tx.send(i).unwrap();
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    drop(first);
+    for i in 0..3 {
+        assert_eq!(order.recv().await, Some(i));
+    }
+}
+
+#[tokio::test]
+async fn waiting_for_authentication_takes_no_slot() {
+    let scheduler = Scheduler::new(LIMITS, false);
+    let _reads = (
+        scheduler.admit(Method::GetHeight, None).await,
+        scheduler.admit(Method::GetHeight, None).await,
+    );
+    assert!(
+        admitted(scheduler.admit(Method::WaitForAuthentication, None))
+            .await
+            .is_some()
+    );
+}
+
+/// A bridge with the given answer timeout whose emitted `http-request`
+/// payloads arrive on the returned channel.
+fn bridge(answer_timeout: Duration) -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let config = BridgeConfig {
+        lanes: LIMITS,
+        answer_timeout,
+        ..BridgeConfig::default()
+    };
+    (Arc::new(Bridge::with_config(emit, config)), rx)
+}
+
+fn authenticate(bridge: &Bridge) {
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+}
+
+fn request(path: &str, origin: &str) -> WalletRequest {
+    WalletRequest {
+        method: "POST".to_string(),
+        path: path.to_string(),
+        headers: vec![("origin".to_string(), format!("https://{}", origin))],
+        body: "{}".to_string(),
+        peer: None,
+    }
+}
+
+fn spawn_forward(
+    bridge: &Arc<Bridge>,
+    request: WalletRequest,
+) -> tokio::task::JoinHandle<Result<String, BridgeError>> {
+    let bridge = bridge.clone();
+    tokio::spawn(async move { bridge.forward(request).await?.body.into_string().await })
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn requests_wait_for_the_frontend_to_listen() {
+    let (bridge, mut events) = bridge(Duration::from_secs(60));
+    let height = spawn_forward(&bridge, request("/getHeight", "a.example"));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(events.try_recv().is_err());
+
+    authenticate(&bridge);
+    let event = next_event(&mut events).await;
+    assert_eq!(event["path"], "/getHeight");
+    bridge.resolve(
+        &json!({ "request_id": event["request_id"], "status": 200, "body": "{}" }).to_string(),
+    );
+    assert_eq!(height.await.unwrap().unwrap(), "{}");
+}
+
+#[tokio::test]
+async fn an_unanswered_request_gives_up_its_lane() {
+    let (bridge, mut events) = bridge(Duration::from_millis(100));
+    authenticate(&bridge);
+    let first = spawn_forward(&bridge, request("/createAction", "a.example"));
+    next_event(&mut events).await;
+    let second = spawn_forward(&bridge, request("/createAction", "b.example"));
+
+    // The first call times out, letting the other app's call through.
+    assert!(matches!(first.await.unwrap(), Err(BridgeError::NoResponse)));
+    let event = next_event(&mut events).await;
+    assert_eq!(bridge.pending_count(), 1);
+    bridge.resolve(
+        &json!({ "request_id": event["request_id"], "status": 200, "body": "{}" }).to_string(),
+    );
+    assert_eq!(second.await.unwrap().unwrap(), "{}");
+}
@@ -0,0 +1,167 @@
+//! The native messaging wire format, caller origins and host manifests.
+
+use std::{fs, path::PathBuf};
+
+use metanet_desktop::native_messaging::{
+    caller_origin, install_manifests, read_message, uninstall_manifests, write_message, Browser,
+    HOST_NAME, MAX_INCOMING_SIZE,
+};
+use serde_json::{json, Value};
+use tokio::io::AsyncWriteExt;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+/// An empty directory to stand in for a home directory.
+fn temp_home(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metanet-{}-{}", name, std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn messages_round_trip_with_a_length_prefix() {
+    let (mut browser, mut host) = tokio::io::duplex(1024);
+    write_message(&mut browser, br#"{"id":1}"#).await.unwrap();
+    write_message(&mut browser, b"").await.unwrap();
+    drop(browser);
+
+    assert_eq!(
+        read_message(&mut host).await.unwrap().as_deref(),
+        Some(&br#"{"id":1}"#[..])
+    );
+    assert_eq!(
+        read_message(&mut host).await.unwrap().as_deref(),
+        Some(&b""[..])
+    );
+    // The browser closing the pipe ends the stream.
+    assert_eq!(read_message(&mut host).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn the_prefix_is_native_endian() {
+    let (mut browser, mut host) = tokio::io::duplex(1024);
+    write_message(&mut browser, b"{}").await.unwrap();
+    drop(browser);
+    let mut wire = Vec::new();
+    tokio::io::AsyncReadExt::read_to_end(&mut host, &mut wire)
+        .await
+        .unwrap();
+    assert_eq!(wire[..4], 2u32.to_ne_bytes());
+    assert_eq!(&wire[4..], b"{}");
+}
+
+#[tokio::test]
+async fn oversize_and_truncated_messages_fail() {
+    let (mut browser, mut host) = tokio::io::duplex(1024);
+    let length = (MAX_INCOMING_SIZE as u32 + 1).to_ne_bytes();
+    browser.write_all(&length).await.unwrap();
+    let error = read_message(&mut host).await.unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+
+    let (mut browser, mut host) = tokio::io::duplex(1024);
+    browser.write_all(&10u32.to_ne_bytes()).await.unwrap();
+    browser.write_all(b"{}").await.unwrap();
+    drop(browser);
+    assert!(read_message(&mut host).await.is_err());
+}
+
+#[test]
+fn chrome_callers_are_identified_by_their_origin() {
+    let origin = caller_origin(&args(&["chrome-extension://abcdefghijklmnop/"]));
+    assert_eq!(
+        origin,
+        Some(("Origin", "chrome-extension://abcdefghijklmnop".to_string()))
+    );
+
+    // Chrome on Windows also passes a parent window handle.
+    let origin = caller_origin(&args(&[
+        "chrome-extension://abcdefghijklmnop/",
+        "--parent-window=0",
+    ]));
+    assert_eq!(origin.unwrap().1, "chrome-extension://abcdefghijklmnop");
+}
+
+#[test]
+fn firefox_callers_are_identified_by_their_extension_id() {
+    let manifest = "/home/u/.mozilla/native-messaging-hosts/x.json";
+    let cases = [
+        (
+            "wallet@example.org",
+            Some("moz-extension://wallet-example.org"),
+        ),
+        (
+            "{6A3B0C2E-1111-4222-8333-944455556666}",
+            Some("moz-extension://6a3b0c2e-1111-4222-8333-944455556666"),
+        ),
+        ("@@", None),
+    ];
+    for (id, expected) in cases {
+        let origin = caller_origin(&args(&[manifest, id]));
+        assert_eq!(
+            origin,
+            expected.map(|o| ("Originator", o.to_string())),
+            "{}",
+            id
+        );
+    }
+    assert_eq!(caller_origin(&args(&[manifest])), None);
+    assert_eq!(caller_origin(&[]), None);
+}
+
+#[test]
+fn manifests_allow_the_given_extensions() {
+    let host = PathBuf::from("/opt/metanet/metanet-native-host");
+    let chrome_ids = args(&["abc"]);
+    let firefox_ids = args(&["wallet@example.org"]);
+
+    let chrome = Browser::Chrome.manifest(&host, &chrome_ids, &firefox_ids);
+    assert_eq!(
+        chrome,
+        json!({
+            "name": HOST_NAME,
+            "description": "Metanet Desktop wallet",
+            "path": "/opt/metanet/metanet-native-host",
+            "type": "stdio",
+            "allowed_origins": ["chrome-extension://abc/"],
+        })
+    );
+    let firefox = Browser::Firefox.manifest(&host, &chrome_ids, &firefox_ids);
+    assert_eq!(firefox["allowed_extensions"], json!(["wallet@example.org"]));
+    assert!(firefox.get("allowed_origins").is_none());
+}
+
+#[test]
+fn manifests_are_installed_for_browsers_present_and_removed_again() {
+    let home = temp_home("native-messaging");
+    fs::create_dir_all(home.join(".config/google-chrome")).unwrap();
+    fs::create_dir_all(home.join(".mozilla")).unwrap();
+    let host = PathBuf::from("/opt/metanet/metanet-native-host");
+
+    let mut written = install_manifests(&home, &host, &args(&["abc"]), &args(&["w@x"])).unwrap();
+    written.sort();
+    let mut expected = vec![
+        home.join(".config/google-chrome/NativeMessagingHosts")
+            .join(format!("{}.json", HOST_NAME)),
+        home.join(".mozilla/native-messaging-hosts")
+            .join(format!("{}.json", HOST_NAME)),
+    ];
+    expected.sort();
+    assert_eq!(written, expected);
+    let chrome: Value =
+        serde_json::from_str(&fs::read_to_string(Browser::Chrome.manifest_path(&home)).unwrap())
+            .unwrap();
+    assert_eq!(chrome["path"], "/opt/metanet/metanet-native-host");
+
+    // Browsers without extensions to allow are left alone.
+    let written = install_manifests(&home, &host, &[], &args(&["w@x"])).unwrap();
+    assert_eq!(written, vec![Browser::Firefox.manifest_path(&home)]);
+
+    let mut removed = uninstall_manifests(&home).unwrap();
+    removed.sort();
+    assert_eq!(removed, expected);
+    assert!(uninstall_manifests(&home).unwrap().is_empty());
+    fs::remove_dir_all(&home).unwrap();
+}
@@ -0,0 +1,73 @@
+//! Which app requests raise a notification, what it says, and how often.
+
+use std::time::{Duration, Instant};
+
+use metanet_desktop::{
+    brc100::Method,
+    bridge::IncomingRequest,
+    notifications::{body, needs_attention, summary, RateLimiter, MIN_INTERVAL},
+};
+
+fn request(method: Method, origin: Option<&str>) -> IncomingRequest {
+    IncomingRequest {
+        request_id: 1,
+        method,
+        origin: origin.map(str::to_string),
+    }
+}
+
+#[test]
+fn each_app_gets_one_notification_per_interval() {
+    let mut limiter = RateLimiter::new(MIN_INTERVAL);
+    let start = Instant::now();
+    assert!(limiter.allow(Some("a.example"), start));
+    assert!(!limiter.allow(Some("a.example"), start + Duration::from_secs(1)));
+    // Other apps have their own allowance.
+    assert!(limiter.allow(Some("b.example"), start + Duration::from_secs(1)));
+
+    let almost = start + MIN_INTERVAL - Duration::from_millis(1);
+    assert!(!limiter.allow(Some("a.example"), almost));
+    assert!(limiter.allow(Some("a.example"), start + MIN_INTERVAL));
+    // Counted from the last notification shown, not the last one refused.
+    assert!(!limiter.allow(
+        Some("a.example"),
+        start + MIN_INTERVAL * 2 - Duration::from_millis(1)
+    ));
+}
+
+#[test]
+fn requests_without_an_origin_share_an_allowance() {
+    let mut limiter = RateLimiter::new(Duration::from_secs(10));
+    let start = Instant::now();
+    assert!(limiter.allow(None, start));
+    assert!(!limiter.allow(None, start + Duration::from_secs(5)));
+    assert!(limiter.allow(Some(""), start + Duration::from_secs(10)));
+    assert!(!limiter.allow(None, start + Duration::from_secs(11)));
+}
+
+#[test]
+fn a_clock_going_backwards_does_not_lift_the_limit() {
+    let mut limiter = RateLimiter::new(Duration::from_secs(10));
+    let start = Instant::now() + Duration::from_secs(60);
+    assert!(limiter.allow(Some("a.example"), start));
+    assert!(!limiter.allow(Some("a.example"), start - Duration::from_secs(5)));
+}
+
+#[test]
+fn only_requests_beyond_reads_need_the_user() {
+    assert!(needs_attention(&request(Method::CreateAction, None)));
+    assert!(needs_attention(&request(Method::CreateSignature, None)));
+    assert!(!needs_attention(&request(Method::GetHeight, None)));
+    assert!(!needs_attention(&request(Method::ListOutputs, None)));
+}
+
+#[test]
+fn notifications_name_the_app_and_the_call() {
+    let create = request(Method::CreateAction, Some("shop.example"));
+    assert_eq!(summary(&create), "Request from shop.example");
+    assert_eq!(body(&create), "createAction: create a transaction");
+    assert_eq!(
+        summary(&request(Method::Encrypt, None)),
+        "Request from an app"
+    );
+}
@@ -0,0 +1,171 @@
+//! Per-origin ordering of spending calls through the bridge.
+
+use std::{sync::Arc, time::Duration};
+
+use metanet_desktop::{
+    bridge::{Bridge, WalletRequest},
+    settings::Settings,
+};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, time::timeout};
+
+/// A bridge set up from `settings` as the app does, whose emitted
+/// `http-request` payloads arrive on the returned channel.
+fn bridge_with(settings: Settings) -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::with_config(emit, settings.bridge_config()));
+    // The frontend listens once the user has authenticated.
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+/// A bridge with enough spending slots that only ordering holds calls back.
+fn bridge(order_per_origin: bool) -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    bridge_with(Settings {
+        order_per_origin,
+        concurrent_spending_calls: 4,
+        ..Settings::default()
+    })
+}
+
+fn request(path: &str, origin: &str, body: Value) -> WalletRequest {
+    WalletRequest {
+        method: "POST".to_string(),
+        path: path.to_string(),
+        headers: vec![("origin".to_string(), format!("https://{}", origin))],
+        body: body.to_string(),
+        peer: None,
+    }
+}
+
+/// Forward `request` in the background and give it time to reach the bridge,
+/// so spawn order is arrival order.
+async fn send(bridge: &Arc<Bridge>, request: WalletRequest) -> tokio::task::JoinHandle<String> {
+    let bridge = bridge.clone();
+    let handle = tokio::spawn(async move {
+        let response = bridge.forward(request).await.unwrap();
+        response.body.into_string().await.unwrap()
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    handle
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap()
+}
+
+async fn assert_no_event(events: &mut mpsc::UnboundedReceiver<Value>) {
+    let event = timeout(Duration::from_millis(100), events.recv()).await;
+    assert!(event.is_err(), "unexpected request emitted: {:?}", event);
+}
+
+fn answer(bridge: &Bridge, event: &Value, body: &str) {
+    let response = json!({
+        "request_id": event["request_id"],
+        "status": 200,
+        "body": body,
+    });
+    bridge.resolve(&response.to_string());
+}
+
+#[tokio::test]
+async fn spending_calls_from_one_origin_are_emitted_in_arrival_order() {
+    let (bridge, mut events) = bridge(true);
+    let paths = [
+        "/createAction",
+        "/signAction",
+        "/internalizeAction",
+        "/abortAction",
+    ];
+    let mut handles = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        let body = json!({ "description": format!("call {}", i) });
+        handles.push(send(&bridge, request(path, "app.example", body)).await);
+    }
+
+    for (i, path) in paths.iter().enumerate() {
+        let event = next_event(&mut events).await;
+        assert_eq!(event["path"], *path);
+        assert!(event["body"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("call {}", i)));
+        // The next call waits until this one has been answered.
+        assert_no_event(&mut events).await;
+        answer(&bridge, &event, &i.to_string());
+    }
+    for (i, handle) in handles.into_iter().enumerate() {
+        assert_eq!(handle.await.unwrap(), i.to_string());
+    }
+}
+
+#[tokio::test]
+async fn reads_from_the_same_origin_stay_concurrent() {
+    let (bridge, mut events) = bridge(true);
+    let create = send(&bridge, request("/createAction", "app.example", json!({}))).await;
+    let first = next_event(&mut events).await;
+    assert_eq!(first["path"], "/createAction");
+
+    let height = send(&bridge, request("/getHeight", "app.example", json!({}))).await;
+    let read = next_event(&mut events).await;
+    assert_eq!(read["path"], "/getHeight");
+    answer(&bridge, &read, "{\"height\":1}");
+    assert_eq!(height.await.unwrap(), "{\"height\":1}");
+
+    answer(&bridge, &first, "{}");
+    create.await.unwrap();
+}
+
+#[tokio::test]
+async fn other_origins_are_not_held_up() {
+    let (bridge, mut events) = bridge(true);
+    let a = send(&bridge, request("/createAction", "a.example", json!({}))).await;
+    let from_a = next_event(&mut events).await;
+
+    let b = send(&bridge, request("/createAction", "b.example", json!({}))).await;
+    let from_b = next_event(&mut events).await;
+    assert_eq!(from_b["path"], "/createAction");
+
+    answer(&bridge, &from_b, "{}");
+    answer(&bridge, &from_a, "{}");
+    a.await.unwrap();
+    b.await.unwrap();
+}
+
+#[tokio::test]
+async fn without_ordering_spending_calls_run_concurrently() {
+    let (bridge, mut events) = bridge(false);
+    let first = send(&bridge, request("/createAction", "app.example", json!({}))).await;
+    let second = send(&bridge, request("/createAction", "app.example", json!({}))).await;
+
+    let one = next_event(&mut events).await;
+    let two = next_event(&mut events).await;
+    answer(&bridge, &two, "{}");
+    answer(&bridge, &one, "{}");
+    first.await.unwrap();
+    second.await.unwrap();
+}
+
+#[tokio::test]
+async fn by_default_spending_calls_from_all_apps_run_one_at_a_time() {
+    let (bridge, mut events) = bridge_with(Settings::default());
+    let a = send(&bridge, request("/createAction", "a.example", json!({}))).await;
+    let b = send(&bridge, request("/createAction", "b.example", json!({}))).await;
+
+    let from_a = next_event(&mut events).await;
+    assert_no_event(&mut events).await;
+    answer(&bridge, &from_a, "{}");
+    let from_b = next_event(&mut events).await;
+    answer(&bridge, &from_b, "{}");
+    a.await.unwrap();
+    b.await.unwrap();
+}
@@ -0,0 +1,92 @@
+//! Whether the user counts as away, with and without the desktop's idle
+//! time.
+
+use std::{thread, time::Duration};
+
+use metanet_desktop::presence::Presence;
+
+const IDLE_AFTER: Duration = Duration::from_millis(10);
+
+/// A presence whose user last used the wallet longer than `IDLE_AFTER` ago.
+fn away_from_the_wallet() -> Presence {
+    let presence = Presence::new(IDLE_AFTER);
+    thread::sleep(IDLE_AFTER * 2);
+    presence
+}
+
+#[test]
+fn only_the_wallet_counts_without_the_system_idle_time() {
+    let presence = away_from_the_wallet();
+    let event = presence.update(None).expect("the user went idle");
+    assert!(event.idle);
+    assert!(!event.system_idle_known);
+}
+
+#[test]
+fn using_other_apps_keeps_the_user_present() {
+    let presence = away_from_the_wallet();
+    assert_eq!(presence.update(Some(Duration::ZERO)), None);
+    let current = presence.current();
+    assert!(!current.idle);
+    assert!(current.system_idle_known);
+}
+
+#[test]
+fn away_from_everything_is_idle() {
+    let presence = away_from_the_wallet();
+    let event = presence
+        .update(Some(Duration::from_secs(3600)))
+        .expect("the user went idle");
+    assert!(event.idle);
+    assert!(event.system_idle_known);
+    // The shorter of the two idle times counts.
+    assert!(event.idle_seconds < 3600);
+}
+
+#[test]
+fn using_the_wallet_brings_the_user_back() {
+    let presence = away_from_the_wallet();
+    assert!(presence.update(None).unwrap().idle);
+    presence.touch();
+    let event = presence.update(None).expect("the user came back");
+    assert!(!event.idle);
+    assert_eq!(presence.update(None), None);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn logind_idle_time_runs_from_when_the_session_went_idle() {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use metanet_desktop::logind_idle::idle_time;
+
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let micros = |at: SystemTime| at.duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+    let since = micros(now - Duration::from_secs(90));
+
+    assert_eq!(idle_time(true, since, now), Duration::from_secs(90));
+    assert_eq!(idle_time(false, since, now), Duration::ZERO);
+    // Set in the future by a clock since set back.
+    let later = micros(now + Duration::from_secs(5));
+    assert_eq!(idle_time(true, later, now), Duration::ZERO);
+}
+
+#[test]
+fn auto_lock_waits_for_the_timeout() {
+    let presence = away_from_the_wallet();
+    presence.update(Some(Duration::ZERO));
+    assert!(!presence.auto_lock_due(IDLE_AFTER));
+    presence.update(Some(Duration::from_secs(3600)));
+    assert!(presence.auto_lock_due(IDLE_AFTER));
+    assert!(!presence.auto_lock_due(Duration::from_secs(3600)));
+}
+
+#[test]
+fn auto_lock_is_off_without_the_system_idle_time() {
+    let presence = away_from_the_wallet();
+    presence.update(None);
+    assert!(presence.current().idle);
+    // However long ago the wallet was used, the user may be in another app.
+    assert!(!presence.auto_lock_due(IDLE_AFTER));
+    assert!(!presence.auto_lock_due(Duration::ZERO));
+}
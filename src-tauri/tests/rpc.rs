@@ -0,0 +1,257 @@
+//! The JSON-RPC front end: calls, batches, notifications and error codes.
+
+use std::{sync::Arc, time::Duration};
+
+use metanet_desktop::{
+    bridge::Bridge,
+    rpc::{
+        self, ACCESS_PAUSED, INVALID_PARAMS, INVALID_REQUEST, MAX_BATCH_SIZE, METHOD_NOT_FOUND,
+        PARSE_ERROR, WALLET_ERROR, WALLET_LOCKED,
+    },
+};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, task::JoinHandle, time::timeout};
+
+/// A listening bridge whose emitted `http-request` payloads arrive on the
+/// returned channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+/// Handle `body` as a JSON-RPC request from `https://app.example`.
+fn call(bridge: &Arc<Bridge>, body: Value) -> JoinHandle<Option<Value>> {
+    let bridge = bridge.clone();
+    tokio::spawn(async move {
+        let headers = vec![("origin".to_string(), "https://app.example".to_string())];
+        rpc::handle(&bridge, headers, None, body.to_string().as_bytes())
+            .await
+            .map(|response| serde_json::from_str(&response).unwrap())
+    })
+}
+
+/// The response to a body that never reaches the wallet.
+async fn refused(bridge: &Arc<Bridge>, body: &[u8]) -> Value {
+    let response = rpc::handle(bridge, Vec::new(), None, body).await.unwrap();
+    serde_json::from_str(&response).unwrap()
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<Value>) -> Value {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap()
+}
+
+fn answer(bridge: &Bridge, event: &Value, status: u16, body: Value) {
+    let response = json!({
+        "request_id": event["request_id"],
+        "status": status,
+        "body": body.to_string(),
+    });
+    bridge.resolve(&response.to_string());
+}
+
+fn get_public_key() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "getPublicKey",
+        "params": { "identityKey": true },
+    })
+}
+
+fn error_code(response: &Value) -> i64 {
+    response["error"]["code"].as_i64().unwrap()
+}
+
+#[tokio::test]
+async fn a_call_is_answered_with_the_wallet_result() {
+    let (bridge, mut events) = bridge();
+    let response = call(
+        &bridge,
+        json!({ "jsonrpc": "2.0", "id": 7, "method": "getHeight", "params": {} }),
+    );
+
+    let event = next_event(&mut events).await;
+    assert_eq!(event["path"], "/getHeight");
+    answer(&bridge, &event, 200, json!({ "height": 850000 }));
+    assert_eq!(
+        response.await.unwrap().unwrap(),
+        json!({ "jsonrpc": "2.0", "result": { "height": 850000 }, "id": 7 })
+    );
+}
+
+#[tokio::test]
+async fn batch_calls_reach_the_wallet_in_order_and_are_answered_in_order() {
+    let (bridge, mut events) = bridge();
+    let methods = ["getVersion", "getNetwork", "getHeight", "isAuthenticated"];
+    let batch: Vec<Value> = methods
+        .iter()
+        .enumerate()
+        .map(|(id, method)| json!({ "jsonrpc": "2.0", "id": id, "method": method }))
+        .collect();
+    let response = call(&bridge, Value::Array(batch));
+
+    let mut emitted = Vec::new();
+    for method in methods {
+        let event = next_event(&mut events).await;
+        assert_eq!(event["path"], format!("/{}", method));
+        emitted.push(event);
+    }
+    // Answered out of order, responses still follow the batch.
+    for (id, event) in emitted.iter().enumerate().rev() {
+        answer(&bridge, event, 200, json!({ "answer": id }));
+    }
+    let response = response.await.unwrap().unwrap();
+    let responses = response.as_array().unwrap();
+    assert_eq!(responses.len(), methods.len());
+    for (id, response) in responses.iter().enumerate() {
+        assert_eq!(response["id"], id);
+        assert_eq!(response["result"]["answer"], id);
+    }
+}
+
+#[tokio::test]
+async fn a_full_batch_reaches_the_wallet_in_order() {
+    let (bridge, mut events) = bridge();
+    let batch: Vec<Value> = (1..=MAX_BATCH_SIZE)
+        .map(|height| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": height,
+                "method": "getHeaderForHeight",
+                "params": { "height": height },
+            })
+        })
+        .collect();
+    let response = call(&bridge, Value::Array(batch));
+
+    // Each call reaches the wallet in the order it was sent, including
+    // those waiting for a free slot.
+    for height in 1..=MAX_BATCH_SIZE {
+        let event = next_event(&mut events).await;
+        let args: Value = serde_json::from_str(event["body"].as_str().unwrap()).unwrap();
+        assert_eq!(args["height"], height);
+        answer(&bridge, &event, 200, json!({ "header": height }));
+    }
+    let response = response.await.unwrap().unwrap();
+    assert_eq!(response.as_array().unwrap().len(), MAX_BATCH_SIZE);
+}
+
+#[tokio::test]
+async fn notifications_get_no_response() {
+    let (bridge, mut events) = bridge();
+    let single = call(&bridge, json!({ "jsonrpc": "2.0", "method": "getHeight" }));
+    answer(&bridge, &next_event(&mut events).await, 200, json!({}));
+    assert_eq!(single.await.unwrap(), None);
+
+    // A batch answers only the calls that have an id.
+    let batch = call(
+        &bridge,
+        json!([
+            { "jsonrpc": "2.0", "method": "getNetwork" },
+            { "jsonrpc": "2.0", "id": "v", "method": "getVersion" },
+        ]),
+    );
+    for _ in 0..2 {
+        answer(&bridge, &next_event(&mut events).await, 200, json!({}));
+    }
+    let responses = batch.await.unwrap().unwrap();
+    assert_eq!(responses.as_array().unwrap().len(), 1);
+    assert_eq!(responses[0]["id"], "v");
+}
+
+#[tokio::test]
+async fn oversize_and_empty_batches_are_refused() {
+    let (bridge, mut events) = bridge();
+    let call = json!({ "jsonrpc": "2.0", "id": 1, "method": "getHeight" });
+    let oversize = Value::Array(vec![call; MAX_BATCH_SIZE + 1]).to_string();
+    let response = refused(&bridge, oversize.as_bytes()).await;
+    assert_eq!(error_code(&response), INVALID_REQUEST);
+    assert_eq!(response["id"], Value::Null);
+
+    let response = refused(&bridge, b"[]").await;
+    assert_eq!(error_code(&response), INVALID_REQUEST);
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn malformed_calls_get_their_error_codes() {
+    let (bridge, mut events) = bridge();
+    let cases: [(&[u8], i64); 6] = [
+        (b"{", PARSE_ERROR),
+        (b"5", INVALID_REQUEST),
+        (br#"{"id":1,"method":"getHeight"}"#, INVALID_REQUEST),
+        (
+            br#"{"jsonrpc":"2.0","id":{},"method":"getHeight"}"#,
+            INVALID_REQUEST,
+        ),
+        (
+            br#"{"jsonrpc":"2.0","id":1,"method":"getBalance"}"#,
+            METHOD_NOT_FOUND,
+        ),
+        (
+            br#"{"jsonrpc":"2.0","id":1,"method":"getHeight","params":5}"#,
+            INVALID_PARAMS,
+        ),
+    ];
+    for (body, code) in cases {
+        let response = refused(&bridge, body).await;
+        assert_eq!(
+            error_code(&response),
+            code,
+            "{}",
+            String::from_utf8_lossy(body)
+        );
+    }
+
+    // Arguments failing validation carry the failing fields.
+    let response = refused(
+        &bridge,
+        br#"{"jsonrpc":"2.0","id":1,"method":"getPublicKey","params":{"identityKey":"yes"}}"#,
+    )
+    .await;
+    assert_eq!(error_code(&response), INVALID_PARAMS);
+    assert_eq!(response["error"]["data"]["code"], "ERR_INVALID_PARAMETER");
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn wallet_errors_keep_their_description_and_body() {
+    let (bridge, mut events) = bridge();
+    let response = call(&bridge, get_public_key());
+    let error = json!({ "isError": true, "code": "ERR_USER_DENIED", "description": "No" });
+    answer(&bridge, &next_event(&mut events).await, 400, error.clone());
+
+    let response = response.await.unwrap().unwrap();
+    assert_eq!(error_code(&response), WALLET_ERROR);
+    assert_eq!(response["error"]["message"], "No");
+    assert_eq!(response["error"]["data"], error);
+}
+
+#[tokio::test]
+async fn calls_while_paused_get_their_error_code() {
+    let (bridge, mut events) = bridge();
+    bridge.set_paused(true);
+    let response = call(&bridge, get_public_key()).await.unwrap().unwrap();
+    assert_eq!(error_code(&response), ACCESS_PAUSED);
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn calls_while_locked_get_their_error_code() {
+    let (bridge, mut events) = bridge();
+    bridge.lock();
+    let response = call(&bridge, get_public_key()).await.unwrap().unwrap();
+    assert_eq!(error_code(&response), WALLET_LOCKED);
+    assert!(events.try_recv().is_err());
+}
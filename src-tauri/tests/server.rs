@@ -0,0 +1,111 @@
+//! Reading request bodies in the HTTP service.
+
+use std::{io, sync::Arc, time::Duration};
+
+use futures_util::stream;
+use hyper::{
+    body::Bytes,
+    header::{ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH},
+    Body, Request, Response, StatusCode,
+};
+use metanet_desktop::{
+    brc100::{WalletError, ERR_INVALID_BODY},
+    bridge::Bridge,
+    server::{self, MAX_BODY_SIZE},
+};
+use serde_json::{json, Value};
+use tokio::{sync::mpsc, time::timeout};
+
+/// A listening bridge whose emitted `http-request` payloads arrive on the
+/// returned channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+async fn serve(bridge: &Arc<Bridge>, req: Request<Body>) -> Response<Body> {
+    server::handle(bridge.clone(), req).await.unwrap()
+}
+
+async fn wallet_error(response: Response<Body>) -> WalletError {
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn bodies_over_the_limit_are_refused() {
+    let (bridge, mut events) = bridge();
+    let oversize = vec![b' '; MAX_BODY_SIZE + 1];
+
+    // Whether the length is declared up front or not.
+    let declared = Request::post("/createAction")
+        .header(CONTENT_LENGTH, oversize.len())
+        .body(Body::from(oversize.clone()))
+        .unwrap();
+    let parts: Vec<io::Result<Bytes>> = oversize
+        .chunks(64 * 1024)
+        .map(|part| Ok(Bytes::copy_from_slice(part)))
+        .collect();
+    let streamed = Request::post("/createAction")
+        .body(Body::wrap_stream(stream::iter(parts)))
+        .unwrap();
+
+    for req in [declared, streamed] {
+        let response = serve(&bridge, req).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(wallet_error(response).await.code, ERR_INVALID_BODY);
+    }
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn a_body_that_fails_to_arrive_is_refused() {
+    let (bridge, mut events) = bridge();
+    for path in ["/getHeight", server::RPC_PATH] {
+        let parts: Vec<io::Result<Bytes>> = vec![
+            Ok(Bytes::from_static(b"{")),
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")),
+        ];
+        let req = Request::post(path)
+            .body(Body::wrap_stream(stream::iter(parts)))
+            .unwrap();
+        let response = serve(&bridge, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(wallet_error(response).await.code, ERR_INVALID_BODY);
+    }
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn large_results_are_compressed_for_clients_that_accept_it() {
+    let (bridge, mut events) = bridge();
+    let req = Request::post("/listOutputs")
+        .header("origin", "https://app.example")
+        .header(ACCEPT_ENCODING, "gzip")
+        .body(Body::from(json!({ "basket": "default" }).to_string()))
+        .unwrap();
+    let response = tokio::spawn({
+        let bridge = bridge.clone();
+        async move { serve(&bridge, req).await }
+    });
+
+    let event = timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("no request was emitted")
+        .unwrap();
+    let result = json!({ "outputs": vec!["0".repeat(64); 100] }).to_string();
+    bridge.resolve(
+        &json!({ "request_id": event["request_id"], "status": 200, "body": result }).to_string(),
+    );
+    let response = response.await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_ENCODING], "gzip");
+}
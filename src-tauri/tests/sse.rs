@@ -0,0 +1,180 @@
+//! The `/events` stream: the state replayed to new subscribers, the
+//! `events` query and per-app events.
+
+use std::{sync::Arc, time::Duration};
+
+use hyper::{body::HttpBody, Body, Request, StatusCode};
+use metanet_desktop::{bridge::Bridge, sse};
+use serde_json::{json, Value};
+use tokio::time::timeout;
+
+fn bridge() -> Arc<Bridge> {
+    Arc::new(Bridge::new(Box::new(|_| Ok(()))))
+}
+
+fn publish(bridge: &Bridge, event: Value) {
+    bridge.publish(&event.to_string());
+}
+
+/// The frames of an event stream, as `(event, data)`.
+struct Frames {
+    body: Body,
+    buffer: String,
+}
+
+impl Frames {
+    fn open(bridge: &Arc<Bridge>, uri: &str, origin: Option<&str>) -> Self {
+        let mut req = Request::get(uri);
+        if let Some(origin) = origin {
+            req = req.header("Origin", origin);
+        }
+        let response = sse::stream(bridge.clone(), req.body(Body::empty()).unwrap());
+        assert_eq!(response.status(), StatusCode::OK);
+        Self {
+            body: response.into_body(),
+            buffer: String::new(),
+        }
+    }
+
+    async fn next(&mut self) -> (String, Value) {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let frame: String = self.buffer.drain(..end + 2).collect();
+                let mut event = None;
+                let mut data = None;
+                for line in frame.lines() {
+                    if let Some(name) = line.strip_prefix("event: ") {
+                        event = Some(name.to_string());
+                    } else if let Some(json) = line.strip_prefix("data: ") {
+                        data = Some(serde_json::from_str(json).unwrap());
+                    }
+                }
+                return (event.unwrap(), data.unwrap());
+            }
+            let chunk = timeout(Duration::from_secs(1), self.body.data())
+                .await
+                .expect("no event arrived")
+                .unwrap()
+                .unwrap();
+            self.buffer.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    }
+
+    async fn names(&mut self, count: usize) -> Vec<String> {
+        let mut names = Vec::new();
+        for _ in 0..count {
+            names.push(self.next().await.0);
+        }
+        names
+    }
+}
+
+#[tokio::test]
+async fn new_subscribers_get_the_latest_state_first() {
+    let bridge = bridge();
+    publish(
+        &bridge,
+        json!({ "event": "authenticated", "data": { "authenticated": true } }),
+    );
+    publish(
+        &bridge,
+        json!({ "event": "networkChanged", "data": { "network": "mainnet" } }),
+    );
+    publish(
+        &bridge,
+        json!({ "event": "heightChanged", "data": { "height": 1 } }),
+    );
+    publish(
+        &bridge,
+        json!({ "event": "heightChanged", "data": { "height": 2 } }),
+    );
+    publish(&bridge, json!({ "event": "locked", "data": {} }));
+
+    let mut frames = Frames::open(&bridge, "/events", None);
+    let mut replayed = vec![
+        frames.next().await,
+        frames.next().await,
+        frames.next().await,
+    ];
+    replayed.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        replayed,
+        [
+            ("heightChanged".to_string(), json!({ "height": 2 })),
+            ("locked".to_string(), json!({})),
+            (
+                "networkChanged".to_string(),
+                json!({ "network": "mainnet" })
+            ),
+        ]
+    );
+
+    publish(
+        &bridge,
+        json!({ "event": "heightChanged", "data": { "height": 3 } }),
+    );
+    assert_eq!(
+        frames.next().await,
+        ("heightChanged".to_string(), json!({ "height": 3 }))
+    );
+}
+
+#[tokio::test]
+async fn the_events_query_picks_the_events() {
+    for query in [
+        "events=networkChanged,heightChanged",
+        "events=networkChanged%2CheightChanged",
+        "other=1&events=networkChanged,,heightChanged",
+    ] {
+        let bridge = bridge();
+        let mut frames = Frames::open(&bridge, &format!("/events?{}", query), None);
+        publish(&bridge, json!({ "event": "authenticated", "data": {} }));
+        publish(&bridge, json!({ "event": "networkChanged", "data": {} }));
+        publish(&bridge, json!({ "event": "locked", "data": {} }));
+        publish(&bridge, json!({ "event": "heightChanged", "data": {} }));
+        assert_eq!(
+            frames.names(2).await,
+            ["networkChanged", "heightChanged"],
+            "{}",
+            query
+        );
+    }
+
+    // Names are decoded before they are compared.
+    let bridge = bridge();
+    let mut frames = Frames::open(&bridge, "/events?events=custom+event%21", None);
+    publish(&bridge, json!({ "event": "custom", "data": {} }));
+    publish(&bridge, json!({ "event": "custom event!", "data": {} }));
+    assert_eq!(frames.names(1).await, ["custom event!"]);
+}
+
+#[tokio::test]
+async fn app_events_only_reach_that_app() {
+    // Left out: the replayed authentication state.
+    const EVENTS: &str = "/events?events=permissionGranted,heightChanged";
+    let bridge = bridge();
+    let mut app = Frames::open(&bridge, EVENTS, Some("https://app.example"));
+    let mut other = Frames::open(&bridge, EVENTS, Some("https://other.example"));
+    let mut anonymous = Frames::open(&bridge, EVENTS, None);
+
+    publish(
+        &bridge,
+        json!({ "event": "permissionGranted", "data": {}, "origin": "app.example" }),
+    );
+    publish(&bridge, json!({ "event": "heightChanged", "data": {} }));
+
+    assert_eq!(app.names(2).await, ["permissionGranted", "heightChanged"]);
+    assert_eq!(other.names(1).await, ["heightChanged"]);
+    assert_eq!(anonymous.names(1).await, ["heightChanged"]);
+
+    // Per-app events are not replayed.
+    let mut later = Frames::open(&bridge, EVENTS, Some("https://app.example"));
+    assert_eq!(later.names(1).await, ["heightChanged"]);
+}
+
+#[tokio::test]
+async fn only_get_opens_a_stream() {
+    let req = Request::post(sse::EVENTS_PATH).body(Body::empty()).unwrap();
+    let response = sse::stream(bridge(), req);
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
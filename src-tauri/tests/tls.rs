@@ -0,0 +1,162 @@
+//! The local CA and its `localhost` certificate, checked the way a client
+//! trusting the CA would.
+
+use std::{
+    fs, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use metanet_desktop::tls::LocalCa;
+use rcgen::{CertificateParams, CidrSubnet, GeneralSubtree, KeyPair, NameConstraints};
+use tokio_rustls::{
+    rustls::{
+        self,
+        pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName},
+        ClientConfig, RootCertStore, ServerConfig,
+    },
+    TlsAcceptor, TlsConnector,
+};
+
+/// An empty directory for the CA.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metanet-{}-{}", name, std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// Connect to `server` as `name`, trusting only the CA in `dir`.
+async fn handshake(server: Arc<ServerConfig>, dir: &Path, name: &str) -> io::Result<()> {
+    let ca_pem = fs::read(dir.join("ca.pem")).unwrap();
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut ca_pem.as_slice()) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let client = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    let (client_io, server_io) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(TlsAcceptor::from(server).accept(server_io));
+    let name = ServerName::try_from(name.to_string()).unwrap();
+    TlsConnector::from(Arc::new(client))
+        .connect(name, client_io)
+        .await
+        .map(|_| ())
+}
+
+fn localhost_only() -> NameConstraints {
+    NameConstraints {
+        permitted_subtrees: vec![
+            GeneralSubtree::DnsName("localhost".to_string()),
+            GeneralSubtree::IpAddress(CidrSubnet::from_addr_prefix(
+                IpAddr::V4(Ipv4Addr::LOCALHOST),
+                32,
+            )),
+            GeneralSubtree::IpAddress(CidrSubnet::from_addr_prefix(
+                IpAddr::V6(Ipv6Addr::LOCALHOST),
+                128,
+            )),
+        ],
+        excluded_subtrees: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn clients_trusting_the_ca_accept_localhost() {
+    let dir = temp_dir("tls-localhost");
+    let ca = LocalCa::new(&dir);
+    assert!(ca.ensure().unwrap());
+    let config = ca.server_config().unwrap();
+    for name in ["localhost", "127.0.0.1", "::1"] {
+        handshake(config.clone(), &dir, name)
+            .await
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    }
+}
+
+#[test]
+fn the_ca_is_issued_once_and_kept_private() {
+    let dir = temp_dir("tls-issue");
+    let ca = LocalCa::new(&dir);
+    assert!(ca.ensure().unwrap());
+    let ca_pem = fs::read_to_string(ca.ca_cert_path()).unwrap();
+    let params = CertificateParams::from_ca_cert_pem(&ca_pem).unwrap();
+    assert_eq!(params.name_constraints, Some(localhost_only()));
+
+    // Nothing is due, so nothing changes.
+    assert!(!ca.ensure().unwrap());
+    assert_eq!(fs::read_to_string(ca.ca_cert_path()).unwrap(), ca_pem);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&dir), 0o700);
+        assert_eq!(mode(&dir.join("ca-key.pem")), 0o600);
+        assert_eq!(mode(&dir.join("localhost-key.pem")), 0o600);
+    }
+}
+
+#[tokio::test]
+async fn the_ca_cannot_vouch_for_other_sites() {
+    let dir = temp_dir("tls-constrained");
+    LocalCa::new(&dir).ensure().unwrap();
+
+    // Sign a certificate for another site with the CA's own key, as
+    // anyone holding a leaked key could.
+    let ca_pem = fs::read_to_string(dir.join("ca.pem")).unwrap();
+    let ca_key = KeyPair::from_pem(&fs::read_to_string(dir.join("ca-key.pem")).unwrap()).unwrap();
+    let ca = CertificateParams::from_ca_cert_pem(&ca_pem)
+        .unwrap()
+        .self_signed(&ca_key)
+        .unwrap();
+    let key = KeyPair::generate().unwrap();
+    let cert = CertificateParams::new(vec!["example.com".to_string()])
+        .unwrap()
+        .signed_by(&key, &ca, &ca_key)
+        .unwrap();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()));
+    let certs: Vec<CertificateDer<'static>> = vec![cert.der().clone()];
+    let config = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .unwrap();
+
+    let error = handshake(Arc::new(config), &dir, "example.com")
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("NameConstraintViolation"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn an_unconstrained_ca_is_replaced() {
+    let dir = temp_dir("tls-unconstrained");
+    fs::create_dir_all(&dir).unwrap();
+    let key = KeyPair::generate().unwrap();
+    let mut params = CertificateParams::default();
+    params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+    let old = params.self_signed(&key).unwrap();
+    fs::write(dir.join("ca.pem"), old.pem()).unwrap();
+    fs::write(dir.join("ca-key.pem"), key.serialize_pem()).unwrap();
+
+    let ca = LocalCa::new(&dir);
+    assert!(ca.ensure().unwrap());
+    let ca_pem = fs::read_to_string(ca.ca_cert_path()).unwrap();
+    assert_ne!(ca_pem, old.pem());
+    let params = CertificateParams::from_ca_cert_pem(&ca_pem).unwrap();
+    assert_eq!(params.name_constraints, Some(localhost_only()));
+}
@@ -0,0 +1,58 @@
+//! What the tray shows for a given bridge state.
+
+use metanet_desktop::tray::{badged_icon, pending_label, tooltip};
+
+const RED: [u8; 4] = [0xe5, 0x39, 0x35, 0xff];
+const BLUE: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * width + x) * 4) as usize;
+    rgba[offset..offset + 4].try_into().unwrap()
+}
+
+#[test]
+fn pending_requests_are_counted_in_words() {
+    assert_eq!(pending_label(0), "No pending requests");
+    assert_eq!(pending_label(1), "1 pending request");
+    assert_eq!(pending_label(12), "12 pending requests");
+}
+
+#[test]
+fn the_tooltip_mentions_only_what_is_going_on() {
+    assert_eq!(tooltip(0, false), "Metanet Desktop");
+    assert_eq!(tooltip(2, false), "Metanet Desktop — 2 pending requests");
+    assert_eq!(tooltip(0, true), "Metanet Desktop — app access paused");
+    assert_eq!(
+        tooltip(1, true),
+        "Metanet Desktop — app access paused — 1 pending request"
+    );
+}
+
+#[test]
+fn the_badge_sits_in_the_top_right_corner() {
+    let (width, height) = (32, 32);
+    let icon = BLUE.repeat((width * height) as usize);
+    let badged = badged_icon(&icon, width, height);
+    assert_eq!(badged.len(), icon.len());
+
+    // The badge's center, a radius of a quarter of the icon from the edges.
+    assert_eq!(pixel(&badged, width, 23, 8), RED);
+    assert_eq!(pixel(&badged, width, 31, 8), RED);
+    assert_eq!(pixel(&badged, width, 23, 0), RED);
+    // The rest of the icon is left alone.
+    for (x, y) in [(0, 0), (31, 0), (31, 31), (0, 31), (16, 16), (14, 8)] {
+        assert_eq!(pixel(&badged, width, x, y), BLUE, "({}, {})", x, y);
+    }
+    assert_eq!(pixel(&icon, width, 23, 8), BLUE);
+}
+
+#[test]
+fn odd_sizes_and_short_buffers_are_drawn_safely() {
+    for (width, height) in [(1, 1), (3, 7), (16, 9)] {
+        let icon = BLUE.repeat((width * height) as usize);
+        assert_eq!(badged_icon(&icon, width, height).len(), icon.len());
+    }
+    // A buffer shorter than the size claims is not written past.
+    let short = BLUE.repeat(10);
+    assert_eq!(badged_icon(&short, 32, 32).len(), short.len());
+}
@@ -0,0 +1,101 @@
+//! Serving the API on the Unix socket, to the wallet's own user only.
+#![cfg(target_os = "linux")]
+
+use std::{
+    fs,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use metanet_desktop::{bridge::Bridge, uds};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+    time::timeout,
+};
+
+const REQUEST: &[u8] =
+    b"POST /getHeight HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}";
+
+/// An empty directory to bind sockets in.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("metanet-{}-{}", name, std::process::id()));
+    _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A listening bridge whose emitted `http-request` payloads arrive on the returned
+/// channel.
+fn bridge() -> (Arc<Bridge>, mpsc::UnboundedReceiver<Value>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let emit = Box::new(move |json: String| {
+        tx.send(serde_json::from_str(&json).unwrap())
+            .map_err(|e| e.to_string())
+    });
+    let bridge = Arc::new(Bridge::new(emit));
+    bridge.publish(
+        &json!({ "event": "authenticated", "data": { "authenticated": true } }).to_string(),
+    );
+    (bridge, rx)
+}
+
+async fn connect(path: &Path) -> UnixStream {
+    for _ in 0..100 {
+        if let Ok(stream) = UnixStream::connect(path).await {
+            return stream;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("nothing listens on {}", path.display());
+}
+
+#[tokio::test]
+async fn requests_carry_the_callers_credentials() {
+    let dir = temp_dir("uds-own");
+    let path = dir.join("wallet").join("bridge.sock");
+    let (bridge, mut events) = bridge();
+    tokio::spawn({
+        let path = path.clone();
+        async move { uds::serve(bridge, &path).await }
+    });
+
+    let mut stream = connect(&path).await;
+    stream.write_all(REQUEST).await.unwrap();
+    let event = timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("the request never reached the bridge")
+        .unwrap();
+    assert_eq!(event["path"], "/getHeight");
+    let uid = fs::metadata(&dir).unwrap().uid();
+    assert_eq!(event["peer"]["uid"], uid);
+    assert_eq!(event["peer"]["pid"], std::process::id());
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(path.parent().unwrap()), 0o700);
+    assert_eq!(mode(&path), 0o600);
+}
+
+#[tokio::test]
+async fn other_users_are_turned_away() {
+    let dir = temp_dir("uds-other");
+    let path = dir.join("bridge.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+    let someone_else = fs::metadata(&dir).unwrap().uid().wrapping_add(1);
+    let (bridge, mut events) = bridge();
+    tokio::spawn(uds::serve_listener(bridge, listener, someone_else));
+
+    let mut stream = connect(&path).await;
+    // The connection may already be gone by the time the request is sent.
+    _ = stream.write_all(REQUEST).await;
+    let mut response = Vec::new();
+    _ = timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+        .await
+        .expect("the connection was left open");
+    assert!(response.is_empty());
+    assert!(events.try_recv().is_err());
+}
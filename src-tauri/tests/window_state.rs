@@ -0,0 +1,100 @@
+//! Fitting saved window geometry to the monitors present at startup.
+
+use metanet_desktop::window_state::{MonitorArea, Rect, WindowState, MIN_HEIGHT, MIN_WIDTH};
+
+fn monitor(name: &str, x: i32, y: i32, width: u32, height: u32) -> MonitorArea {
+    MonitorArea {
+        name: Some(name.to_string()),
+        work_area: Rect {
+            x,
+            y,
+            width,
+            height,
+        },
+    }
+}
+
+/// A 1920x1080 primary monitor with a 2560x1440 one to its right.
+fn monitors() -> Vec<MonitorArea> {
+    vec![
+        monitor("primary", 0, 0, 1920, 1080),
+        monitor("right", 1920, 0, 2560, 1440),
+    ]
+}
+
+fn state(x: i32, y: i32, width: u32, height: u32, monitor: &str) -> WindowState {
+    WindowState {
+        x,
+        y,
+        width,
+        height,
+        maximized: false,
+        monitor: Some(monitor.to_string()),
+    }
+}
+
+#[test]
+fn keeps_a_window_that_fits() {
+    let saved = state(2100, 100, 1200, 800, "right");
+    assert_eq!(saved.fit_to(&monitors()), saved);
+}
+
+#[test]
+fn moves_a_window_hanging_off_its_monitor_back_onto_it() {
+    let fitted = state(1500, 800, 1200, 800, "primary").fit_to(&monitors());
+    assert_eq!(fitted, state(720, 280, 1200, 800, "primary"));
+}
+
+#[test]
+fn centers_an_off_screen_window_on_the_primary_monitor() {
+    for (x, y) in [(-5000, 100), (100, 5000), (1900, -900)] {
+        let fitted = state(x, y, 1200, 800, "right").fit_to(&monitors());
+        assert_eq!(
+            fitted,
+            state(360, 140, 1200, 800, "primary"),
+            "{}, {}",
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn falls_back_when_its_monitor_was_removed() {
+    // Saved on the right monitor, which is gone now.
+    let saved = state(2100, 100, 1200, 800, "right");
+    let fitted = saved.fit_to(&monitors()[..1]);
+    assert_eq!(fitted, state(360, 140, 1200, 800, "primary"));
+
+    // Still mostly on a monitor that took the place of the old one.
+    let replaced = [
+        monitor("primary", 0, 0, 1920, 1080),
+        monitor("other", 1920, 0, 1920, 1080),
+    ];
+    let fitted = saved.fit_to(&replaced);
+    assert_eq!(fitted, state(2100, 100, 1200, 800, "other"));
+}
+
+#[test]
+fn shrinks_a_window_bigger_than_the_screen() {
+    let fitted = state(-100, -100, 3000, 2000, "primary").fit_to(&monitors());
+    assert_eq!(fitted, state(0, 0, 1920, 1080, "primary"));
+
+    let tiny = [monitor("tiny", 0, 0, 320, 240)];
+    let fitted = state(0, 0, 1200, 800, "tiny").fit_to(&tiny);
+    assert_eq!(fitted, state(0, 0, 320, 240, "tiny"));
+}
+
+#[test]
+fn grows_a_window_below_the_minimum_size() {
+    let fitted = state(100, 100, 10, 10, "primary").fit_to(&monitors());
+    assert_eq!((fitted.width, fitted.height), (MIN_WIDTH, MIN_HEIGHT));
+}
+
+#[test]
+fn keeps_maximized_and_leaves_geometry_without_monitors() {
+    let mut saved = state(-5000, -5000, 1200, 800, "right");
+    saved.maximized = true;
+    assert!(saved.fit_to(&monitors()).maximized);
+    assert_eq!(saved.fit_to(&[]), saved);
+}
@@ -0,0 +1,233 @@
+//! Hands focus back to the previously active window on a virtual X server.
+//!
+//! Each test starts its own `Xvfb` and plays the window manager itself:
+//! it advertises `_NET_ACTIVE_WINDOW`, sets it, and watches for activation
+//! requests on the root window. The tests need `Xvfb`, so they are ignored
+//! by default; run them with `cargo test --test x11_focus -- --ignored`.
+#![cfg(target_os = "linux")]
+
+use std::{
+    path::Path,
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use metanet_desktop::x11_focus::{ActiveWindow, FocusError};
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            AtomEnum, ChangeWindowAttributesAux, ConnectionExt, CreateWindowAux, EventMask,
+            PropMode, Window, WindowClass,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+    COPY_DEPTH_FROM_PARENT,
+};
+
+/// A running `Xvfb`, stopped on drop.
+struct XServer {
+    child: Child,
+    display: String,
+}
+
+impl XServer {
+    /// Start `Xvfb` on the first free display from `first`.
+    fn start(first: u32) -> Self {
+        for number in first..first + 50 {
+            let display = format!(":{}", number);
+            let child = Command::new("Xvfb")
+                .args([
+                    display.as_str(),
+                    "-nolisten",
+                    "tcp",
+                    "-screen",
+                    "0",
+                    "640x480x24",
+                ])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            let mut child = child.expect("Xvfb is not installed");
+            let socket = format!("/tmp/.X11-unix/X{}", number);
+            let deadline = Instant::now() + Duration::from_secs(10);
+            loop {
+                if let Ok(Some(_)) = child.try_wait() {
+                    // The display is taken; try the next one.
+                    break;
+                }
+                if Path::new(&socket).exists() && x11rb::connect(Some(&display)).is_ok() {
+                    return Self { child, display };
+                }
+                if Instant::now() > deadline {
+                    _ = child.kill();
+                    _ = child.wait();
+                    panic!("Xvfb did not start on {}", display);
+                }
+                thread::sleep(Duration::from_millis(20));
+            }
+        }
+        panic!("no free display for Xvfb");
+    }
+
+    fn connect(&self) -> (RustConnection, Window) {
+        let (conn, screen) = x11rb::connect(Some(&self.display)).unwrap();
+        let root = conn.setup().roots[screen].root;
+        (conn, root)
+    }
+}
+
+impl Drop for XServer {
+    fn drop(&mut self) {
+        _ = self.child.kill();
+        _ = self.child.wait();
+    }
+}
+
+fn atom(conn: &RustConnection, name: &str) -> u32 {
+    conn.intern_atom(false, name.as_bytes())
+        .unwrap()
+        .reply()
+        .unwrap()
+        .atom
+}
+
+/// A stand-in window manager supporting `_NET_ACTIVE_WINDOW`.
+fn fake_wm(server: &XServer) -> (RustConnection, Window) {
+    let (conn, root) = server.connect();
+    let supported = [atom(&conn, "_NET_ACTIVE_WINDOW")];
+    conn.change_property32(
+        PropMode::REPLACE,
+        root,
+        atom(&conn, "_NET_SUPPORTED"),
+        AtomEnum::ATOM,
+        &supported,
+    )
+    .unwrap();
+    conn.change_window_attributes(
+        root,
+        &ChangeWindowAttributesAux::new().event_mask(EventMask::SUBSTRUCTURE_REDIRECT),
+    )
+    .unwrap();
+    conn.sync().unwrap();
+    (conn, root)
+}
+
+/// A window owned by process `pid`.
+fn app_window(conn: &RustConnection, root: Window, pid: u32) -> Window {
+    let window = conn.generate_id().unwrap();
+    conn.create_window(
+        COPY_DEPTH_FROM_PARENT,
+        window,
+        root,
+        0,
+        0,
+        100,
+        100,
+        0,
+        WindowClass::INPUT_OUTPUT,
+        0,
+        &CreateWindowAux::new(),
+    )
+    .unwrap();
+    conn.change_property32(
+        PropMode::REPLACE,
+        window,
+        atom(conn, "_NET_WM_PID"),
+        AtomEnum::CARDINAL,
+        &[pid],
+    )
+    .unwrap();
+    conn.sync().unwrap();
+    window
+}
+
+fn set_active(conn: &RustConnection, root: Window, window: Window) {
+    conn.change_property32(
+        PropMode::REPLACE,
+        root,
+        atom(conn, "_NET_ACTIVE_WINDOW"),
+        AtomEnum::WINDOW,
+        &[window],
+    )
+    .unwrap();
+    conn.sync().unwrap();
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn unsupported_without_ewmh_window_manager() {
+    let server = XServer::start(140);
+    let result = ActiveWindow::connect(Some(&server.display));
+    assert!(matches!(result, Err(FocusError::Unsupported)));
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn reads_the_active_window_of_other_processes() {
+    let server = XServer::start(200);
+    let (wm, root) = fake_wm(&server);
+    let own_pid = std::process::id();
+    let other = app_window(&wm, root, own_pid + 1);
+    let own = app_window(&wm, root, own_pid);
+
+    let active = ActiveWindow::connect(Some(&server.display)).unwrap();
+    assert_eq!(active.get().unwrap(), None);
+
+    set_active(&wm, root, other);
+    assert_eq!(active.get().unwrap(), Some(other));
+    assert_eq!(active.get_other(own_pid).unwrap(), Some(other));
+
+    // The wallet's own window is not worth returning to.
+    set_active(&wm, root, own);
+    assert_eq!(active.get().unwrap(), Some(own));
+    assert_eq!(active.get_other(own_pid).unwrap(), None);
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn asks_the_window_manager_to_activate_the_previous_window() {
+    let server = XServer::start(260);
+    let (wm, root) = fake_wm(&server);
+    let previous = app_window(&wm, root, std::process::id() + 1);
+
+    let active = ActiveWindow::connect(Some(&server.display)).unwrap();
+    assert!(active.activate(previous).unwrap());
+
+    let net_active_window = atom(&wm, "_NET_ACTIVE_WINDOW");
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        match wm.poll_for_event().unwrap() {
+            Some(Event::ClientMessage(message)) => {
+                assert_eq!(message.type_, net_active_window);
+                assert_eq!(message.window, previous);
+                assert_eq!(message.format, 32);
+                // Sent as a pager on the user's behalf.
+                assert_eq!(message.data.as_data32()[0], 2);
+                break;
+            }
+            Some(_) => {}
+            None => {
+                assert!(Instant::now() < deadline, "no activation request");
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs Xvfb"]
+fn skips_windows_closed_in_the_meantime() {
+    let server = XServer::start(320);
+    let (wm, root) = fake_wm(&server);
+    let previous = app_window(&wm, root, std::process::id() + 1);
+    wm.destroy_window(previous).unwrap();
+    wm.sync().unwrap();
+
+    let active = ActiveWindow::connect(Some(&server.display)).unwrap();
+    assert!(!active.exists(previous).unwrap());
+    assert!(!active.activate(previous).unwrap());
+}
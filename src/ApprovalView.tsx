@@ -0,0 +1,70 @@
+import React, { useEffect, useState } from 'react'
+import { Box, Button, CircularProgress, Stack, Typography } from '@mui/material'
+import { ApprovalPrompt, answerApproval, getApprovalRequest, showWallet } from './tauriFunctions'
+import { reportActivity } from './presence'
+
+interface Props {
+  requestId: number
+}
+
+// The contents of an approval window: what one pending request asks for.
+// Approving or denying answers the wallet's prompt for it and closes the
+// window; reviewing brings the wallet forward to answer it there, after
+// which Rust closes the window once the request is settled.
+const ApprovalView: React.FC<Props> = ({ requestId }) => {
+  const [prompt, setPrompt] = useState<ApprovalPrompt | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  // Answering a prompt is the user at the wallet, which keeps it unlocked.
+  useEffect(() => reportActivity(), [])
+
+  useEffect(() => {
+    getApprovalRequest(requestId)
+      .then(setPrompt)
+      .catch((e) => setError(String(e)))
+  }, [requestId])
+
+  const answer = (approved: boolean) => {
+    answerApproval(approved).catch((e) => console.error('Failed to answer approval:', e))
+  }
+
+  const review = () => {
+    showWallet().catch((e) => console.error('Failed to show the wallet:', e))
+  }
+
+  if (error) {
+    return (
+      <Box p={3}>
+        <Typography color="error">{error}</Typography>
+      </Box>
+    )
+  }
+  if (!prompt) {
+    return (
+      <Box p={3} display="flex" justifyContent="center">
+        <CircularProgress />
+      </Box>
+    )
+  }
+  return (
+    <Box p={3}>
+      <Stack spacing={2}>
+        <Typography variant="h6">{prompt.summary}</Typography>
+        <Typography>{prompt.description}</Typography>
+        <Stack direction="row" spacing={2} justifyContent="flex-end">
+          <Button variant="text" onClick={review}>
+            Review in Wallet
+          </Button>
+          <Button variant="outlined" color="error" onClick={() => answer(false)}>
+            Deny
+          </Button>
+          <Button variant="contained" onClick={() => answer(true)}>
+            Approve
+          </Button>
+        </Stack>
+      </Stack>
+    </Box>
+  )
+}
+
+export default ApprovalView
@@ -0,0 +1,137 @@
+import { WalletInterface } from '@bsv/sdk'
+import { WalletPermissionsManager } from '@bsv/wallet-toolbox-client'
+import {
+  ApprovalAnswer,
+  isFocused,
+  onApprovalAnswered,
+  onFocusRelinquished,
+  onFocusRequested,
+  openApprovalWindow,
+  showWallet
+} from './tauriFunctions'
+
+// The wallet's permission prompts, as raised by the permissions manager.
+const PERMISSION_EVENTS = [
+  'onProtocolPermissionRequested',
+  'onBasketAccessRequested',
+  'onCertificateAccessRequested',
+  'onSpendingAuthorizationRequested',
+  'onGroupedPermissionRequested'
+] as const
+
+type PermissionEvent = typeof PERMISSION_EVENTS[number]
+
+// A wallet prompt asked in the approval window of the app request that
+// raised it.
+interface Prompt {
+  event: PermissionEvent
+  requestID: string
+  permissions?: any
+}
+
+// App requests being handled, by origin, so that a prompt can be tied to
+// the request that raised it.
+const handling = new Map<string, Set<number>>()
+// Prompts asked in approval windows, by app request ID.
+const prompts = new Map<number, Prompt>()
+// Focus requests left out because their prompt went to an approval window.
+let skippedFocusRequests = 0
+
+// Note that request `requestId` from `origin` is being handled, so prompts
+// it raises can be asked in its approval window. Returns a function to call
+// once it has been answered.
+export function trackRequest(requestId: number, origin: string): () => void {
+  const ids = handling.get(origin) ?? new Set<number>()
+  ids.add(requestId)
+  handling.set(origin, ids)
+  return () => {
+    ids.delete(requestId)
+    if (ids.size === 0 && handling.get(origin) === ids) {
+      handling.delete(origin)
+    }
+    prompts.delete(requestId)
+  }
+}
+
+// Focus handlers for the wallet UI: prompts asked in an approval window
+// leave the wallet window where it is.
+export const approvalFocusHandlers = {
+  onFocusRequested: async (): Promise<void> => {
+    if (prompts.size > 0) {
+      skippedFocusRequests++
+      return
+    }
+    return onFocusRequested()
+  },
+  onFocusRelinquished: async (): Promise<void> => {
+    if (skippedFocusRequests > 0) {
+      skippedFocusRequests--
+      return
+    }
+    return onFocusRelinquished()
+  }
+}
+
+// Ask the wallet's prompts for app requests in approval windows while the
+// wallet window is in the background, and settle them with the user's
+// answers. Returns a function that stops.
+export async function askInApprovalWindows(wallet: WalletInterface): Promise<() => void> {
+  if (!(wallet instanceof WalletPermissionsManager)) {
+    console.warn('No permissions manager to answer from approval windows; prompts stay in the wallet window')
+    return () => {}
+  }
+  const manager = wallet
+  const bindings = PERMISSION_EVENTS.map((event) => {
+    const id = manager.bindCallback(event, (request: any) => askInApprovalWindow(event, request))
+    return [event, id] as const
+  })
+  const stopListening = await onApprovalAnswered((answer) => {
+    settlePrompt(manager, answer).catch((e) => console.error('Failed to settle prompt:', e))
+  })
+  return () => {
+    bindings.forEach(([event, id]) => manager.unbindCallback(event, id))
+    stopListening()
+  }
+}
+
+function askInApprovalWindow(event: PermissionEvent, request: { requestID: string, originator: string, permissions?: any }) {
+  // Of the origin's requests, the oldest not yet waiting on a prompt.
+  const requestId = [...(handling.get(request.originator) ?? [])].find((id) => !prompts.has(id))
+  if (requestId === undefined) {
+    return
+  }
+  // Recorded before the wallet UI asks for focus, so that it is left out.
+  prompts.set(requestId, { event, requestID: request.requestID, permissions: request.permissions })
+  isFocused()
+    .then((focused) => {
+      if (focused) {
+        // The user is at the wallet, which asks as usual.
+        prompts.delete(requestId)
+        return
+      }
+      return openApprovalWindow(requestId)
+    })
+    .catch((e) => {
+      console.error('Failed to open approval window:', e)
+      showWallet().catch((e) => console.error('Failed to show the wallet:', e))
+    })
+}
+
+async function settlePrompt(manager: WalletPermissionsManager, { requestId, approved }: ApprovalAnswer) {
+  const prompt = prompts.get(requestId)
+  if (!prompt) {
+    return
+  }
+  prompts.delete(requestId)
+  if (prompt.event === 'onGroupedPermissionRequested') {
+    if (approved) {
+      await manager.grantGroupedPermission({ requestID: prompt.requestID, granted: prompt.permissions })
+    } else {
+      await manager.denyGroupedPermission(prompt.requestID)
+    }
+  } else if (approved) {
+    await manager.grantPermission({ requestID: prompt.requestID, ephemeral: true })
+  } else {
+    await manager.denyPermission(prompt.requestID)
+  }
+}
@@ -0,0 +1,32 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+
+// A metanet: or web+bsv: link, already validated by the Rust side.
+export type DeepLink =
+  | { kind: 'payment', to: string, satoshis: number, memo: string | null, origin: string | null }
+  | { kind: 'identity', origin: string, fields: string[] }
+  | { kind: 'connect', origin: string }
+
+// Call `handler` with every deep link opened, starting with any that
+// launched the app before we were listening. Returns a function that stops
+// listening.
+export async function listenForDeepLinks(handler: (link: DeepLink) => void): Promise<() => void> {
+  const unlisten = await listen<DeepLink>('deep-link', (event) => handler(event.payload))
+  const queued = await invoke<DeepLink[]>('take_deep_links')
+  queued.forEach(handler)
+  return unlisten
+}
+
+// A one-line description of what a link asks for.
+export function describeDeepLink(link: DeepLink): string {
+  switch (link.kind) {
+    case 'payment':
+      return `${link.origin ?? 'A link'} requests ${link.satoshis} satoshis to ${link.to}` +
+        (link.memo ? ` (${link.memo})` : '')
+    case 'identity':
+      return `${link.origin} requests your identity` +
+        (link.fields.length ? `: ${link.fields.join(', ')}` : '')
+    case 'connect':
+      return `${link.origin} wants to connect to your wallet`
+  }
+}
@@ -5,7 +5,9 @@ import 'react-toastify/dist/ReactToastify.css'
 import { UserInterface } from '@bsv/brc100-ui-react-components'
 import { onWalletReady } from './onWalletReady'
 import ErrorBoundary from './ErrorBoundary'
-import { tauriFunctions } from './tauriFunctions'
+import { tauriFunctions, approvalRequestId } from './tauriFunctions'
+import ApprovalView from './ApprovalView'
+import { approvalFocusHandlers } from './approvals'
 import packageJson from '../package.json'
 import { createTheme, ThemeProvider } from '@mui/material/styles'
 
@@ -21,7 +23,19 @@ const theme = createTheme({
 
 // Create the root and render:
 const rootElement = document.getElementById('root')
-if (rootElement) {
+// Approval windows show just the request they were opened for
+const requestId = approvalRequestId()
+if (rootElement && requestId !== null) {
+  createRoot(rootElement).render(
+    <React.StrictMode>
+      <ThemeProvider theme={theme}>
+        <ErrorBoundary>
+          <ApprovalView requestId={requestId} />
+        </ErrorBoundary>
+      </ThemeProvider>
+    </React.StrictMode>
+  )
+} else if (rootElement) {
   const root = createRoot(rootElement)
 
   root.render(
@@ -30,7 +44,7 @@ if (rootElement) {
         <ErrorBoundary>
           <UserInterface
             onWalletReady={onWalletReady}
-            nativeHandlers={tauriFunctions}
+            nativeHandlers={{ ...tauriFunctions, ...approvalFocusHandlers }}
             appVersion={packageJson.version}
             appName="Metanet Desktop"
           />
@@ -26,6 +26,11 @@ import {
   WERR_REVIEW_ACTIONS
 } from '@bsv/sdk';
 import { listen, emit } from '@tauri-apps/api/event'
+import { emitWalletEvent, watchHeight } from './walletEvents'
+import { listenForDeepLinks, describeDeepLink } from './deepLinks'
+import { reportActivity } from './presence'
+import { askInApprovalWindows, trackRequest } from './approvals'
+import { toast } from 'react-toastify'
 
 
 // Parse the origin header and turn it into a fqdn (e.g. projectbabbage.com:8080)
@@ -57,9 +62,50 @@ function parseOrigin(req, headers: Record<string, string>): string {
 }
 
 
+// Bodies longer than this are sent to Rust in parts, so large list results
+// never travel as one huge event payload.
+const RESPONSE_CHUNK_SIZE = 256 * 1024
+
+// Send a response back to Rust, splitting large bodies into
+// "ts-response-chunk" events that follow a "chunked" ts-response.
+async function emitResponse(response: { request_id: number, status: number, body: string }): Promise<void> {
+  const { request_id, status, body } = response
+  if (body.length <= RESPONSE_CHUNK_SIZE) {
+    await emit('ts-response', response)
+    return
+  }
+  await emit('ts-response', { request_id, status, body: '', chunked: true })
+  let offset = 0
+  while (offset < body.length) {
+    let end = Math.min(offset + RESPONSE_CHUNK_SIZE, body.length)
+    // Never split a surrogate pair across chunks.
+    const last = body.charCodeAt(end - 1)
+    if (end < body.length && last >= 0xd800 && last <= 0xdbff) {
+      end -= 1
+    }
+    await emit('ts-response-chunk', { request_id, data: body.slice(offset, end), done: end >= body.length })
+    offset = end
+  }
+}
+
 export const onWalletReady = async (wallet: WalletInterface): Promise<(() => void) | undefined> => {
-  return await listen('http-request', async (event) => {
+  wallet.getNetwork({})
+    .then(({ network }) => emitWalletEvent('networkChanged', { network }))
+    .catch((e) => console.error('getNetwork error:', e))
+  const stopWatchingHeight = watchHeight(wallet)
+  // Links opened from other apps are surfaced once the wallet is unlocked.
+  const stopListeningForDeepLinks = await listenForDeepLinks((link) => {
+    toast.info(describeDeepLink(link))
+  })
+  // Using the wallet keeps it from counting the user as idle.
+  const stopReportingActivity = reportActivity()
+  // Prompts raised by app requests are asked in approval windows while the
+  // wallet window is in the background.
+  const stopAskingInApprovalWindows = await askInApprovalWindows(wallet)
+
+  const unlisten = await listen('http-request', async (event) => {
     let response
+    let untrack: (() => void) | undefined
 
     try {
       const req = JSON.parse(event.payload as string)
@@ -71,6 +117,9 @@ export const onWalletReady = async (wallet: WalletInterface): Promise<(() => voi
       })
       req.headers = Object.fromEntries(req.headers)
       const origin = parseOrigin(req, req.headers)
+      if (origin) {
+        untrack = trackRequest(req.request_id, origin)
+      }
 
       switch (req.path) {
         // 1. createAction
@@ -211,6 +260,7 @@ export const onWalletReady = async (wallet: WalletInterface): Promise<(() => voi
               status: 200,
               body: JSON.stringify(result),
             }
+            emitWalletEvent('actionInternalized', { accepted: result.accepted }, origin)
           } catch (error) {
             if (typeof error === 'object' && error.constructor.name === 'WERR_REVIEW_ACTIONS') {
               const e = new WERR_REVIEW_ACTIONS(
@@ -792,9 +842,24 @@ export const onWalletReady = async (wallet: WalletInterface): Promise<(() => voi
       }
 
       // Emit the response back to Rust.
-      emit('ts-response', response)
+      await emitResponse(response)
     } catch (e) {
       console.error("Error handling http-request event:", e)
+    } finally {
+      untrack?.()
     }
   })
+
+  // The wallet only becomes ready once the user has authenticated. The
+  // bridge holds requests back until then, so this goes out only once
+  // requests are listened for.
+  emitWalletEvent('authenticated', { authenticated: true })
+
+  return () => {
+    stopWatchingHeight()
+    stopListeningForDeepLinks()
+    stopReportingActivity()
+    stopAskingInApprovalWindows()
+    unlisten()
+  }
 }
\ No newline at end of file
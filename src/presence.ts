@@ -0,0 +1,51 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+
+export type WindowState = 'focused' | 'blurred' | 'minimized' | 'restored'
+
+// Whether the user is at the wallet, as tracked by the Rust side.
+export interface Presence {
+  idle: boolean
+  idleSeconds: number
+  focused: boolean
+  minimized: boolean
+  // Whether use of other apps counts too; without it (Wayland without
+  // logind) only use of the wallet does
+  systemIdleKnown: boolean
+}
+
+// How often interaction with the page is reported, at most.
+const ACTIVITY_REPORT_INTERVAL_MS = 5000
+
+export async function getPresence(): Promise<Presence> {
+  return invoke<Presence>('get_presence')
+}
+
+// Call `handler` when the main window is focused, blurred, minimized or
+// restored. Returns a function that stops listening.
+export async function onWindowState(handler: (state: WindowState) => void): Promise<() => void> {
+  return listen<{ state: WindowState }>('window-state', (event) => handler(event.payload.state))
+}
+
+// Call `handler` when the user goes idle or comes back. Returns a function
+// that stops listening.
+export async function onPresenceChange(handler: (presence: Presence) => void): Promise<() => void> {
+  return listen<Presence>('user-presence', (event) => handler(event.payload))
+}
+
+// Report keyboard and pointer use in the page, which the Rust side cannot
+// see. Returns a function that stops reporting.
+export function reportActivity(): () => void {
+  let lastReport = 0
+  const report = () => {
+    const now = Date.now()
+    if (now - lastReport < ACTIVITY_REPORT_INTERVAL_MS) {
+      return
+    }
+    lastReport = now
+    invoke<void>('report_activity').catch((e) => console.error('report_activity error:', e))
+  }
+  const events = ['keydown', 'pointerdown', 'wheel'] as const
+  events.forEach((name) => window.addEventListener(name, report, { passive: true }))
+  return () => events.forEach((name) => window.removeEventListener(name, report))
+}
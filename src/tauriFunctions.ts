@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { save } from '@tauri-apps/plugin-dialog'
 
 // Tauri commands exposed as async calls
@@ -14,6 +15,115 @@ export async function onFocusRelinquished(): Promise<void> {
   return invoke<void>('relinquish_focus')
 }
 
+// Ask the user about the wallet prompt raised by one request in a small
+// window of its own; it closes once the request is answered or cancelled
+export async function openApprovalWindow(requestId: number): Promise<void> {
+  return invoke<void>('open_approval_window', { requestId })
+}
+
+// The request an approval window was opened for, or null in the main window
+export function approvalRequestId(): number | null {
+  const requestId = new URLSearchParams(window.location.search).get('approval')
+  return requestId === null ? null : Number(requestId)
+}
+
+export interface ApprovalPrompt {
+  requestId: number
+  origin: string | null
+  method: string
+  /** Who is asking, e.g. "Request from example.com". */
+  summary: string
+  /** What they ask the wallet to do. */
+  description: string
+}
+
+// What the approval window for a pending request asks the user about
+export async function getApprovalRequest(requestId: number): Promise<ApprovalPrompt> {
+  return invoke<ApprovalPrompt>('get_approval_request', { requestId })
+}
+
+// Bring the wallet window forward, e.g. to review a request in full
+export async function showWallet(): Promise<void> {
+  return invoke<void>('show_wallet')
+}
+
+// Answer the request of this approval window, which then closes. A denial
+// is answered to the app right away
+export async function answerApproval(approved: boolean): Promise<void> {
+  return invoke<void>('answer_approval', { approved })
+}
+
+export interface ApprovalAnswer {
+  requestId: number
+  approved: boolean
+}
+
+// Called in the main window when the user answers an approval window, or
+// closes it, which denies; returns a function that stops listening
+export async function onApprovalAnswered(handler: (answer: ApprovalAnswer) => void): Promise<() => void> {
+  return listen<ApprovalAnswer>('approval-answered', (event) => handler(event.payload))
+}
+
+export interface Settings {
+  /** Also serve the API over HTTPS on 127.0.0.1:3322. Applied on restart. */
+  https: boolean
+  /** Quit when the window is closed instead of keeping the bridge running in the tray. */
+  quitOnClose: boolean
+  /** Whether the close-to-tray notice was already shown. */
+  closeNoticeShown: boolean
+  /** Start minimized to the tray at login. Change it with setLaunchAtLogin; setSettings leaves it as it is. */
+  launchAtLogin: boolean
+  /** Lock the wallet after this many minutes without the user; 0 never locks. Off while the presence's systemIdleKnown is false. */
+  autoLockMinutes: number
+  /** Run each app's spending calls one at a time, in arrival order, while other apps' calls may run alongside. Applied on restart. */
+  orderPerOrigin: boolean
+  /** How many spending calls from all apps may be with the wallet at once. At 1, the default, orderPerOrigin changes nothing. Applied on restart. */
+  concurrentSpendingCalls: number
+  /** Seconds a spending call's answer is replayed to retries with the same Idempotency-Key; 0 disables. Applied on restart. */
+  idempotencyWindowSeconds: number
+}
+
+export async function getSettings(): Promise<Settings> {
+  return invoke<Settings>('get_settings')
+}
+
+export async function setSettings(settings: Settings): Promise<void> {
+  return invoke<void>('set_settings', { settings })
+}
+
+// Whether the app starts at login (Linux XDG autostart)
+export async function getLaunchAtLogin(): Promise<boolean> {
+  return invoke<boolean>('get_launch_at_login')
+}
+
+// Install or remove the autostart entry, resolving to the resulting state
+export async function setLaunchAtLogin(enabled: boolean): Promise<boolean> {
+  return invoke<boolean>('set_launch_at_login', { enabled })
+}
+
+// Lock the wallet now; requests that spend or use its keys are refused
+// until the user authenticates again
+export async function lockWallet(): Promise<void> {
+  return invoke<void>('lock_wallet')
+}
+
+export async function isLocked(): Promise<boolean> {
+  return invoke<boolean>('is_locked')
+}
+
+// Save the local HTTPS CA certificate so the user can choose to trust it
+export async function exportLocalCa(): Promise<boolean> {
+  const path = await save({
+    filters: [{ name: 'PEM Certificates', extensions: ['pem', 'crt'] }],
+    defaultPath: 'metanet-desktop-ca.pem'
+  })
+  if (!path) {
+    return false
+  }
+  await invoke<void>('export_local_ca', { path })
+  return true
+}
+
 // Export a bundle of all Tauri functions to pass to the UI components
 export const tauriFunctions = {
   isFocused,
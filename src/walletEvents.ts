@@ -0,0 +1,32 @@
+import { WalletInterface } from '@bsv/sdk'
+import { emit } from '@tauri-apps/api/event'
+
+// Announce a wallet state change to the Rust bridge, which relays it to
+// subscribed clients. Events tagged with an origin only reach that app.
+export function emitWalletEvent(event: string, data: unknown = {}, origin?: string): void {
+  emit('wallet-event', { event, data, origin }).catch((e) => {
+    console.error('Failed to emit wallet-event:', e)
+  })
+}
+
+const HEIGHT_POLL_INTERVAL_MS = 30_000
+
+// Poll the chain tip and announce every new block height. Returns a
+// function that stops polling.
+export function watchHeight(wallet: WalletInterface): () => void {
+  let lastHeight: number | undefined
+  const check = async () => {
+    try {
+      const { height } = await wallet.getHeight({})
+      if (height !== lastHeight) {
+        lastHeight = height
+        emitWalletEvent('heightChanged', { height })
+      }
+    } catch (e) {
+      console.error('getHeight error:', e)
+    }
+  }
+  check()
+  const timer = setInterval(check, HEIGHT_POLL_INTERVAL_MS)
+  return () => clearInterval(timer)
+}